@@ -3,11 +3,170 @@ use serde::Deserialize;
 #[derive(Deserialize, Debug)]
 pub struct AutoCorrectBatchRequest {
     pub from_folder: String,
-    pub to_folder: String
+    pub to_folder: String,
+    /// Name of a pipeline from the pipelines config. If set, the correction jobs run this pipeline instead of their single step.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pipeline: Option<String>,
+    #[serde(default)]
+    pub crop: CropOptions,
+    #[serde(default)]
+    pub color: MediaColorOptions,
+    #[serde(default)]
+    pub negatives: NegativeOptions,
+    #[serde(default)]
+    pub dust: DustOptions,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct AutoCorrectSingleRequest {
     pub from_path: String,
     pub to_folder: String
-}
\ No newline at end of file
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct CropOptions {
+    /// Pixels to trim inside the detected image area. Negative values keep some of the mount.
+    #[serde(default)]
+    pub margin: i32,
+    /// How different a pixel must be from the mount color to count as part of the image, from 0.0 to 1.0.
+    #[serde(default = "default_fuzz")]
+    pub fuzz: f64,
+    /// Largest skew in degrees that will be corrected.
+    #[serde(default = "default_max_angle")]
+    pub max_angle: f64,
+}
+fn default_fuzz() -> f64 { 0.3 }
+fn default_max_angle() -> f64 { 5.0 }
+impl Default for CropOptions {
+    fn default() -> Self {
+        CropOptions { margin: 0, fuzz: default_fuzz(), max_angle: default_max_angle() }
+    }
+}
+
+/// Color restoration settings for each media type. Media types without settings are copied unchanged.
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub struct MediaColorOptions {
+    pub slides: Option<ColorRestoreOptions>,
+    pub prints: Option<ColorRestoreOptions>,
+    pub negatives: Option<ColorRestoreOptions>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct ColorRestoreOptions {
+    /// Stretch each channel so its darkest and brightest `clip_fraction` of pixels become black and white.
+    #[serde(default = "default_true")]
+    pub stretch: bool,
+    #[serde(default = "default_clip_fraction")]
+    pub clip_fraction: f64,
+    #[serde(default)]
+    pub balance: WhiteBalance,
+    /// Strength from 0.0 to 1.0 of per-channel curves pulling each channel's midtones together, which undoes dye fading.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fade_recovery: Option<f64>,
+}
+fn default_true() -> bool { true }
+fn default_clip_fraction() -> f64 { 0.005 }
+impl Default for ColorRestoreOptions {
+    fn default() -> Self {
+        ColorRestoreOptions { stretch: true, clip_fraction: default_clip_fraction(), balance: WhiteBalance::None, fade_recovery: None }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WhiteBalance {
+    #[default]
+    None,
+    /// Scale channels so the average color is neutral gray.
+    GrayWorld,
+    /// Scale channels so the brightest pixels are neutral white.
+    WhitePatch,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct NegativeOptions {
+    #[serde(default)]
+    pub film: FilmType,
+    /// Area of unexposed film to sample the film base from. Without one, the brightest pixels of each channel are used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_sample: Option<Region>,
+    /// Fraction of the darkest and brightest pixels clipped when normalizing the inverted image.
+    #[serde(default = "default_clip_fraction")]
+    pub clip_fraction: f64,
+}
+impl Default for NegativeOptions {
+    fn default() -> Self {
+        NegativeOptions { film: FilmType::Color, base_sample: None, clip_fraction: default_clip_fraction() }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FilmType {
+    #[default]
+    Color,
+    BlackAndWhite,
+}
+
+/// A rectangle of an image, in pixels.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct DustOptions {
+    #[serde(default)]
+    pub method: DustMethod,
+    /// How aggressively specks are detected, from 0.0 (only obvious specks) to 1.0 (any small outlier).
+    #[serde(default = "default_dust_strength")]
+    pub strength: f64,
+    /// Radius in pixels of the neighbourhood that specks are compared against and filled from.
+    #[serde(default = "default_dust_radius")]
+    pub radius: u32,
+    /// If true, a PNG of the repaired pixels is written to a dust_masks folder for QC review.
+    #[serde(default = "default_true")]
+    pub write_mask: bool,
+}
+fn default_dust_strength() -> f64 { 0.5 }
+fn default_dust_radius() -> u32 { 2 }
+impl Default for DustOptions {
+    fn default() -> Self {
+        DustOptions { method: DustMethod::Median, strength: default_dust_strength(), radius: default_dust_radius(), write_mask: true }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DustMethod {
+    /// Flag pixels much brighter or darker than the median of their neighbourhood.
+    #[default]
+    Median,
+    /// Flag pixels that are dark in the infrared channel stored as a TIFF's fourth channel, which dust blocks but film dyes don't.
+    Infrared,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct SharpenOptions {
+    /// Blur radius of the unsharp mask.
+    #[serde(default = "default_sharpen_sigma")]
+    pub sigma: f32,
+    /// Smallest brightness difference that gets sharpened, which keeps film grain from being amplified.
+    #[serde(default = "default_sharpen_threshold")]
+    pub threshold: i32,
+}
+fn default_sharpen_sigma() -> f32 { 1.0 }
+fn default_sharpen_threshold() -> i32 { 2 }
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct JpegExportOptions {
+    #[serde(default = "default_jpeg_quality")]
+    pub quality: u8,
+    /// If set, images are scaled down so neither side is longer than this.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_dimension: Option<u32>,
+}
+fn default_jpeg_quality() -> u8 { 92 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 #[derive(Debug)]
 pub enum AutocorrectError {
-    PhotoError().
+    PhotoError(String),
 }
 impl std::error::Error for AutocorrectError {}
 impl std::fmt::Display for AutocorrectError {
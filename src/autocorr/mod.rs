@@ -1,3 +1,4 @@
 pub mod photos;
 pub mod error;
-pub mod auto_correct_request;
\ No newline at end of file
+pub mod auto_correct_request;
+pub mod pipeline;
\ No newline at end of file
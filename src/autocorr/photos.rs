@@ -1 +1,5 @@
-pub mod correct_slides;
\ No newline at end of file
+pub mod correct_slides;
+pub mod orientation;
+pub mod color_restore;
+pub mod invert_negatives;
+pub mod dust_removal;
\ No newline at end of file
@@ -0,0 +1,259 @@
+pub mod error;
+use std::path::{Path, PathBuf};
+use glob::glob;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use error::ColorRestoreError;
+use crate::{autocorr::auto_correct_request::{AutoCorrectBatchRequest, ColorRestoreOptions, MediaColorOptions, WhiteBalance}, qc::final_check::media_file::MediaFile, reporter::Reporter, utils::{image_io, types::media_types::{photo_media_data::ImageTags, MediaType}}};
+
+#[derive(Deserialize, Debug)]
+struct Data {
+    data: AutoCorrectBatchRequest
+}
+
+/// Per-channel RGB statistics, scaled from 0.0 to 1.0 regardless of bit depth.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct ColorStatistics {
+    pub mean: [f64; 3],
+    pub std_dev: [f64; 3],
+}
+
+#[derive(Serialize, Debug)]
+pub struct ColorRestoration {
+    pub file_name: String,
+    pub before: ColorStatistics,
+    pub after: ColorStatistics,
+}
+
+const LEVELS: usize = 65536;
+const MAX_LEVEL: f64 = 65535.0;
+
+
+/// Restores the colors of every media file in `from_folder` into `to_folder`, using the options for each file's media type.
+pub fn restore_colors(request_json: Value, reporter: &mut dyn Reporter) -> Result<Vec<ColorRestoration>, ColorRestoreError> {
+    let request = serde_json::from_value::<Data>(request_json).map_err(ColorRestoreError::DeserializeError)?.data;
+    validate_media_color_options(&request.color)?;
+    let to_folder = PathBuf::from(&request.to_folder);
+    std::fs::create_dir_all(&to_folder).map_err(|e| ColorRestoreError::CouldNotCreateFolder(to_folder.clone(), e))?;
+    if image_io::is_same_folder(Path::new(&request.from_folder), &to_folder) {
+        return Err(ColorRestoreError::InvalidRequest(format!("to_folder {} is the folder being read from", to_folder.to_string_lossy())))
+    }
+
+    let mut restorations = Vec::new();
+    let files = glob(&format!("{}\\*", request.from_folder)).map_err(ColorRestoreError::InvalidDirectory)?;
+    for entry in files {
+        let path = entry.map_err(ColorRestoreError::InvalidFile)?;
+        let Some(media_file) = MediaFile::from_path(&path).map_err(|e| ColorRestoreError::MediaFileParseError(path.clone(), e))? else {
+            continue;
+        };
+        let to_path = to_folder.join(&media_file.raw_file_name);
+        let options = match media_file.media_type {
+            MediaType::Slides(_) => request.color.slides,
+            MediaType::Prints(_) => request.color.prints,
+            MediaType::Negatives(_) => request.color.negatives,
+        };
+        let Some(options) = options else {
+            std::fs::copy(&path, &to_path).map_err(|e| ColorRestoreError::CouldNotCopy(path.clone(), e))?;
+            continue;
+        };
+
+        reporter.progress(&format!("Restoring colors of {}", media_file.raw_file_name));
+        let tags = ImageTags::from_path(&path).map_err(|e| ColorRestoreError::CouldNotReadTags(path.clone(), e))?;
+        let image = image::open(&path).map_err(|e| ColorRestoreError::ImageError(path.clone(), e))?;
+        let (restored, before, after) = restore_image(&image, &options);
+        image_io::save_image_with_tags(&restored, &to_path, 95, &tags).map_err(|e| ColorRestoreError::CouldNotSave(to_path.clone(), e))?;
+        restorations.push(ColorRestoration { file_name: media_file.raw_file_name, before, after });
+    }
+
+    Ok(restorations)
+}
+
+pub fn validate_media_color_options(media_options: &MediaColorOptions) -> Result<(), ColorRestoreError> {
+    for options in [media_options.slides, media_options.prints, media_options.negatives].iter().flatten() {
+        validate_color_options(options)?;
+    }
+    Ok(())
+}
+
+pub fn validate_color_options(options: &ColorRestoreOptions) -> Result<(), ColorRestoreError> {
+    if !(0.0..0.5).contains(&options.clip_fraction) {
+        return Err(ColorRestoreError::InvalidRequest(format!("clip fraction {} is not between 0 and 0.5", options.clip_fraction)))
+    }
+    if let Some(strength) = options.fade_recovery {
+        if !(0.0..=1.0).contains(&strength) {
+            return Err(ColorRestoreError::InvalidRequest(format!("fade recovery strength {strength} is not between 0 and 1")))
+        }
+    }
+    Ok(())
+}
+
+/// Builds a tone curve for each channel from its histogram and applies them, keeping the image's bit depth.
+/// Returns the restored image along with its statistics before and after.
+pub fn restore_image(image: &DynamicImage, options: &ColorRestoreOptions) -> (DynamicImage, ColorStatistics, ColorStatistics) {
+    let mut pixels = image.to_rgb16();
+    let histograms = channel_histograms(&pixels);
+    let mut curves: [Vec<f64>; 3] = std::array::from_fn(|_| (0..LEVELS).map(|level| level as f64 / MAX_LEVEL).collect());
+    let before = statistics(&histograms, &curves);
+
+    if options.stretch {
+        for channel in 0..3 {
+            let low = curves[channel][percentile_level(&histograms[channel], options.clip_fraction)];
+            let high = curves[channel][percentile_level(&histograms[channel], 1.0 - options.clip_fraction)];
+            if high > low {
+                apply(&mut curves[channel], |value| (value - low) / (high - low));
+            }
+        }
+    }
+
+    let gains = match options.balance {
+        WhiteBalance::None => None,
+        WhiteBalance::GrayWorld => {
+            let means = statistics(&histograms, &curves).mean;
+            Some(means.map(|mean| means.iter().sum::<f64>() / 3.0 / mean))
+        }
+        WhiteBalance::WhitePatch => {
+            Some(std::array::from_fn(|channel| 1.0 / curves[channel][percentile_level(&histograms[channel], 1.0 - options.clip_fraction)]))
+        }
+    };
+    if let Some(gains) = gains {
+        for channel in 0..3 {
+            if gains[channel].is_finite() {
+                apply(&mut curves[channel], |value| value * gains[channel]);
+            }
+        }
+    }
+
+    if let Some(strength) = options.fade_recovery {
+        // A gamma per channel that moves its median onto the average median neutralizes midtone casts
+        let medians: [f64; 3] = std::array::from_fn(|channel| curves[channel][percentile_level(&histograms[channel], 0.5)]);
+        let target = medians.iter().sum::<f64>() / 3.0;
+        for channel in 0..3 {
+            if medians[channel] > 0.0 && medians[channel] < 1.0 && target > 0.0 && target < 1.0 {
+                let exponent = 1.0 + strength * (target.ln() / medians[channel].ln() - 1.0);
+                apply(&mut curves[channel], |value| value.powf(exponent));
+            }
+        }
+    }
+
+    let after = statistics(&histograms, &curves);
+    let lookup_tables = curves.map(|curve| curve.iter().map(|value| (value * MAX_LEVEL).round() as u16).collect::<Vec<u16>>());
+    for pixel in pixels.pixels_mut() {
+        for channel in 0..3 {
+            pixel[channel] = lookup_tables[channel][usize::from(pixel[channel])];
+        }
+    }
+
+    let restored = match image_io::is_high_bit_depth(image.color()) {
+        true => DynamicImage::ImageRgb16(pixels),
+        false => DynamicImage::ImageRgb8(DynamicImage::ImageRgb16(pixels).to_rgb8()),
+    };
+    (restored, before, after)
+}
+
+
+pub fn channel_histograms(pixels: &image::ImageBuffer<image::Rgb<u16>, Vec<u16>>) -> [Vec<u64>; 3] {
+    let mut histograms: [Vec<u64>; 3] = std::array::from_fn(|_| vec![0; LEVELS]);
+    for pixel in pixels.pixels() {
+        for channel in 0..3 {
+            histograms[channel][usize::from(pixel[channel])] += 1;
+        }
+    }
+    histograms
+}
+
+/// The lowest level at or below which `fraction` of the channel's pixels fall.
+pub fn percentile_level(histogram: &[u64], fraction: f64) -> usize {
+    let total: u64 = histogram.iter().sum();
+    let target = (total as f64 * fraction).ceil() as u64;
+    let mut cumulative = 0;
+    for (level, count) in histogram.iter().enumerate() {
+        cumulative += count;
+        if cumulative >= target.max(1) {
+            return level;
+        }
+    }
+    LEVELS - 1
+}
+
+fn apply(curve: &mut [f64], function: impl Fn(f64) -> f64) {
+    for value in curve.iter_mut() {
+        *value = function(*value).clamp(0.0, 1.0);
+    }
+}
+
+/// Statistics the image would have after its channels are mapped through `curves`.
+fn statistics(histograms: &[Vec<u64>; 3], curves: &[Vec<f64>; 3]) -> ColorStatistics {
+    let mut mean = [0.0; 3];
+    let mut std_dev = [0.0; 3];
+    for channel in 0..3 {
+        let total = histograms[channel].iter().sum::<u64>().max(1) as f64;
+        let weighted = || histograms[channel].iter().zip(&curves[channel]).filter(|(count, _)| **count > 0);
+        mean[channel] = weighted().map(|(count, value)| *count as f64 * value).sum::<f64>() / total;
+        std_dev[channel] = (weighted().map(|(count, value)| *count as f64 * (value - mean[channel]).powi(2)).sum::<f64>() / total).sqrt();
+    }
+    ColorStatistics { mean, std_dev }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, Rgb, RgbImage};
+    use serde_json::json;
+    use crate::{autocorr::auto_correct_request::{ColorRestoreOptions, WhiteBalance}, reporter::SilentReporter, utils::test_files};
+    use super::{percentile_level, restore_colors, restore_image, validate_color_options, error::ColorRestoreError};
+
+    /// A faded scan with a red cast, whose channels only span part of their range.
+    fn faded_scan() -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(64, 64, |x, y| {
+            let level = ((x + y) / 4) as u8;
+            Rgb([150 + level, 100 + level, 80 + level])
+        }))
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_options() {
+        assert!(validate_color_options(&ColorRestoreOptions::default()).is_ok());
+        for options in [ColorRestoreOptions { clip_fraction: 0.5, ..ColorRestoreOptions::default() }, ColorRestoreOptions { fade_recovery: Some(1.5), ..ColorRestoreOptions::default() }] {
+            assert!(matches!(validate_color_options(&options), Err(ColorRestoreError::InvalidRequest(_))), "{options:?} was accepted");
+        }
+    }
+
+    #[test]
+    fn refuses_to_write_into_the_folder_being_read() {
+        let dir = test_files::temp_dir("color-restore-same-folder").to_string_lossy().into_owned();
+        let result = restore_colors(json!({ "data": { "from_folder": dir, "to_folder": dir } }), &mut SilentReporter);
+        assert!(matches!(result, Err(ColorRestoreError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn percentile_level_finds_the_level_below_a_fraction() {
+        let histogram = [0, 5, 0, 5, 10];
+        assert_eq!(percentile_level(&histogram, 0.0), 1);
+        assert_eq!(percentile_level(&histogram, 0.5), 3);
+        assert_eq!(percentile_level(&histogram, 1.0), 4);
+    }
+
+    #[test]
+    fn stretching_widens_each_channel() {
+        let (restored, before, after) = restore_image(&faded_scan(), &ColorRestoreOptions { clip_fraction: 0.0, ..ColorRestoreOptions::default() });
+        assert!(matches!(restored, DynamicImage::ImageRgb8(_)));
+        for channel in 0..3 {
+            assert!(after.std_dev[channel] > before.std_dev[channel] * 2.0, "channel {channel}: {before:?} to {after:?}");
+        }
+    }
+
+    #[test]
+    fn gray_world_removes_a_cast() {
+        let options = ColorRestoreOptions { stretch: false, balance: WhiteBalance::GrayWorld, ..ColorRestoreOptions::default() };
+        let (_, before, after) = restore_image(&faded_scan(), &options);
+        let spread = |mean: [f64; 3]| mean.iter().cloned().fold(f64::MIN, f64::max) - mean.iter().cloned().fold(f64::MAX, f64::min);
+        assert!(spread(before.mean) > 0.2);
+        assert!(spread(after.mean) < 0.01, "{after:?}");
+    }
+
+    #[test]
+    fn keeps_16_bit_scans_at_16_bits() {
+        let (restored, _, _) = restore_image(&DynamicImage::ImageRgb16(faded_scan().to_rgb16()), &ColorRestoreOptions::default());
+        assert!(matches!(restored, DynamicImage::ImageRgb16(_)));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,34 @@
+use glob::{GlobError, PatternError};
+use crate::{qc::final_check::media_file::error::MediaFileParseError, utils::{image_io::error::ImageIoError, types::media_types::photo_media_data::error::PhotoMediaDataError}};
+
+#[derive(Debug)]
+pub enum ColorRestoreError {
+    DeserializeError(serde_json::Error),
+    InvalidRequest(String),
+    InvalidDirectory(PatternError),
+    InvalidFile(GlobError),
+    MediaFileParseError(std::path::PathBuf, MediaFileParseError),
+    CouldNotCreateFolder(std::path::PathBuf, std::io::Error),
+    CouldNotCopy(std::path::PathBuf, std::io::Error),
+    ImageError(std::path::PathBuf, image::ImageError),
+    CouldNotReadTags(std::path::PathBuf, PhotoMediaDataError),
+    CouldNotSave(std::path::PathBuf, ImageIoError),
+}
+impl std::error::Error for ColorRestoreError {}
+impl std::fmt::Display for ColorRestoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter)
+    -> std::fmt::Result {
+        match self {
+            ColorRestoreError::DeserializeError(err) => write!(f, "could not deserialize request: {err}"),
+            ColorRestoreError::InvalidRequest(err) => write!(f, "invalid request: {err}"),
+            ColorRestoreError::InvalidDirectory(err) => write!(f, "invalid directory: {err}"),
+            ColorRestoreError::InvalidFile(err) => write!(f, "invalid file path: {err}"),
+            ColorRestoreError::MediaFileParseError(path, e) => write!(f, "error parsing {}: {e}", path.file_name().unwrap_or(std::ffi::OsStr::new("invalid file name")).to_string_lossy()),
+            ColorRestoreError::CouldNotCreateFolder(path, e) => write!(f, "could not create folder {}: {e}", path.to_string_lossy()),
+            ColorRestoreError::CouldNotCopy(path, e) => write!(f, "could not copy {}: {e}", path.to_string_lossy()),
+            ColorRestoreError::ImageError(path, e) => write!(f, "could not process {}: {e}", path.to_string_lossy()),
+            ColorRestoreError::CouldNotReadTags(path, e) => write!(f, "could not read tags of {}: {e}", path.to_string_lossy()),
+            ColorRestoreError::CouldNotSave(path, e) => write!(f, "could not save {}: {e}", path.to_string_lossy()),
+        }
+    }
+}
\ No newline at end of file
@@ -1,9 +1,292 @@
 pub mod error;
+use std::path::{Path, PathBuf};
+use glob::glob;
+use image::{imageops::FilterType, DynamicImage, GrayImage, Luma, Rgb, RgbImage};
+use imageproc::geometric_transformations::{rotate_about_center, Interpolation};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use error::SlidesAutocorrectError;
+use crate::{autocorr::auto_correct_request::{AutoCorrectBatchRequest, CropOptions}, reporter::Reporter, utils::{image_io, types::media_types::photo_media_data::ImageTags}};
 
-pub fn correct_image(file_path: std::path::PathBuf, to_folder: std::path::PathBuf) -> Result<(), SlidesAutocorrectError> {
-    if !file_path.is_file() {
-        
+#[derive(Deserialize, Debug)]
+struct Data {
+    data: AutoCorrectBatchRequest
+}
+
+/// Area of a deskewed scan that was kept, in pixels.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SlideCorrection {
+    pub file_name: String,
+    /// Clockwise rotation applied to straighten the slide, in degrees.
+    pub angle: f64,
+    pub crop: CropRect,
+}
+
+/// Largest dimension of the downscaled copy used to find the mount edges.
+const DETECTION_SIZE: u32 = 800;
+const ANGLE_STEP_DEGREES: f64 = 0.1;
+/// Rows and columns with less than this fraction of image pixels are treated as dust on the mount.
+const MIN_LINE_FRACTION: f64 = 0.02;
+/// Beyond this a scan is more likely on its side than skewed, and the search gets slow.
+const MAX_SKEW_DEGREES: f64 = 45.0;
+
+
+/// Deskews and crops every slide scan in the request's `from_folder` into its `to_folder`.
+pub fn correct_slides(request_json: Value, reporter: &mut dyn Reporter) -> Result<Vec<SlideCorrection>, SlidesAutocorrectError> {
+    let request = serde_json::from_value::<Data>(request_json).map_err(SlidesAutocorrectError::DeserializeError)?.data;
+    validate_crop_options(&request.crop)?;
+    let to_folder = PathBuf::from(&request.to_folder);
+    std::fs::create_dir_all(&to_folder).map_err(|e| SlidesAutocorrectError::CouldNotCreateFolder(to_folder.clone(), e))?;
+    if image_io::is_same_folder(Path::new(&request.from_folder), &to_folder) {
+        return Err(SlidesAutocorrectError::InvalidRequest(format!("to_folder {} is the folder being read from", to_folder.to_string_lossy())))
+    }
+
+    let mut corrections = Vec::new();
+    let files = glob(&format!("{}\\*", request.from_folder)).map_err(SlidesAutocorrectError::InvalidDirectory)?;
+    for entry in files {
+        let path = entry.map_err(SlidesAutocorrectError::InvalidFile)?;
+        if !image_io::is_image_file(&path) {
+            continue;
+        }
+        reporter.progress(&format!("Correcting {}", path.file_name().unwrap_or_default().to_string_lossy()));
+        corrections.push(correct_image(path, to_folder.clone(), &request.crop)?);
+    }
+
+    Ok(corrections)
+}
+
+pub fn validate_crop_options(options: &CropOptions) -> Result<(), SlidesAutocorrectError> {
+    if !(0.0..=1.0).contains(&options.fuzz) {
+        return Err(SlidesAutocorrectError::InvalidRequest(format!("fuzz {} is not between 0 and 1", options.fuzz)))
+    }
+    if !(0.0..=MAX_SKEW_DEGREES).contains(&options.max_angle) {
+        return Err(SlidesAutocorrectError::InvalidRequest(format!("max angle {} is not between 0 and {MAX_SKEW_DEGREES}", options.max_angle)))
     }
     Ok(())
+}
+
+/// Straightens a slide scan, crops it to the area inside the mount and writes it to `to_folder` under the same name.
+pub fn correct_image(file_path: PathBuf, to_folder: PathBuf, options: &CropOptions) -> Result<SlideCorrection, SlidesAutocorrectError> {
+    if !file_path.is_file() {
+        return Err(SlidesAutocorrectError::NotAFile(file_path))
+    }
+    let file_name = file_path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let tags = ImageTags::from_path(&file_path).map_err(|e| SlidesAutocorrectError::CouldNotReadTags(file_path.clone(), e))?;
+    let image = image::open(&file_path).map_err(|e| SlidesAutocorrectError::ImageError(file_path.clone(), e))?;
+
+    let (deskewed, theta) = deskew(&image, options);
+    let (corrected, crop) = crop_to_image_area(&deskewed, options)
+        .ok_or_else(|| SlidesAutocorrectError::NoImageArea(file_path.clone()))?;
+
+    let to_path = to_folder.join(&file_name);
+    image_io::save_image_with_tags(&corrected, &to_path, 95, &tags).map_err(|e| SlidesAutocorrectError::CouldNotSave(to_path.clone(), e))?;
+
+    Ok(SlideCorrection { file_name, angle: theta, crop })
+}
+
+/// Rotates a scan so the image area inside the mount is upright. Returns the rotated image and the clockwise rotation in degrees.
+pub fn deskew(image: &DynamicImage, options: &CropOptions) -> (DynamicImage, f64) {
+    let preview = image.resize(DETECTION_SIZE, DETECTION_SIZE, FilterType::Triangle).to_rgb8();
+    let mount_color = sample_mount_color(&preview);
+    let theta = find_skew(&foreground_mask(&preview, mount_color, options.fuzz), options.max_angle);
+    (rotate_image(image, theta, mount_color), f64::from(theta).to_degrees())
+}
+
+/// Crops an upright scan to the area inside the mount, trimming the margin from each side.
+/// Returns `None` if no image area is left.
+pub fn crop_to_image_area(image: &DynamicImage, options: &CropOptions) -> Option<(DynamicImage, CropRect)> {
+    let preview = image.resize(DETECTION_SIZE, DETECTION_SIZE, FilterType::Triangle).to_rgb8();
+    let mount_color = sample_mount_color(&preview);
+    let bounds = foreground_bounds(&foreground_mask(&preview, mount_color, options.fuzz))?;
+    let crop = scale_bounds(bounds, &preview, image, options.margin)?;
+    Some((image.crop_imm(crop.x, crop.y, crop.width, crop.height), crop))
+}
+
+
+/// Averages the corners of the scan, which are always mount.
+fn sample_mount_color(image: &RgbImage) -> [f64; 3] {
+    let patch = 4.min(image.width()).min(image.height());
+    let corners = [(0, 0), (image.width() - patch, 0), (0, image.height() - patch), (image.width() - patch, image.height() - patch)];
+    let mut sum = [0.0; 3];
+    for (corner_x, corner_y) in corners {
+        for y in corner_y..corner_y + patch {
+            for x in corner_x..corner_x + patch {
+                let pixel = image.get_pixel(x, y);
+                for channel in 0..3 {
+                    sum[channel] += f64::from(pixel[channel]);
+                }
+            }
+        }
+    }
+    let count = f64::from(4 * patch * patch);
+    sum.map(|channel| channel / count)
+}
+
+/// Marks pixels that differ from the mount color by more than `fuzz` as part of the image.
+fn foreground_mask(image: &RgbImage, mount_color: [f64; 3], fuzz: f64) -> GrayImage {
+    let max_distance = 255.0 * 3f64.sqrt();
+    GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        let pixel = image.get_pixel(x, y);
+        let distance = (0..3).map(|channel| (f64::from(pixel[channel]) - mount_color[channel]).powi(2)).sum::<f64>().sqrt();
+        Luma([if distance / max_distance > fuzz { 255 } else { 0 }])
+    })
+}
+
+/// Finds the clockwise rotation, in radians, which gives the image area the smallest upright bounding box.
+fn find_skew(mask: &GrayImage, max_angle: f64) -> f32 {
+    let (width, height) = mask.dimensions();
+    let (center_x, center_y) = (width as f64 / 2.0, height as f64 / 2.0);
+    let min_row_count = ((width as f64 * MIN_LINE_FRACTION) as u32).max(1);
+    let min_column_count = ((height as f64 * MIN_LINE_FRACTION) as u32).max(1);
+
+    // Only the outermost image pixels of each row and column can touch the bounding box
+    let mut edge_points: Vec<(f64, f64)> = Vec::new();
+    let mut push_edges = |points: Vec<(u32, u32)>, min_count: u32| {
+        if points.len() as u32 >= min_count {
+            for (x, y) in [points[0], points[points.len() - 1]] {
+                edge_points.push((x as f64 - center_x, y as f64 - center_y));
+            }
+        }
+    };
+    for y in 0..height {
+        push_edges((0..width).filter(|x| mask.get_pixel(*x, y)[0] > 0).map(|x| (x, y)).collect(), min_row_count);
+    }
+    for x in 0..width {
+        push_edges((0..height).filter(|y| mask.get_pixel(x, *y)[0] > 0).map(|y| (x, y)).collect(), min_column_count);
+    }
+    if edge_points.is_empty() {
+        return 0.0;
+    }
+
+    let bounding_area = |theta: f64| {
+        let (sin, cos) = theta.sin_cos();
+        let (mut min_x, mut max_x, mut min_y, mut max_y) = (f64::MAX, f64::MIN, f64::MAX, f64::MIN);
+        for (x, y) in &edge_points {
+            let (rotated_x, rotated_y) = (cos * x - sin * y, sin * x + cos * y);
+            min_x = min_x.min(rotated_x);
+            max_x = max_x.max(rotated_x);
+            min_y = min_y.min(rotated_y);
+            max_y = max_y.max(rotated_y);
+        }
+        (max_x - min_x) * (max_y - min_y)
+    };
+
+    let steps = (max_angle / ANGLE_STEP_DEGREES).round() as i32;
+    let mut best = (0.0, bounding_area(0.0));
+    for step in (-steps..=steps).filter(|step| *step != 0) {
+        let theta = (f64::from(step) * ANGLE_STEP_DEGREES).to_radians();
+        let area = bounding_area(theta);
+        if area < best.1 {
+            best = (theta, area);
+        }
+    }
+    best.0 as f32
+}
+
+/// Inclusive bounds (left, top, right, bottom) of the rows and columns containing image pixels.
+fn foreground_bounds(mask: &GrayImage) -> Option<(u32, u32, u32, u32)> {
+    let (width, height) = mask.dimensions();
+    let min_row_count = ((width as f64 * MIN_LINE_FRACTION) as u32).max(1);
+    let min_column_count = ((height as f64 * MIN_LINE_FRACTION) as u32).max(1);
+
+    let rows: Vec<u32> = (0..height).filter(|y| (0..width).filter(|x| mask.get_pixel(*x, *y)[0] > 0).count() as u32 >= min_row_count).collect();
+    let columns: Vec<u32> = (0..width).filter(|x| (0..height).filter(|y| mask.get_pixel(*x, *y)[0] > 0).count() as u32 >= min_column_count).collect();
+
+    Some((*columns.first()?, *rows.first()?, *columns.last()?, *rows.last()?))
+}
+
+/// Converts bounds found on the preview to the full size image, then trims `margin` pixels from each side.
+fn scale_bounds(bounds: (u32, u32, u32, u32), preview: &RgbImage, image: &DynamicImage, margin: i32) -> Option<CropRect> {
+    let scale_x = f64::from(image.width()) / f64::from(preview.width());
+    let scale_y = f64::from(image.height()) / f64::from(preview.height());
+    let (left, top, right, bottom) = bounds;
+
+    let margin = i64::from(margin);
+    let left = ((f64::from(left) * scale_x).floor() as i64 + margin).max(0);
+    let top = ((f64::from(top) * scale_y).floor() as i64 + margin).max(0);
+    let right = ((f64::from(right + 1) * scale_x).ceil() as i64 - margin).min(i64::from(image.width()));
+    let bottom = ((f64::from(bottom + 1) * scale_y).ceil() as i64 - margin).min(i64::from(image.height()));
+    if right <= left || bottom <= top {
+        return None;
+    }
+
+    Some(CropRect { x: left as u32, y: top as u32, width: (right - left) as u32, height: (bottom - top) as u32 })
+}
+
+/// Rotates clockwise by `theta` radians, keeping 16 bit scans at 16 bits and filling uncovered corners with the mount color.
+fn rotate_image(image: &DynamicImage, theta: f32, mount_color: [f64; 3]) -> DynamicImage {
+    if theta == 0.0 {
+        return image.clone();
+    }
+    match image_io::is_high_bit_depth(image.color()) {
+        true => {
+            let fill = Rgb(mount_color.map(|channel| (channel * 257.0).round() as u16));
+            DynamicImage::ImageRgb16(rotate_about_center(&image.to_rgb16(), theta, Interpolation::Bilinear, fill))
+        }
+        false => {
+            let fill = Rgb(mount_color.map(|channel| channel.round() as u8));
+            DynamicImage::ImageRgb8(rotate_about_center(&image.to_rgb8(), theta, Interpolation::Bilinear, fill))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, Rgb, RgbImage};
+    use serde_json::json;
+    use crate::{autocorr::auto_correct_request::CropOptions, reporter::SilentReporter, utils::test_files};
+    use super::{correct_image, correct_slides, crop_to_image_area, deskew, rotate_image, validate_crop_options, error::SlidesAutocorrectError};
+
+    /// A white mount with a dark image area at (50, 40) sized 100x70.
+    fn mounted_slide() -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(200, 150, |x, y| match (50..150).contains(&x) && (40..110).contains(&y) {
+            true => Rgb([60, 40, 30]),
+            false => Rgb([250, 250, 250]),
+        }))
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_options() {
+        assert!(validate_crop_options(&CropOptions::default()).is_ok());
+        for options in [CropOptions { fuzz: 1.5, ..CropOptions::default() }, CropOptions { max_angle: 90.0, ..CropOptions::default() }, CropOptions { max_angle: -1.0, ..CropOptions::default() }] {
+            assert!(matches!(validate_crop_options(&options), Err(SlidesAutocorrectError::InvalidRequest(_))), "{options:?} was accepted");
+        }
+    }
+
+    #[test]
+    fn refuses_to_write_into_the_folder_being_read() {
+        let dir = test_files::temp_dir("correct-slides-same-folder").to_string_lossy().into_owned();
+        let result = correct_slides(json!({ "data": { "from_folder": dir, "to_folder": dir } }), &mut SilentReporter);
+        assert!(matches!(result, Err(SlidesAutocorrectError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn crops_to_the_area_inside_the_mount() {
+        let (cropped, crop) = crop_to_image_area(&mounted_slide(), &CropOptions::default()).unwrap();
+        assert!(crop.x.abs_diff(50) <= 1 && crop.y.abs_diff(40) <= 1, "{crop:?}");
+        assert!(crop.width.abs_diff(100) <= 2 && crop.height.abs_diff(70) <= 2, "{crop:?}");
+        assert_eq!((cropped.width(), cropped.height()), (crop.width, crop.height));
+    }
+
+    #[test]
+    fn deskew_undoes_a_small_rotation() {
+        let skewed = rotate_image(&mounted_slide(), 3f32.to_radians(), [250.0, 250.0, 250.0]);
+        let (_, angle) = deskew(&skewed, &CropOptions::default());
+        assert!((angle + 3.0).abs() <= 0.3, "found an angle of {angle}");
+    }
+
+    #[test]
+    fn correct_image_needs_a_file() {
+        let dir = test_files::temp_dir("correct-slides-missing");
+        let result = correct_image(dir.join("SmithJ_Slides_001.tif"), dir.clone(), &CropOptions::default());
+        assert!(matches!(result, Err(SlidesAutocorrectError::NotAFile(_))));
+    }
 }
\ No newline at end of file
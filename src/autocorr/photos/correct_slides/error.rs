@@ -1,6 +1,19 @@
+use glob::{GlobError, PatternError};
+use crate::utils::{image_io::error::ImageIoError, types::media_types::photo_media_data::error::PhotoMediaDataError};
+
 #[derive(Debug)]
 pub enum SlidesAutocorrectError {
-    PhotoshopError(String)
+    PhotoshopError(String),
+    NotAFile(std::path::PathBuf),
+    DeserializeError(serde_json::Error),
+    InvalidRequest(String),
+    InvalidDirectory(PatternError),
+    InvalidFile(GlobError),
+    CouldNotCreateFolder(std::path::PathBuf, std::io::Error),
+    ImageError(std::path::PathBuf, image::ImageError),
+    CouldNotReadTags(std::path::PathBuf, PhotoMediaDataError),
+    CouldNotSave(std::path::PathBuf, ImageIoError),
+    NoImageArea(std::path::PathBuf),
 }
 impl std::error::Error for SlidesAutocorrectError {}
 impl std::fmt::Display for SlidesAutocorrectError {
@@ -8,6 +21,16 @@ impl std::fmt::Display for SlidesAutocorrectError {
     -> std::fmt::Result {
         match self {
             SlidesAutocorrectError::PhotoshopError(e) => write!(f, "photoshop error: {e}"),
+            SlidesAutocorrectError::NotAFile(path) => write!(f, "path \"{}\" is not a file", path.to_string_lossy()),
+            SlidesAutocorrectError::DeserializeError(err) => write!(f, "could not deserialize request: {err}"),
+            SlidesAutocorrectError::InvalidRequest(err) => write!(f, "invalid request: {err}"),
+            SlidesAutocorrectError::InvalidDirectory(err) => write!(f, "invalid directory: {err}"),
+            SlidesAutocorrectError::InvalidFile(err) => write!(f, "invalid file path: {err}"),
+            SlidesAutocorrectError::CouldNotCreateFolder(path, e) => write!(f, "could not create folder {}: {e}", path.to_string_lossy()),
+            SlidesAutocorrectError::ImageError(path, e) => write!(f, "could not process {}: {e}", path.to_string_lossy()),
+            SlidesAutocorrectError::CouldNotReadTags(path, e) => write!(f, "could not read tags of {}: {e}", path.to_string_lossy()),
+            SlidesAutocorrectError::CouldNotSave(path, e) => write!(f, "could not save {}: {e}", path.to_string_lossy()),
+            SlidesAutocorrectError::NoImageArea(path) => write!(f, "no image area could be found inside the mount of {}", path.to_string_lossy()),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,209 @@
+pub mod error;
+use std::path::{Path, PathBuf};
+use glob::glob;
+use image::{ColorType, DynamicImage, GrayImage, ImageBuffer, Luma, Rgb};
+use imageproc::{distance_transform::Norm, filter::median_filter, morphology::dilate};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use error::DustRemovalError;
+use crate::{autocorr::auto_correct_request::{AutoCorrectBatchRequest, DustMethod, DustOptions}, reporter::Reporter, utils::{image_io, types::media_types::photo_media_data::ImageTags}};
+
+#[derive(Deserialize, Debug)]
+struct Data {
+    data: AutoCorrectBatchRequest
+}
+
+#[derive(Serialize, Debug)]
+pub struct DustRemoval {
+    pub file_name: String,
+    pub repaired_pixels: u64,
+    /// Path of the written mask relative to `to_folder`, if one was written.
+    pub mask_file: Option<String>,
+}
+
+const MASK_FOLDER: &str = "dust_masks";
+
+
+/// Removes dust from every image in `from_folder`, writing repaired images to `to_folder` under the same name.
+pub fn remove_dust_from_folder(request_json: Value, reporter: &mut dyn Reporter) -> Result<Vec<DustRemoval>, DustRemovalError> {
+    let request = serde_json::from_value::<Data>(request_json).map_err(DustRemovalError::DeserializeError)?.data;
+    validate_dust_options(&request.dust)?;
+    let to_folder = PathBuf::from(&request.to_folder);
+    let mask_folder = to_folder.join(MASK_FOLDER);
+    let created_folder = if request.dust.write_mask { &mask_folder } else { &to_folder };
+    std::fs::create_dir_all(created_folder).map_err(|e| DustRemovalError::CouldNotCreateFolder(created_folder.clone(), e))?;
+    if image_io::is_same_folder(Path::new(&request.from_folder), &to_folder) {
+        return Err(DustRemovalError::InvalidRequest(format!("to_folder {} is the folder being read from", to_folder.to_string_lossy())))
+    }
+
+    let mut removals = Vec::new();
+    let files = glob(&format!("{}\\*", request.from_folder)).map_err(DustRemovalError::InvalidDirectory)?;
+    for entry in files {
+        let path = entry.map_err(DustRemovalError::InvalidFile)?;
+        if !image_io::is_image_file(&path) {
+            continue;
+        }
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        reporter.progress(&format!("Removing dust from {file_name}"));
+
+        let tags = ImageTags::from_path(&path).map_err(|e| DustRemovalError::CouldNotReadTags(path.clone(), e))?;
+
+        let image = image::open(&path).map_err(|e| DustRemovalError::ImageError(path.clone(), e))?;
+        let (repaired, mask) = remove_dust(&image, &request.dust).ok_or_else(|| DustRemovalError::NoInfraredChannel(path.clone()))?;
+        let to_path = to_folder.join(&file_name);
+        image_io::save_image_with_tags(&repaired, &to_path, 95, &tags).map_err(|e| DustRemovalError::CouldNotSave(to_path.clone(), e))?;
+
+        let mut mask_file = None;
+        if request.dust.write_mask {
+            let mask_name = format!("{}.png", path.file_stem().unwrap_or_default().to_string_lossy());
+            let mask_path = mask_folder.join(&mask_name);
+            mask.save(&mask_path).map_err(|e| DustRemovalError::ImageError(mask_path, e))?;
+            mask_file = Some(format!("{MASK_FOLDER}\\{mask_name}"));
+        }
+        let repaired_pixels = mask.pixels().filter(|pixel| pixel[0] > 0).count() as u64;
+        removals.push(DustRemoval { file_name, repaired_pixels, mask_file });
+    }
+
+    Ok(removals)
+}
+
+pub fn validate_dust_options(options: &DustOptions) -> Result<(), DustRemovalError> {
+    if !(0.0..=1.0).contains(&options.strength) {
+        return Err(DustRemovalError::InvalidRequest(format!("dust strength {} is not between 0 and 1", options.strength)))
+    }
+    if !(1..=16).contains(&options.radius) {
+        return Err(DustRemovalError::InvalidRequest(format!("dust radius {} is not between 1 and 16", options.radius)))
+    }
+    Ok(())
+}
+
+/// Finds specks and scratches and fills them with the median of their clean neighbours.
+/// Returns the repaired image and a mask of the repaired pixels, or `None` if the infrared method was
+/// requested for an image without a fourth channel.
+pub fn remove_dust(image: &DynamicImage, options: &DustOptions) -> Option<(DynamicImage, GrayImage)> {
+    let mask = match options.method {
+        DustMethod::Median => median_outlier_mask(image, options),
+        DustMethod::Infrared => infrared_mask(image, options)?,
+    };
+    // Specks have soft edges, so repair a pixel past what was detected
+    let mask = dilate(&mask, Norm::LInf, 1);
+
+    let mut pixels = image.to_rgb16();
+    let source = pixels.clone();
+    for (x, y, _) in mask.enumerate_pixels().filter(|(_, _, pixel)| pixel[0] > 0) {
+        if let Some(fill) = clean_neighbourhood_median(&source, &mask, x, y, options.radius) {
+            pixels.put_pixel(x, y, fill);
+        }
+    }
+
+    let is_gray = matches!(image.color(), ColorType::L8 | ColorType::L16 | ColorType::La8 | ColorType::La16);
+    let repaired = DynamicImage::ImageRgb16(pixels);
+    let repaired = match (is_gray, image_io::is_high_bit_depth(image.color())) {
+        (false, true) => repaired,
+        (false, false) => DynamicImage::ImageRgb8(repaired.to_rgb8()),
+        (true, true) => DynamicImage::ImageLuma16(repaired.to_luma16()),
+        (true, false) => DynamicImage::ImageLuma8(repaired.to_luma8()),
+    };
+    Some((repaired, mask))
+}
+
+
+/// Flags pixels whose brightness is far from the median of their neighbourhood.
+fn median_outlier_mask(image: &DynamicImage, options: &DustOptions) -> GrayImage {
+    let luma = image.to_luma8();
+    let median = median_filter(&luma, options.radius, options.radius);
+    let threshold = ((0.27 - 0.25 * options.strength) * 255.0) as i16;
+    GrayImage::from_fn(luma.width(), luma.height(), |x, y| {
+        let difference = (i16::from(luma.get_pixel(x, y)[0]) - i16::from(median.get_pixel(x, y)[0])).abs();
+        Luma([if difference > threshold { 255 } else { 0 }])
+    })
+}
+
+/// Flags pixels much darker than typical in the infrared channel.
+fn infrared_mask(image: &DynamicImage, options: &DustOptions) -> Option<GrayImage> {
+    if !image.color().has_alpha() {
+        return None;
+    }
+    let infrared = image.to_rgba16();
+    let mut levels: Vec<u16> = infrared.pixels().map(|pixel| pixel[3]).collect();
+    let middle = levels.len() / 2;
+    let median = f64::from(*levels.select_nth_unstable(middle).1);
+    let threshold = median * (0.5 + 0.4 * options.strength);
+    Some(GrayImage::from_fn(infrared.width(), infrared.height(), |x, y| {
+        Luma([if f64::from(infrared.get_pixel(x, y)[3]) < threshold { 255 } else { 0 }])
+    }))
+}
+
+/// Per-channel median of the unmasked pixels around a pixel, widening the search if they're all masked.
+fn clean_neighbourhood_median(pixels: &ImageBuffer<Rgb<u16>, Vec<u16>>, mask: &GrayImage, x: u32, y: u32, radius: u32) -> Option<Rgb<u16>> {
+    let (width, height) = pixels.dimensions();
+    let mut radius = radius;
+    for _ in 0..3 {
+        let mut channels: [Vec<u16>; 3] = Default::default();
+        for neighbour_y in y.saturating_sub(radius)..=(y + radius).min(height - 1) {
+            for neighbour_x in x.saturating_sub(radius)..=(x + radius).min(width - 1) {
+                if mask.get_pixel(neighbour_x, neighbour_y)[0] == 0 {
+                    let pixel = pixels.get_pixel(neighbour_x, neighbour_y);
+                    for channel in 0..3 {
+                        channels[channel].push(pixel[channel]);
+                    }
+                }
+            }
+        }
+        if !channels[0].is_empty() {
+            return Some(Rgb(channels.map(|mut values| {
+                let middle = values.len() / 2;
+                *values.select_nth_unstable(middle).1
+            })));
+        }
+        radius *= 2;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, GrayImage, Luma, Rgba, RgbaImage};
+    use serde_json::json;
+    use crate::{autocorr::auto_correct_request::{DustMethod, DustOptions}, reporter::SilentReporter, utils::test_files};
+    use super::{remove_dust, remove_dust_from_folder, validate_dust_options, error::DustRemovalError};
+
+    #[test]
+    fn validate_rejects_out_of_range_options() {
+        assert!(validate_dust_options(&DustOptions::default()).is_ok());
+        for options in [DustOptions { strength: 2.0, ..DustOptions::default() }, DustOptions { radius: 0, ..DustOptions::default() }, DustOptions { radius: 17, ..DustOptions::default() }] {
+            assert!(matches!(validate_dust_options(&options), Err(DustRemovalError::InvalidRequest(_))), "{options:?} was accepted");
+        }
+    }
+
+    #[test]
+    fn refuses_to_write_into_the_folder_being_read() {
+        let dir = test_files::temp_dir("dust-removal-same-folder").to_string_lossy().into_owned();
+        let result = remove_dust_from_folder(json!({ "data": { "from_folder": dir, "to_folder": dir, "dust": { "write_mask": false } } }), &mut SilentReporter);
+        assert!(matches!(result, Err(DustRemovalError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn median_method_fills_a_speck_from_its_neighbours() {
+        let mut image = GrayImage::from_pixel(32, 32, Luma([128]));
+        image.put_pixel(10, 10, Luma([0]));
+
+        let (repaired, mask) = remove_dust(&DynamicImage::ImageLuma8(image), &DustOptions::default()).unwrap();
+
+        assert_eq!(repaired.to_luma8().get_pixel(10, 10)[0], 128);
+        assert!(mask.get_pixel(10, 10)[0] > 0);
+        assert_eq!(mask.get_pixel(30, 30)[0], 0);
+    }
+
+    #[test]
+    fn infrared_method_needs_a_fourth_channel() {
+        let options = DustOptions { method: DustMethod::Infrared, ..DustOptions::default() };
+        assert!(remove_dust(&DynamicImage::ImageLuma8(GrayImage::new(8, 8)), &options).is_none());
+
+        let mut image = RgbaImage::from_pixel(32, 32, Rgba([100, 120, 140, 255]));
+        image.put_pixel(5, 5, Rgba([10, 10, 10, 20]));
+        let (repaired, mask) = remove_dust(&DynamicImage::ImageRgba8(image), &options).unwrap();
+        assert!(mask.get_pixel(5, 5)[0] > 0);
+        assert_eq!(repaired.to_rgb8().get_pixel(5, 5).0, [100, 120, 140]);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,32 @@
+use glob::{GlobError, PatternError};
+use crate::utils::{image_io::error::ImageIoError, types::media_types::photo_media_data::error::PhotoMediaDataError};
+
+#[derive(Debug)]
+pub enum DustRemovalError {
+    DeserializeError(serde_json::Error),
+    InvalidRequest(String),
+    InvalidDirectory(PatternError),
+    InvalidFile(GlobError),
+    CouldNotCreateFolder(std::path::PathBuf, std::io::Error),
+    ImageError(std::path::PathBuf, image::ImageError),
+    CouldNotReadTags(std::path::PathBuf, PhotoMediaDataError),
+    CouldNotSave(std::path::PathBuf, ImageIoError),
+    NoInfraredChannel(std::path::PathBuf),
+}
+impl std::error::Error for DustRemovalError {}
+impl std::fmt::Display for DustRemovalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter)
+    -> std::fmt::Result {
+        match self {
+            DustRemovalError::DeserializeError(err) => write!(f, "could not deserialize request: {err}"),
+            DustRemovalError::InvalidRequest(err) => write!(f, "invalid request: {err}"),
+            DustRemovalError::InvalidDirectory(err) => write!(f, "invalid directory: {err}"),
+            DustRemovalError::InvalidFile(err) => write!(f, "invalid file path: {err}"),
+            DustRemovalError::CouldNotCreateFolder(path, e) => write!(f, "could not create folder {}: {e}", path.to_string_lossy()),
+            DustRemovalError::ImageError(path, e) => write!(f, "could not process {}: {e}", path.to_string_lossy()),
+            DustRemovalError::CouldNotReadTags(path, e) => write!(f, "could not read tags of {}: {e}", path.to_string_lossy()),
+            DustRemovalError::CouldNotSave(path, e) => write!(f, "could not save {}: {e}", path.to_string_lossy()),
+            DustRemovalError::NoInfraredChannel(path) => write!(f, "{} has no infrared channel", path.to_string_lossy()),
+        }
+    }
+}
\ No newline at end of file
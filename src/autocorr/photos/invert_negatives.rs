@@ -0,0 +1,179 @@
+pub mod error;
+use std::path::{Path, PathBuf};
+use glob::glob;
+use image::{DynamicImage, ImageBuffer, Rgb};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use error::NegativeInversionError;
+use crate::{autocorr::auto_correct_request::{AutoCorrectBatchRequest, ColorRestoreOptions, FilmType, NegativeOptions}, qc::final_check::media_file::MediaFile, reporter::Reporter, utils::{image_io, types::media_types::{photo_media_data::ImageTags, MediaType}}};
+use super::color_restore;
+
+#[derive(Deserialize, Debug)]
+struct Data {
+    data: AutoCorrectBatchRequest
+}
+
+#[derive(Serialize, Debug)]
+pub struct NegativeInversion {
+    pub file_name: String,
+    /// Color of the unexposed film that was neutralized, per RGB channel from 0.0 to 1.0.
+    pub film_base: [f64; 3],
+}
+
+const MAX_LEVEL: f64 = 65535.0;
+
+
+/// Inverts every negative in `from_folder` into a positive in `to_folder` under the same name.
+/// Files of other media types are copied unchanged.
+pub fn invert_negatives(request_json: Value, reporter: &mut dyn Reporter) -> Result<Vec<NegativeInversion>, NegativeInversionError> {
+    let request = serde_json::from_value::<Data>(request_json).map_err(NegativeInversionError::DeserializeError)?.data;
+    validate_negative_options(&request.negatives)?;
+    let to_folder = PathBuf::from(&request.to_folder);
+    std::fs::create_dir_all(&to_folder).map_err(|e| NegativeInversionError::CouldNotCreateFolder(to_folder.clone(), e))?;
+    if image_io::is_same_folder(Path::new(&request.from_folder), &to_folder) {
+        return Err(NegativeInversionError::InvalidRequest(format!("to_folder {} is the folder being read from", to_folder.to_string_lossy())))
+    }
+
+    let mut inversions = Vec::new();
+    let files = glob(&format!("{}\\*", request.from_folder)).map_err(NegativeInversionError::InvalidDirectory)?;
+    for entry in files {
+        let path = entry.map_err(NegativeInversionError::InvalidFile)?;
+        let Some(media_file) = MediaFile::from_path(&path).map_err(|e| NegativeInversionError::MediaFileParseError(path.clone(), e))? else {
+            continue;
+        };
+        let to_path = to_folder.join(&media_file.raw_file_name);
+        let MediaType::Negatives(_) = media_file.media_type else {
+            std::fs::copy(&path, &to_path).map_err(|e| NegativeInversionError::CouldNotCopy(path.clone(), e))?;
+            continue;
+        };
+
+        reporter.progress(&format!("Inverting {}", media_file.raw_file_name));
+        let tags = ImageTags::from_path(&path).map_err(|e| NegativeInversionError::CouldNotReadTags(path.clone(), e))?;
+        let image = image::open(&path).map_err(|e| NegativeInversionError::ImageError(path.clone(), e))?;
+        let (positive, film_base) = invert_image(&image, &request.negatives).ok_or_else(|| NegativeInversionError::InvalidBaseSample(path.clone()))?;
+        image_io::save_image_with_tags(&positive, &to_path, 95, &tags).map_err(|e| NegativeInversionError::CouldNotSave(to_path.clone(), e))?;
+        inversions.push(NegativeInversion { file_name: media_file.raw_file_name, film_base });
+    }
+
+    Ok(inversions)
+}
+
+pub fn validate_negative_options(options: &NegativeOptions) -> Result<(), NegativeInversionError> {
+    if !(0.0..0.5).contains(&options.clip_fraction) {
+        return Err(NegativeInversionError::InvalidRequest(format!("clip fraction {} is not between 0 and 0.5", options.clip_fraction)))
+    }
+    Ok(())
+}
+
+/// Divides out the film base color, inverts and stretches the result to full range.
+/// Returns `None` if the base sample area doesn't fit inside the image.
+pub fn invert_image(image: &DynamicImage, options: &NegativeOptions) -> Option<(DynamicImage, [f64; 3])> {
+    let mut pixels = image.to_rgb16();
+    let film_base = sample_film_base(&pixels, options)?;
+
+    for pixel in pixels.pixels_mut() {
+        let neutralized: [f64; 3] = std::array::from_fn(|channel| (f64::from(pixel[channel]) / MAX_LEVEL / film_base[channel]).min(1.0));
+        let inverted = match options.film {
+            FilmType::Color => neutralized.map(|value| 1.0 - value),
+            FilmType::BlackAndWhite => [1.0 - (0.2126 * neutralized[0] + 0.7152 * neutralized[1] + 0.0722 * neutralized[2]); 3],
+        };
+        *pixel = Rgb(inverted.map(|value| (value * MAX_LEVEL).round() as u16));
+    }
+
+    let normalize_options = ColorRestoreOptions { clip_fraction: options.clip_fraction, ..ColorRestoreOptions::default() };
+    let (positive, _, _) = color_restore::restore_image(&DynamicImage::ImageRgb16(pixels), &normalize_options);
+    let positive = match (options.film, image_io::is_high_bit_depth(image.color())) {
+        (FilmType::Color, true) => positive,
+        (FilmType::Color, false) => DynamicImage::ImageRgb8(positive.to_rgb8()),
+        (FilmType::BlackAndWhite, true) => DynamicImage::ImageLuma16(positive.to_luma16()),
+        (FilmType::BlackAndWhite, false) => DynamicImage::ImageLuma8(positive.to_luma8()),
+    };
+    Some((positive, film_base))
+}
+
+
+/// Average color of the sample area, or the brightest pixels of each channel if there isn't one,
+/// since unexposed film lets the most light through.
+fn sample_film_base(pixels: &ImageBuffer<Rgb<u16>, Vec<u16>>, options: &NegativeOptions) -> Option<[f64; 3]> {
+    let film_base = match options.base_sample {
+        Some(region) => {
+            if region.width == 0 || region.height == 0
+                || u64::from(region.x) + u64::from(region.width) > u64::from(pixels.width())
+                || u64::from(region.y) + u64::from(region.height) > u64::from(pixels.height()) {
+                return None;
+            }
+            let mut sum = [0.0; 3];
+            for y in region.y..region.y + region.height {
+                for x in region.x..region.x + region.width {
+                    let pixel = pixels.get_pixel(x, y);
+                    for channel in 0..3 {
+                        sum[channel] += f64::from(pixel[channel]);
+                    }
+                }
+            }
+            let count = f64::from(region.width) * f64::from(region.height);
+            sum.map(|channel| channel / count / MAX_LEVEL)
+        }
+        None => {
+            let histograms = color_restore::channel_histograms(pixels);
+            std::array::from_fn(|channel| color_restore::percentile_level(&histograms[channel], 1.0 - options.clip_fraction) as f64 / MAX_LEVEL)
+        }
+    };
+    // An all black base would divide by zero, so treat it as clear film instead
+    Some(film_base.map(|channel| if channel > 0.0 { channel } else { 1.0 }))
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, GenericImageView, Rgb, RgbImage};
+    use serde_json::json;
+    use crate::{autocorr::auto_correct_request::{FilmType, NegativeOptions, Region}, reporter::SilentReporter, utils::test_files};
+    use super::{invert_image, invert_negatives, validate_negative_options, error::NegativeInversionError};
+
+    /// An orange film base on the left, where nothing was exposed, and dense film on the right.
+    fn color_negative() -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(64, 32, |x, _| match x < 32 {
+            true => Rgb([230, 150, 90]),
+            false => Rgb([40, 30, 20]),
+        }))
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_clip_fractions() {
+        assert!(validate_negative_options(&NegativeOptions::default()).is_ok());
+        let options = NegativeOptions { clip_fraction: 0.5, ..NegativeOptions::default() };
+        assert!(matches!(validate_negative_options(&options), Err(NegativeInversionError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn refuses_to_write_into_the_folder_being_read() {
+        let dir = test_files::temp_dir("invert-negatives-same-folder").to_string_lossy().into_owned();
+        let result = invert_negatives(json!({ "data": { "from_folder": dir, "to_folder": dir } }), &mut SilentReporter);
+        assert!(matches!(result, Err(NegativeInversionError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn rejects_a_base_sample_outside_the_image() {
+        let options = NegativeOptions { base_sample: Some(Region { x: 60, y: 0, width: 10, height: 10 }), ..NegativeOptions::default() };
+        assert!(invert_image(&color_negative(), &options).is_none());
+    }
+
+    #[test]
+    fn film_base_becomes_black_and_dense_film_becomes_white() {
+        let options = NegativeOptions { base_sample: Some(Region { x: 0, y: 0, width: 8, height: 8 }), ..NegativeOptions::default() };
+        let (positive, film_base) = invert_image(&color_negative(), &options).unwrap();
+        assert!((film_base[0] - 230.0 / 255.0).abs() < 0.01, "{film_base:?}");
+
+        let positive = positive.to_rgb8();
+        assert!(positive.get_pixel(4, 4).0.iter().all(|channel| *channel < 20), "{:?}", positive.get_pixel(4, 4));
+        assert!(positive.get_pixel(60, 4).0.iter().all(|channel| *channel > 235), "{:?}", positive.get_pixel(60, 4));
+    }
+
+    #[test]
+    fn black_and_white_film_comes_out_grayscale() {
+        let options = NegativeOptions { film: FilmType::BlackAndWhite, ..NegativeOptions::default() };
+        let (positive, _) = invert_image(&color_negative(), &options).unwrap();
+        assert!(matches!(positive, DynamicImage::ImageLuma8(_)));
+        assert_eq!(positive.dimensions(), (64, 32));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,36 @@
+use glob::{GlobError, PatternError};
+use crate::{qc::final_check::media_file::error::MediaFileParseError, utils::{image_io::error::ImageIoError, types::media_types::photo_media_data::error::PhotoMediaDataError}};
+
+#[derive(Debug)]
+pub enum NegativeInversionError {
+    DeserializeError(serde_json::Error),
+    InvalidRequest(String),
+    InvalidDirectory(PatternError),
+    InvalidFile(GlobError),
+    MediaFileParseError(std::path::PathBuf, MediaFileParseError),
+    CouldNotCreateFolder(std::path::PathBuf, std::io::Error),
+    CouldNotCopy(std::path::PathBuf, std::io::Error),
+    ImageError(std::path::PathBuf, image::ImageError),
+    CouldNotReadTags(std::path::PathBuf, PhotoMediaDataError),
+    CouldNotSave(std::path::PathBuf, ImageIoError),
+    InvalidBaseSample(std::path::PathBuf),
+}
+impl std::error::Error for NegativeInversionError {}
+impl std::fmt::Display for NegativeInversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter)
+    -> std::fmt::Result {
+        match self {
+            NegativeInversionError::DeserializeError(err) => write!(f, "could not deserialize request: {err}"),
+            NegativeInversionError::InvalidRequest(err) => write!(f, "invalid request: {err}"),
+            NegativeInversionError::InvalidDirectory(err) => write!(f, "invalid directory: {err}"),
+            NegativeInversionError::InvalidFile(err) => write!(f, "invalid file path: {err}"),
+            NegativeInversionError::MediaFileParseError(path, e) => write!(f, "error parsing {}: {e}", path.file_name().unwrap_or(std::ffi::OsStr::new("invalid file name")).to_string_lossy()),
+            NegativeInversionError::CouldNotCreateFolder(path, e) => write!(f, "could not create folder {}: {e}", path.to_string_lossy()),
+            NegativeInversionError::CouldNotCopy(path, e) => write!(f, "could not copy {}: {e}", path.to_string_lossy()),
+            NegativeInversionError::ImageError(path, e) => write!(f, "could not process {}: {e}", path.to_string_lossy()),
+            NegativeInversionError::CouldNotReadTags(path, e) => write!(f, "could not read tags of {}: {e}", path.to_string_lossy()),
+            NegativeInversionError::CouldNotSave(path, e) => write!(f, "could not save {}: {e}", path.to_string_lossy()),
+            NegativeInversionError::InvalidBaseSample(path) => write!(f, "film base sample area is outside of {}", path.to_string_lossy()),
+        }
+    }
+}
\ No newline at end of file
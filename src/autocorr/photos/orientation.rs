@@ -0,0 +1,108 @@
+use std::path::{Path, PathBuf};
+use glob::glob;
+use image::metadata::Orientation;
+use serde::Deserialize;
+use serde_json::Value;
+use crate::{qc::final_check::media_file::MediaFile, reporter::Reporter, utils::{image_io, types::media_types::{photo_media_data::ImageTags, MediaType}}};
+
+pub mod error;
+use error::OrientationError;
+
+
+#[derive(Deserialize, Debug)]
+struct Data {
+    data: BakeOrientationRequest
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BakeOrientationRequest {
+    pub to_folder: String,
+    #[serde(default = "default_jpeg_quality")]
+    pub jpeg_quality: u8,
+}
+fn default_jpeg_quality() -> u8 { 95 }
+
+
+/// Copies every media file in a folder to `to_folder`, rotating the pixels of files with an EXIF orientation
+/// other than 1 so they're upright and resetting their orientation flag.
+pub fn bake_orientation(dir: String, request_json: Value, reporter: &mut dyn Reporter) -> Result<u32, OrientationError> {
+    let request = serde_json::from_value::<Data>(request_json).map_err(OrientationError::DeserializeError)?.data;
+    let to_folder = PathBuf::from(&request.to_folder);
+    // Copying a file onto itself truncates it, so this has to be refused before anything is written
+    if image_io::is_same_folder(Path::new(&dir), &to_folder) {
+        return Err(OrientationError::InvalidRequest(format!("to_folder {} is the folder being read from", to_folder.to_string_lossy())))
+    }
+    std::fs::create_dir_all(&to_folder).map_err(|e| OrientationError::CouldNotCreateFolder(to_folder.clone(), e))?;
+
+    let mut rotated_count = 0;
+    let files = glob(&format!("{dir}\\*")).map_err(OrientationError::InvalidDirectory)?;
+    for entry in files {
+        let path = entry.map_err(OrientationError::InvalidFile)?;
+        let Some(media_file) = MediaFile::from_path(&path).map_err(|e| OrientationError::MediaFileParseError(path.clone(), e))? else {
+            continue;
+        };
+        let to_path = to_folder.join(&media_file.raw_file_name);
+        let orientation = match media_file.media_type {
+            MediaType::Prints(data) | MediaType::Slides(data) | MediaType::Negatives(data) => data.orientation,
+        };
+
+        if orientation == 1 {
+            std::fs::copy(&path, &to_path).map_err(|e| OrientationError::CouldNotCopy(path.clone(), e))?;
+            continue;
+        }
+        let exif_orientation = u8::try_from(orientation).ok().and_then(Orientation::from_exif)
+            .ok_or_else(|| OrientationError::InvalidOrientation(orientation, media_file.raw_file_name.clone()))?;
+        reporter.progress(&format!("Rotating {} (orientation {orientation})", media_file.raw_file_name));
+        bake_file(&path, &to_path, exif_orientation, request.jpeg_quality)?;
+        rotated_count += 1;
+    }
+
+    Ok(rotated_count)
+}
+
+
+fn bake_file(from_path: &Path, to_path: &Path, orientation: Orientation, jpeg_quality: u8) -> Result<(), OrientationError> {
+    // Re-encoding drops the original tags, so they're read before writing anything and carried over with an upright orientation
+    let mut tags = ImageTags::from_path(from_path).map_err(|e| OrientationError::CouldNotReadTags(from_path.to_path_buf(), e))?;
+    tags.orientation = Some(1);
+    let mut image = image::open(from_path).map_err(|e| OrientationError::ImageError(from_path.to_path_buf(), e))?;
+    image.apply_orientation(orientation);
+    image_io::save_image_with_tags(&image, to_path, jpeg_quality, &tags).map_err(|e| OrientationError::CouldNotSave(to_path.to_path_buf(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{metadata::Orientation, GenericImageView};
+    use serde_json::json;
+    use crate::{reporter::SilentReporter, utils::{test_files, types::media_types::photo_media_data::ImageTags}};
+    use super::{bake_file, bake_orientation, error::OrientationError};
+
+    #[test]
+    fn refuses_to_write_into_the_folder_being_read() {
+        let dir = test_files::temp_dir("orientation-same-folder");
+        let path = dir.join("SmithJ_Slides_001.jpg");
+        test_files::write_scan(&path, &test_files::gradient(8, 4, [255, 255, 255]));
+        let size = std::fs::metadata(&path).unwrap().len();
+
+        let dir_name = dir.to_string_lossy().into_owned();
+        let result = bake_orientation(dir_name.clone(), json!({ "data": { "to_folder": dir_name } }), &mut SilentReporter);
+
+        assert!(matches!(result, Err(OrientationError::InvalidRequest(_))));
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), size);
+    }
+
+    #[test]
+    fn rotates_pixels_upright_and_resets_the_flag() {
+        let dir = test_files::temp_dir("orientation-bake");
+        let from_path = dir.join("SmithJ_Slides_001.jpg");
+        let to_path = dir.join("upright.jpg");
+        test_files::write_scan_with_tags(&from_path, &test_files::gradient(8, 4, [255, 255, 255]), ImageTags { orientation: Some(6), ..ImageTags::default() });
+
+        bake_file(&from_path, &to_path, Orientation::Rotate90, 95).unwrap();
+
+        assert_eq!(image::open(&to_path).unwrap().dimensions(), (4, 8));
+        let tags = ImageTags::from_path(&to_path).unwrap();
+        assert_eq!(tags.orientation, Some(1));
+        assert_eq!(tags.x_resolution, Some((300, 1)));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,36 @@
+use glob::{GlobError, PatternError};
+use crate::{qc::final_check::media_file::error::MediaFileParseError, utils::{image_io::error::ImageIoError, types::media_types::photo_media_data::error::PhotoMediaDataError}};
+
+#[derive(Debug)]
+pub enum OrientationError {
+    DeserializeError(serde_json::Error),
+    InvalidRequest(String),
+    InvalidDirectory(PatternError),
+    InvalidFile(GlobError),
+    MediaFileParseError(std::path::PathBuf, MediaFileParseError),
+    InvalidOrientation(u16, String),
+    CouldNotCreateFolder(std::path::PathBuf, std::io::Error),
+    CouldNotCopy(std::path::PathBuf, std::io::Error),
+    ImageError(std::path::PathBuf, image::ImageError),
+    CouldNotReadTags(std::path::PathBuf, PhotoMediaDataError),
+    CouldNotSave(std::path::PathBuf, ImageIoError),
+}
+impl std::error::Error for OrientationError {}
+impl std::fmt::Display for OrientationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter)
+    -> std::fmt::Result {
+        match self {
+            OrientationError::DeserializeError(err) => write!(f, "could not deserialize request: {err}"),
+            OrientationError::InvalidRequest(err) => write!(f, "invalid request: {err}"),
+            OrientationError::InvalidDirectory(err) => write!(f, "invalid directory: {err}"),
+            OrientationError::InvalidFile(err) => write!(f, "invalid file path: {err}"),
+            OrientationError::MediaFileParseError(path, e) => write!(f, "error parsing {}: {e}", path.file_name().unwrap_or(std::ffi::OsStr::new("invalid file name")).to_string_lossy()),
+            OrientationError::InvalidOrientation(orientation, file_name) => write!(f, "file {file_name} has an invalid EXIF orientation of {orientation}"),
+            OrientationError::CouldNotCreateFolder(path, e) => write!(f, "could not create folder {}: {e}", path.to_string_lossy()),
+            OrientationError::CouldNotCopy(path, e) => write!(f, "could not copy {}: {e}", path.to_string_lossy()),
+            OrientationError::ImageError(path, e) => write!(f, "could not rotate {}: {e}", path.to_string_lossy()),
+            OrientationError::CouldNotReadTags(path, e) => write!(f, "could not read tags of {}: {e}", path.to_string_lossy()),
+            OrientationError::CouldNotSave(path, e) => write!(f, "could not save {}: {e}", path.to_string_lossy()),
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,279 @@
+pub mod error;
+use std::{collections::HashMap, path::{Path, PathBuf}};
+use glob::glob;
+use image::imageops::FilterType;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use error::PipelineError;
+use crate::{autocorr::{auto_correct_request::{AutoCorrectBatchRequest, ColorRestoreOptions, CropOptions, DustOptions, JpegExportOptions, NegativeOptions, SharpenOptions}, photos::{color_restore, correct_slides, dust_removal, invert_negatives}}, reporter::Reporter, utils::{image_io, types::{file_extension_type::FileExtensionType, media_types::photo_media_data::ImageTags}}};
+
+#[derive(Deserialize, Debug)]
+struct Data {
+    data: AutoCorrectBatchRequest
+}
+
+/// Named sequences of correction steps, read from a TOML file such as:
+/// ```toml
+/// [[pipelines.premium]]
+/// step = "deskew"
+///
+/// [[pipelines.premium]]
+/// step = "crop"
+/// margin = 10
+///
+/// [[pipelines.premium]]
+/// step = "export_jpeg"
+/// quality = 95
+/// ```
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct PipelinesConfig {
+    #[serde(default)]
+    pub pipelines: HashMap<String, Vec<PipelineStep>>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum PipelineStep {
+    Deskew(CropOptions),
+    Crop(CropOptions),
+    ColorBalance(ColorRestoreOptions),
+    InvertNegative(NegativeOptions),
+    RemoveDust(DustOptions),
+    Sharpen(SharpenOptions),
+    /// Writes the result as a JPEG instead of in the source format. Must be the last step.
+    ExportJpeg(JpegExportOptions),
+}
+impl std::fmt::Display for PipelineStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PipelineStep::Deskew(_) => write!(f, "deskew"),
+            PipelineStep::Crop(_) => write!(f, "crop"),
+            PipelineStep::ColorBalance(_) => write!(f, "color_balance"),
+            PipelineStep::InvertNegative(_) => write!(f, "invert_negative"),
+            PipelineStep::RemoveDust(_) => write!(f, "remove_dust"),
+            PipelineStep::Sharpen(_) => write!(f, "sharpen"),
+            PipelineStep::ExportJpeg(_) => write!(f, "export_jpeg"),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct StepReport {
+    pub step: String,
+    pub details: Value,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PipelineRun {
+    pub file_name: String,
+    pub output_file: String,
+    pub steps: Vec<StepReport>,
+}
+
+const OUTPUT_JPEG_QUALITY: u8 = 95;
+
+
+impl PipelinesConfig {
+    /// Reads the pipelines config at `path` and checks every step's parameters, so a bad pipeline is found before any job uses it.
+    pub fn load(path: &Path) -> Result<PipelinesConfig, PipelineError> {
+        let text = std::fs::read_to_string(path).map_err(|e| PipelineError::CouldNotReadConfig(path.to_path_buf(), e))?;
+        let config: PipelinesConfig = toml::from_str(&text).map_err(|e| PipelineError::InvalidConfig(path.to_path_buf(), e))?;
+        for (name, steps) in &config.pipelines {
+            validate_steps(name, steps)?;
+        }
+        Ok(config)
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Vec<PipelineStep>, PipelineError> {
+        self.pipelines.get(name).ok_or_else(|| PipelineError::UnknownPipeline(name.to_string()))
+    }
+}
+
+
+/// Runs the requested pipeline on every image in `from_folder`, writing results to `to_folder`.
+pub fn run_pipeline(request_json: Value, pipelines: &PipelinesConfig, reporter: &mut dyn Reporter) -> Result<Vec<PipelineRun>, PipelineError> {
+    let request = serde_json::from_value::<Data>(request_json).map_err(PipelineError::DeserializeError)?.data;
+    let pipeline_name = request.pipeline.as_ref().ok_or(PipelineError::NoPipelineRequested)?;
+    let steps = pipelines.get(pipeline_name)?;
+    let to_folder = PathBuf::from(&request.to_folder);
+    std::fs::create_dir_all(&to_folder).map_err(|e| PipelineError::CouldNotCreateFolder(to_folder.clone(), e))?;
+    if image_io::is_same_folder(Path::new(&request.from_folder), &to_folder) {
+        return Err(PipelineError::InvalidRequest(format!("to_folder {} is the folder being read from", to_folder.to_string_lossy())))
+    }
+
+    let mut runs = Vec::new();
+    let files = glob(&format!("{}\\*", request.from_folder)).map_err(PipelineError::InvalidDirectory)?;
+    for entry in files {
+        let path = entry.map_err(PipelineError::InvalidFile)?;
+        if !image_io::is_image_file(&path) {
+            continue;
+        }
+        runs.push(run_file(&path, &to_folder, steps, reporter)?);
+    }
+
+    Ok(runs)
+}
+
+
+fn validate_steps(name: &str, steps: &[PipelineStep]) -> Result<(), PipelineError> {
+    if steps.is_empty() {
+        return Err(PipelineError::InvalidStep(name.to_string(), 0, "pipeline has no steps".to_string()))
+    }
+    for (i, step) in steps.iter().enumerate() {
+        let invalid = |msg: String| PipelineError::InvalidStep(name.to_string(), i + 1, msg);
+        match step {
+            PipelineStep::Deskew(options) | PipelineStep::Crop(options) => correct_slides::validate_crop_options(options).map_err(|e| invalid(e.to_string()))?,
+            PipelineStep::ColorBalance(options) => color_restore::validate_color_options(options).map_err(|e| invalid(e.to_string()))?,
+            PipelineStep::InvertNegative(options) => invert_negatives::validate_negative_options(options).map_err(|e| invalid(e.to_string()))?,
+            PipelineStep::RemoveDust(options) => {
+                dust_removal::validate_dust_options(options).map_err(|e| invalid(e.to_string()))?;
+                if options.write_mask {
+                    return Err(invalid("dust masks aren't written by pipelines, so write_mask must be false".to_string()))
+                }
+            }
+            PipelineStep::Sharpen(options) => {
+                if !options.sigma.is_finite() || options.sigma <= 0.0 {
+                    return Err(invalid(format!("sigma {} must be a positive number", options.sigma)))
+                }
+            }
+            PipelineStep::ExportJpeg(options) => {
+                if !(1..=100).contains(&options.quality) {
+                    return Err(invalid(format!("quality {} is not between 1 and 100", options.quality)))
+                }
+                if options.max_dimension == Some(0) {
+                    return Err(invalid("max_dimension must be at least 1".to_string()))
+                }
+                if i != steps.len() - 1 {
+                    return Err(invalid("export_jpeg must be the last step".to_string()))
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_file(path: &Path, to_folder: &Path, steps: &[PipelineStep], reporter: &mut dyn Reporter) -> Result<PipelineRun, PipelineError> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let tags = ImageTags::from_path(path).map_err(|e| PipelineError::CouldNotReadTags(path.to_path_buf(), e))?;
+    let mut image = image::open(path).map_err(|e| PipelineError::ImageError(path.to_path_buf(), e))?;
+    let mut output_file = file_name.clone();
+    let mut jpeg_quality = OUTPUT_JPEG_QUALITY;
+    let mut reports = Vec::new();
+
+    for (i, step) in steps.iter().enumerate() {
+        reporter.progress(&format!("{file_name} [{}/{}] {step}", i + 1, steps.len()));
+        let failed = |msg: &str| PipelineError::StepFailed(step.to_string(), path.to_path_buf(), msg.to_string());
+        let details = match step {
+            PipelineStep::Deskew(options) => {
+                let (deskewed, angle) = correct_slides::deskew(&image, options);
+                image = deskewed;
+                json!({ "angle": angle })
+            }
+            PipelineStep::Crop(options) => {
+                let (cropped, crop) = correct_slides::crop_to_image_area(&image, options).ok_or_else(|| failed("no image area found inside the mount"))?;
+                image = cropped;
+                json!({ "crop": crop })
+            }
+            PipelineStep::ColorBalance(options) => {
+                let (restored, before, after) = color_restore::restore_image(&image, options);
+                image = restored;
+                json!({ "before": before, "after": after })
+            }
+            PipelineStep::InvertNegative(options) => {
+                let (positive, film_base) = invert_negatives::invert_image(&image, options).ok_or_else(|| failed("film base sample area is outside of the image"))?;
+                image = positive;
+                json!({ "film_base": film_base })
+            }
+            PipelineStep::RemoveDust(options) => {
+                let (repaired, mask) = dust_removal::remove_dust(&image, options).ok_or_else(|| failed("no infrared channel"))?;
+                image = repaired;
+                json!({ "repaired_pixels": mask.pixels().filter(|pixel| pixel[0] > 0).count() })
+            }
+            PipelineStep::Sharpen(options) => {
+                image = image.unsharpen(options.sigma, options.threshold);
+                json!({})
+            }
+            PipelineStep::ExportJpeg(options) => {
+                if let Some(max_dimension) = options.max_dimension {
+                    if image.width() > max_dimension || image.height() > max_dimension {
+                        image = image.resize(max_dimension, max_dimension, FilterType::Lanczos3);
+                    }
+                }
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                output_file = format!("{stem}.{}", FileExtensionType::Jpeg);
+                jpeg_quality = options.quality;
+                json!({ "width": image.width(), "height": image.height() })
+            }
+        };
+        reports.push(StepReport { step: step.to_string(), details });
+    }
+
+    let to_path = to_folder.join(&output_file);
+    image_io::save_image_with_tags(&image, &to_path, jpeg_quality, &tags).map_err(|e| PipelineError::CouldNotSave(to_path.clone(), e))?;
+
+    Ok(PipelineRun { file_name, output_file, steps: reports })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use serde_json::json;
+    use crate::{reporter::SilentReporter, utils::test_files};
+    use super::{error::PipelineError, run_file, run_pipeline, PipelinesConfig};
+
+    fn write_config(name: &str, toml: &str) -> PathBuf {
+        let path = test_files::temp_dir(name).join("pipelines.toml");
+        std::fs::write(&path, toml).unwrap();
+        path
+    }
+
+    #[test]
+    fn loads_the_example_config() {
+        let config = PipelinesConfig::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("pipelines.toml")).unwrap();
+        assert!(config.get("premium").is_ok());
+        assert!(matches!(config.get("missing"), Err(PipelineError::UnknownPipeline(_))));
+    }
+
+    #[test]
+    fn rejects_invalid_steps_when_loading() {
+        let invalid = [
+            ("empty", "[pipelines]\nbasic = []\n"),
+            ("export-not-last", "[[pipelines.basic]]\nstep = \"export_jpeg\"\n\n[[pipelines.basic]]\nstep = \"sharpen\"\n"),
+            ("dust-mask", "[[pipelines.basic]]\nstep = \"remove_dust\"\n"),
+            ("zero-dimension", "[[pipelines.basic]]\nstep = \"export_jpeg\"\nmax_dimension = 0\n"),
+            ("zero-quality", "[[pipelines.basic]]\nstep = \"export_jpeg\"\nquality = 0\n"),
+            ("zero-sigma", "[[pipelines.basic]]\nstep = \"sharpen\"\nsigma = 0.0\n"),
+        ];
+        for (name, toml) in invalid {
+            let result = PipelinesConfig::load(&write_config(&format!("pipeline-{name}"), toml));
+            assert!(matches!(result, Err(PipelineError::InvalidStep(..))), "{name} was accepted");
+        }
+    }
+
+    #[test]
+    fn run_pipeline_needs_a_pipeline_and_a_separate_folder() {
+        let config = PipelinesConfig::load(&write_config("pipeline-run", "[[pipelines.basic]]\nstep = \"sharpen\"\n")).unwrap();
+        let dir = test_files::temp_dir("pipeline-run-folders").to_string_lossy().into_owned();
+
+        let result = run_pipeline(json!({ "data": { "from_folder": dir, "to_folder": dir } }), &config, &mut SilentReporter);
+        assert!(matches!(result, Err(PipelineError::NoPipelineRequested)));
+        let result = run_pipeline(json!({ "data": { "from_folder": dir, "to_folder": dir, "pipeline": "basic" } }), &config, &mut SilentReporter);
+        assert!(matches!(result, Err(PipelineError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn runs_every_step_and_exports_a_jpeg() {
+        let config = PipelinesConfig::load(&write_config("pipeline-steps",
+            "[[pipelines.basic]]\nstep = \"sharpen\"\n\n[[pipelines.basic]]\nstep = \"export_jpeg\"\nmax_dimension = 32\n")).unwrap();
+        let dir = test_files::temp_dir("pipeline-steps-files");
+        let path = dir.join("SmithJ_Slides_001.tif");
+        test_files::write_scan(&path, &test_files::gradient(64, 48, [255, 200, 150]));
+
+        let run = run_file(&path, &dir, config.get("basic").unwrap(), &mut SilentReporter).unwrap();
+
+        assert_eq!(run.output_file, "SmithJ_Slides_001.jpg");
+        assert_eq!(run.steps.iter().map(|step| step.step.as_str()).collect::<Vec<_>>(), ["sharpen", "export_jpeg"]);
+        let output = image::open(dir.join(&run.output_file)).unwrap();
+        assert_eq!((output.width(), output.height()), (32, 24));
+    }
+}
\ No newline at end of file
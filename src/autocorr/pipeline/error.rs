@@ -0,0 +1,42 @@
+use glob::{GlobError, PatternError};
+use crate::utils::{image_io::error::ImageIoError, types::media_types::photo_media_data::error::PhotoMediaDataError};
+
+#[derive(Debug)]
+pub enum PipelineError {
+    DeserializeError(serde_json::Error),
+    CouldNotReadConfig(std::path::PathBuf, std::io::Error),
+    InvalidConfig(std::path::PathBuf, toml::de::Error),
+    InvalidRequest(String),
+    NoPipelineRequested,
+    UnknownPipeline(String),
+    InvalidStep(String, usize, String),
+    InvalidDirectory(PatternError),
+    InvalidFile(GlobError),
+    CouldNotCreateFolder(std::path::PathBuf, std::io::Error),
+    ImageError(std::path::PathBuf, image::ImageError),
+    CouldNotReadTags(std::path::PathBuf, PhotoMediaDataError),
+    CouldNotSave(std::path::PathBuf, ImageIoError),
+    StepFailed(String, std::path::PathBuf, String),
+}
+impl std::error::Error for PipelineError {}
+impl std::fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter)
+    -> std::fmt::Result {
+        match self {
+            PipelineError::DeserializeError(err) => write!(f, "could not deserialize request: {err}"),
+            PipelineError::CouldNotReadConfig(path, e) => write!(f, "could not read pipelines config {}: {e}", path.to_string_lossy()),
+            PipelineError::InvalidConfig(path, e) => write!(f, "invalid pipelines config {}: {e}", path.to_string_lossy()),
+            PipelineError::InvalidRequest(err) => write!(f, "invalid request: {err}"),
+            PipelineError::NoPipelineRequested => write!(f, "no pipeline was named in the request"),
+            PipelineError::UnknownPipeline(name) => write!(f, "no pipeline named \"{name}\" is configured"),
+            PipelineError::InvalidStep(pipeline, step_number, msg) => write!(f, "step {step_number} of pipeline \"{pipeline}\" is invalid: {msg}"),
+            PipelineError::InvalidDirectory(err) => write!(f, "invalid directory: {err}"),
+            PipelineError::InvalidFile(err) => write!(f, "invalid file path: {err}"),
+            PipelineError::CouldNotCreateFolder(path, e) => write!(f, "could not create folder {}: {e}", path.to_string_lossy()),
+            PipelineError::ImageError(path, e) => write!(f, "could not process {}: {e}", path.to_string_lossy()),
+            PipelineError::CouldNotReadTags(path, e) => write!(f, "could not read tags of {}: {e}", path.to_string_lossy()),
+            PipelineError::CouldNotSave(path, e) => write!(f, "could not save {}: {e}", path.to_string_lossy()),
+            PipelineError::StepFailed(step, path, msg) => write!(f, "{step} failed on {}: {msg}", path.to_string_lossy()),
+        }
+    }
+}
\ No newline at end of file
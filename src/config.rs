@@ -0,0 +1,484 @@
+pub mod error;
+use std::{collections::{HashMap, HashSet}, net::IpAddr, path::PathBuf, sync::Arc};
+use clap::Args;
+use rustls::pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+use mw_files::autocorr::pipeline::PipelinesConfig;
+use error::ConfigError;
+use crate::handlers::auth::Role;
+
+/// Server settings, read from a TOML file such as:
+/// ```toml
+/// bind_address = "0.0.0.0"
+/// port = 7001
+/// allowed_origins = ["https://intake.example.com"]
+/// allowed_roots = ["D:\\Orders"]
+/// http_workers = 4
+/// tls_cert_path = "C:\\Worker\\worker.crt"
+/// tls_key_path = "C:\\Worker\\worker.key"
+/// history_path = "C:\\Worker\\job_history.sqlite3"
+/// pipelines_path = "C:\\Worker\\pipelines.toml"
+///
+/// [[tokens]]
+/// name = "jsmith"
+/// token = "a long random secret"
+/// role = "qc_lead"
+///
+/// [naming]
+/// profile = "legacy"
+///
+/// [naming.profiles.legacy]
+/// group_num_precision = 3
+/// index_num_precision = 4
+///
+/// [watch]
+/// folders = ["D:\\Drop\\Slides", "D:\\Drop\\Prints"]
+/// debounce_seconds = 30
+/// ```
+/// Any setting can be overridden with a command line flag or environment variable, see [`ServerArgs`].
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ServerConfig {
+    #[serde(default = "default_bind_address")]
+    pub bind_address: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Path the websocket is served at.
+    #[serde(default = "default_ws_path")]
+    pub ws_path: String,
+    /// Origins allowed to open a websocket. Any origin is allowed if this is empty.
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+    /// Directories websocket jobs may read and write in. Any directory is allowed if this is empty.
+    #[serde(default)]
+    pub allowed_roots: Vec<PathBuf>,
+    /// Server worker threads, which serve sockets and HTTP requests. Jobs run on a separate pool of blocking threads,
+    /// so a long job doesn't stall the other sockets served from its thread.
+    #[serde(default = "default_http_workers")]
+    pub http_workers: usize,
+    /// PEM certificate chain. If this and `tls_key_path` are set, the websocket is served over TLS as `wss://`.
+    #[serde(default)]
+    pub tls_cert_path: Option<PathBuf>,
+    /// PEM private key for `tls_cert_path`.
+    #[serde(default)]
+    pub tls_key_path: Option<PathBuf>,
+    /// SQLite database every job and its result is recorded in. Created if it doesn't exist.
+    #[serde(default = "default_history_path")]
+    pub history_path: PathBuf,
+    /// Correction pipelines for the run_pipeline job. Defaults to pipelines.toml in the working directory, if there is one.
+    #[serde(default)]
+    pub pipelines_path: Option<PathBuf>,
+    /// Read from `pipelines_path` when the config is loaded.
+    #[serde(skip)]
+    pub pipelines: PipelinesConfig,
+    /// Clients must connect with one of these tokens if any are set.
+    #[serde(default)]
+    pub tokens: Vec<TokenConfig>,
+    #[serde(default)]
+    pub naming: NamingConfig,
+    /// Hot folders are only watched if this is set.
+    #[serde(default)]
+    pub watch: Option<WatchConfig>,
+}
+fn default_bind_address() -> String { String::from("127.0.0.1") }
+fn default_port() -> u16 { 7001 }
+fn default_ws_path() -> String { String::from("/ws/") }
+fn default_history_path() -> PathBuf { PathBuf::from("job_history.sqlite3") }
+fn default_http_workers() -> usize { std::thread::available_parallelism().map(usize::from).unwrap_or(1) }
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct NamingConfig {
+    /// Profile used by requests that don't name one in `naming_profile`.
+    #[serde(default = "default_naming_profile")]
+    pub profile: String,
+    /// Profiles besides the built in `standard` one, which can also be redefined here.
+    #[serde(default)]
+    pub profiles: HashMap<String, NamingProfile>,
+}
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TokenConfig {
+    /// Who the token belongs to, for logs.
+    pub name: String,
+    pub token: String,
+    #[serde(default)]
+    pub role: Role,
+}
+
+/// Drop folders whose group folders are final checked as they arrive.
+/// Each group folder is checked with the final check request in a rules file next to it,
+/// so `D:\Drop\Slides\SmithJ` is checked with `D:\Drop\Slides\SmithJ.final_check.json`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct WatchConfig {
+    #[serde(default)]
+    pub folders: Vec<PathBuf>,
+    #[serde(default = "default_poll_seconds")]
+    pub poll_seconds: u64,
+    /// How long a group folder must go unchanged before it's checked, so groups still being copied aren't.
+    #[serde(default = "default_debounce_seconds")]
+    pub debounce_seconds: u64,
+    /// Every result is appended to this file as a line of JSON.
+    #[serde(default = "default_results_log")]
+    pub results_log: PathBuf,
+}
+fn default_poll_seconds() -> u64 { 5 }
+fn default_debounce_seconds() -> u64 { 30 }
+fn default_results_log() -> PathBuf { PathBuf::from("watch_results.jsonl") }
+
+/// The naming section on its own, ignoring the rest of the config file.
+#[derive(Deserialize, Debug, Default)]
+struct NamingSection {
+    #[serde(default)]
+    naming: NamingConfig,
+}
+
+fn default_naming_profile() -> String { String::from(STANDARD_NAMING_PROFILE) }
+
+/// Precisions final checks expect when a request doesn't give its own.
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct NamingProfile {
+    #[serde(default = "default_group_num_precision")]
+    pub group_num_precision: u64,
+    #[serde(default = "default_index_num_precision")]
+    pub index_num_precision: u64,
+}
+fn default_group_num_precision() -> u64 { 2 }
+fn default_index_num_precision() -> u64 { 3 }
+
+/// Overrides for the config file. Flags take precedence over environment variables.
+#[derive(Args, Debug)]
+pub struct ServerArgs {
+    /// Server config file. Defaults to worker.toml in the working directory, if there is one.
+    #[arg(long, env = "MW_CONFIG", global = true)]
+    pub config: Option<PathBuf>,
+    #[arg(long, env = "MW_BIND_ADDRESS")]
+    pub bind_address: Option<String>,
+    #[arg(long, env = "MW_PORT")]
+    pub port: Option<u16>,
+    #[arg(long, env = "MW_WS_PATH")]
+    pub ws_path: Option<String>,
+    /// Replaces the configured allowed origins. Separate several with commas.
+    #[arg(long = "allowed-origin", env = "MW_ALLOWED_ORIGINS", value_delimiter = ',')]
+    pub allowed_origins: Option<Vec<String>>,
+    /// Replaces the configured allowed roots. Separate several with semicolons.
+    #[arg(long = "allowed-root", env = "MW_ALLOWED_ROOTS", value_delimiter = ';')]
+    pub allowed_roots: Option<Vec<PathBuf>>,
+    #[arg(long, env = "MW_HTTP_WORKERS")]
+    pub http_workers: Option<usize>,
+    #[arg(long, env = "MW_TLS_CERT")]
+    pub tls_cert: Option<PathBuf>,
+    #[arg(long, env = "MW_TLS_KEY")]
+    pub tls_key: Option<PathBuf>,
+    #[arg(long, env = "MW_HISTORY_PATH")]
+    pub history_path: Option<PathBuf>,
+    #[arg(long, env = "MW_PIPELINES")]
+    pub pipelines: Option<PathBuf>,
+    #[arg(long, env = "MW_NAMING_PROFILE")]
+    pub naming_profile: Option<String>,
+}
+
+const DEFAULT_CONFIG_PATH: &str = "worker.toml";
+const DEFAULT_PIPELINES_PATH: &str = "pipelines.toml";
+const STANDARD_NAMING_PROFILE: &str = "standard";
+const MAX_GROUP_NUM_PRECISION: u64 = 6;
+const MIN_TOKEN_LENGTH: usize = 16;
+
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind_address: default_bind_address(),
+            port: default_port(),
+            ws_path: default_ws_path(),
+            allowed_origins: Vec::new(),
+            allowed_roots: Vec::new(),
+            http_workers: default_http_workers(),
+            tls_cert_path: None,
+            tls_key_path: None,
+            history_path: default_history_path(),
+            pipelines_path: None,
+            pipelines: PipelinesConfig::default(),
+            tokens: Vec::new(),
+            naming: NamingConfig::default(),
+            watch: None,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Reads the config file, applies the overrides in `args`, then validates the result.
+    /// Defaults are used if no config file was given and there's none at the default path.
+    pub fn load(args: &ServerArgs) -> Result<ServerConfig, ConfigError> {
+        let mut config: ServerConfig = read_config_file(args)?;
+
+        if let Some(bind_address) = &args.bind_address {
+            config.bind_address = bind_address.clone();
+        }
+        if let Some(port) = args.port {
+            config.port = port;
+        }
+        if let Some(ws_path) = &args.ws_path {
+            config.ws_path = ws_path.clone();
+        }
+        if let Some(allowed_origins) = &args.allowed_origins {
+            config.allowed_origins = allowed_origins.clone();
+        }
+        if let Some(allowed_roots) = &args.allowed_roots {
+            config.allowed_roots = allowed_roots.clone();
+        }
+        if let Some(http_workers) = args.http_workers {
+            config.http_workers = http_workers;
+        }
+        if let Some(tls_cert) = &args.tls_cert {
+            config.tls_cert_path = Some(tls_cert.clone());
+        }
+        if let Some(tls_key) = &args.tls_key {
+            config.tls_key_path = Some(tls_key.clone());
+        }
+        if let Some(history_path) = &args.history_path {
+            config.history_path = history_path.clone();
+        }
+        if let Some(pipelines) = &args.pipelines {
+            config.pipelines_path = Some(pipelines.clone());
+        }
+        if let Some(naming_profile) = &args.naming_profile {
+            config.naming.profile = naming_profile.clone();
+        }
+
+        config.validate()?;
+        let pipelines_path = config.pipelines_path.clone().unwrap_or(PathBuf::from(DEFAULT_PIPELINES_PATH));
+        if config.pipelines_path.is_some() || pipelines_path.exists() {
+            config.pipelines = PipelinesConfig::load(&pipelines_path).map_err(|e| ConfigError::InvalidPipelines(Box::new(e)))?;
+        }
+        // Requested paths are compared against these after resolving `..` and symlinks, so the roots need resolving too
+        config.allowed_roots = config.allowed_roots.iter()
+            .map(|root| std::fs::canonicalize(root).map_err(|e| ConfigError::InvalidAllowedRoot(root.clone(), e)))
+            .collect::<Result<_, _>>()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        self.bind_address.parse::<IpAddr>().map_err(|e| ConfigError::InvalidBindAddress(self.bind_address.clone(), e))?;
+        if self.port == 0 {
+            return Err(ConfigError::InvalidPort)
+        }
+        if !self.ws_path.starts_with('/') {
+            return Err(ConfigError::InvalidWsPath(self.ws_path.clone()))
+        }
+        if let Some(origin) = self.allowed_origins.iter().find(|origin| !origin.starts_with("http://") && !origin.starts_with("https://")) {
+            return Err(ConfigError::InvalidOrigin(origin.clone()))
+        }
+        for root in &self.allowed_roots {
+            let metadata = std::fs::metadata(root).map_err(|e| ConfigError::InvalidAllowedRoot(root.clone(), e))?;
+            if !metadata.is_dir() {
+                return Err(ConfigError::AllowedRootNotADirectory(root.clone()))
+            }
+        }
+        if self.http_workers == 0 {
+            return Err(ConfigError::InvalidHttpWorkers)
+        }
+        if self.tls_cert_path.is_some() != self.tls_key_path.is_some() {
+            return Err(ConfigError::IncompleteTls)
+        }
+        let mut seen_tokens = HashSet::new();
+        for token in &self.tokens {
+            if token.token.len() < MIN_TOKEN_LENGTH {
+                return Err(ConfigError::InvalidToken(token.name.clone(), format!("tokens must be at least {MIN_TOKEN_LENGTH} characters")))
+            }
+            if !seen_tokens.insert(&token.token) {
+                return Err(ConfigError::InvalidToken(token.name.clone(), String::from("token is already given to another client")))
+            }
+        }
+        if let Some(watch) = &self.watch {
+            watch.validate()?;
+        }
+        self.naming.validate()
+    }
+
+    /// Reads the certificate chain and private key, if TLS is configured.
+    pub fn load_tls(&self) -> Result<Option<rustls::ServerConfig>, ConfigError> {
+        let (Some(cert_path), Some(key_path)) = (&self.tls_cert_path, &self.tls_key_path) else {
+            return Ok(None)
+        };
+        let certs = CertificateDer::pem_file_iter(cert_path)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| ConfigError::InvalidTlsFile(cert_path.clone(), e))?;
+        if certs.is_empty() {
+            return Err(ConfigError::NoCertificates(cert_path.clone()))
+        }
+        let key = PrivateKeyDer::from_pem_file(key_path).map_err(|e| ConfigError::InvalidTlsFile(key_path.clone(), e))?;
+
+        // Picked explicitly, since rustls can't choose one itself if a dependency enables another provider
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        rustls::ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+            .map(Some)
+            .map_err(ConfigError::InvalidTls)
+    }
+}
+
+impl Default for NamingConfig {
+    fn default() -> Self {
+        NamingConfig { profile: default_naming_profile(), profiles: HashMap::new() }
+    }
+}
+
+impl NamingConfig {
+    /// Reads only the naming section of the config file and the `naming_profile` override, for commands that
+    /// don't start the server and shouldn't fail on its settings.
+    pub fn load(args: &ServerArgs) -> Result<NamingConfig, ConfigError> {
+        let mut naming = read_config_file::<NamingSection>(args)?.naming;
+        if let Some(naming_profile) = &args.naming_profile {
+            naming.profile = naming_profile.clone();
+        }
+        naming.validate()?;
+        Ok(naming)
+    }
+
+    pub fn get(&self, name: &str) -> Result<NamingProfile, ConfigError> {
+        match (self.profiles.get(name), name == STANDARD_NAMING_PROFILE) {
+            (Some(profile), _) => Ok(*profile),
+            (None, true) => Ok(NamingProfile::default()),
+            (None, false) => Err(ConfigError::UnknownNamingProfile(name.to_string())),
+        }
+    }
+
+    /// Fills in the precisions a final check request leaves out, from the profile it names in `naming_profile` or the default one.
+    pub fn apply(&self, mut request_json: Value) -> Result<Value, ConfigError> {
+        if let Some(Value::Object(data)) = request_json.get_mut("data") {
+            let name = match data.get("naming_profile") {
+                Some(Value::String(name)) => name.clone(),
+                _ => self.profile.clone(),
+            };
+            let profile = self.get(&name)?;
+            data.entry("group_num_precision").or_insert(Value::from(profile.group_num_precision));
+            data.entry("index_num_precision").or_insert(Value::from(profile.index_num_precision));
+        }
+        Ok(request_json)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        self.get(&self.profile)?;
+        for (name, profile) in &self.profiles {
+            if profile.group_num_precision > MAX_GROUP_NUM_PRECISION {
+                return Err(ConfigError::InvalidNamingProfile(name.clone(), format!("group_num_precision {} is higher than the maximum {MAX_GROUP_NUM_PRECISION}", profile.group_num_precision)))
+            }
+            if profile.index_num_precision == 0 {
+                return Err(ConfigError::InvalidNamingProfile(name.clone(), String::from("index_num_precision must be at least 1")))
+            }
+        }
+        Ok(())
+    }
+}
+
+impl WatchConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        for folder in &self.folders {
+            let metadata = std::fs::metadata(folder).map_err(|e| ConfigError::InvalidWatchFolder(folder.clone(), e))?;
+            if !metadata.is_dir() {
+                return Err(ConfigError::WatchFolderNotADirectory(folder.clone()))
+            }
+        }
+        if self.poll_seconds == 0 {
+            return Err(ConfigError::InvalidPollSeconds)
+        }
+        Ok(())
+    }
+}
+
+impl Default for NamingProfile {
+    fn default() -> Self {
+        NamingProfile { group_num_precision: default_group_num_precision(), index_num_precision: default_index_num_precision() }
+    }
+}
+
+/// Parses the config file given in `args`, or the default one if there is one.
+fn read_config_file<T: DeserializeOwned + Default>(args: &ServerArgs) -> Result<T, ConfigError> {
+    let path = args.config.clone().unwrap_or(PathBuf::from(DEFAULT_CONFIG_PATH));
+    if args.config.is_none() && !path.exists() {
+        return Ok(T::default())
+    }
+    let text = std::fs::read_to_string(&path).map_err(|e| ConfigError::CouldNotReadConfig(path.clone(), e))?;
+    toml::from_str(&text).map_err(|e| ConfigError::InvalidConfig(path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, sync::Arc};
+    use rustls::{pki_types::ServerName, ClientConfig, ClientConnection, Connection, RootCertStore, ServerConnection};
+    use super::{error::ConfigError, ServerConfig};
+
+    /// Writes a self-signed certificate for localhost and its key to a temporary folder, returning the folder and certificate.
+    fn write_self_signed(name: &str) -> (PathBuf, rcgen::Certificate) {
+        let folder = std::env::temp_dir().join(format!("mw-files-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        let certified = rcgen::generate_simple_self_signed(vec![String::from("localhost")]).unwrap();
+        std::fs::write(folder.join("worker.crt"), certified.cert.pem()).unwrap();
+        std::fs::write(folder.join("worker.key"), certified.signing_key.serialize_pem()).unwrap();
+        (folder, certified.cert)
+    }
+
+    /// Moves everything `from` has to send into `to`, as a socket would.
+    fn transfer(from: &mut Connection, to: &mut Connection) {
+        let mut buffer = Vec::new();
+        while from.wants_write() {
+            from.write_tls(&mut buffer).unwrap();
+        }
+        let mut unread = &buffer[..];
+        while !unread.is_empty() {
+            to.read_tls(&mut unread).unwrap();
+        }
+        to.process_new_packets().unwrap();
+    }
+
+    #[test]
+    fn load_tls_completes_a_handshake() {
+        let (folder, cert) = write_self_signed("tls");
+        let config = ServerConfig { tls_cert_path: Some(folder.join("worker.crt")), tls_key_path: Some(folder.join("worker.key")), ..ServerConfig::default() };
+        let server_config = config.load_tls().unwrap().expect("TLS is configured");
+
+        let mut roots = RootCertStore::empty();
+        roots.add(cert.der().clone()).unwrap();
+        let client_config = ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions().unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let mut client = Connection::from(ClientConnection::new(Arc::new(client_config), ServerName::try_from("localhost").unwrap()).unwrap());
+        let mut server = Connection::from(ServerConnection::new(Arc::new(server_config)).unwrap());
+        for _ in 0..10 {
+            if !client.is_handshaking() && !server.is_handshaking() {
+                break;
+            }
+            transfer(&mut client, &mut server);
+            transfer(&mut server, &mut client);
+        }
+        std::fs::remove_dir_all(&folder).unwrap();
+
+        assert!(!client.is_handshaking() && !server.is_handshaking(), "handshake did not complete");
+    }
+
+    #[test]
+    fn load_tls_is_off_without_cert_and_key() {
+        assert!(ServerConfig::default().load_tls().unwrap().is_none());
+    }
+
+    #[test]
+    fn cert_or_key_alone_is_refused() {
+        let (folder, _) = write_self_signed("incomplete-tls");
+        let cert_only = ServerConfig { tls_cert_path: Some(folder.join("worker.crt")), ..ServerConfig::default() };
+        let key_only = ServerConfig { tls_key_path: Some(folder.join("worker.key")), ..ServerConfig::default() };
+        let results = [cert_only.validate(), key_only.validate()];
+        std::fs::remove_dir_all(&folder).unwrap();
+
+        for result in results {
+            let e = result.unwrap_err();
+            assert!(matches!(e, ConfigError::IncompleteTls), "unexpected error: {e}");
+            assert_eq!(e.to_string(), "tls_cert_path and tls_key_path must be set together");
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,53 @@
+use mw_files::autocorr::pipeline::error::PipelineError;
+
+#[derive(Debug)]
+pub enum ConfigError {
+    CouldNotReadConfig(std::path::PathBuf, std::io::Error),
+    InvalidConfig(std::path::PathBuf, toml::de::Error),
+    InvalidBindAddress(String, std::net::AddrParseError),
+    InvalidPort,
+    InvalidWsPath(String),
+    InvalidOrigin(String),
+    InvalidAllowedRoot(std::path::PathBuf, std::io::Error),
+    AllowedRootNotADirectory(std::path::PathBuf),
+    InvalidHttpWorkers,
+    InvalidToken(String, String),
+    IncompleteTls,
+    InvalidTlsFile(std::path::PathBuf, rustls::pki_types::pem::Error),
+    NoCertificates(std::path::PathBuf),
+    InvalidTls(rustls::Error),
+    UnknownNamingProfile(String),
+    InvalidNamingProfile(String, String),
+    InvalidPipelines(Box<PipelineError>),
+    InvalidWatchFolder(std::path::PathBuf, std::io::Error),
+    WatchFolderNotADirectory(std::path::PathBuf),
+    InvalidPollSeconds,
+}
+impl std::error::Error for ConfigError {}
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter)
+    -> std::fmt::Result {
+        match self {
+            ConfigError::CouldNotReadConfig(path, e) => write!(f, "could not read server config {}: {e}", path.to_string_lossy()),
+            ConfigError::InvalidConfig(path, e) => write!(f, "invalid server config {}: {e}", path.to_string_lossy()),
+            ConfigError::InvalidBindAddress(address, e) => write!(f, "bind_address {address} is not an IP address: {e}"),
+            ConfigError::InvalidPort => write!(f, "port must be between 1 and 65535"),
+            ConfigError::InvalidWsPath(path) => write!(f, "ws_path {path} must start with /"),
+            ConfigError::InvalidOrigin(origin) => write!(f, "allowed origin {origin} must start with http:// or https://"),
+            ConfigError::InvalidAllowedRoot(path, e) => write!(f, "allowed root {} could not be read: {e}", path.to_string_lossy()),
+            ConfigError::AllowedRootNotADirectory(path) => write!(f, "allowed root {} is not a directory", path.to_string_lossy()),
+            ConfigError::InvalidHttpWorkers => write!(f, "http_workers must be at least 1"),
+            ConfigError::InvalidToken(name, msg) => write!(f, "invalid token for {name}: {msg}"),
+            ConfigError::IncompleteTls => write!(f, "tls_cert_path and tls_key_path must be set together"),
+            ConfigError::InvalidTlsFile(path, e) => write!(f, "could not read {}: {e}", path.to_string_lossy()),
+            ConfigError::NoCertificates(path) => write!(f, "no certificates were found in {}", path.to_string_lossy()),
+            ConfigError::InvalidTls(e) => write!(f, "invalid TLS certificate or key: {e}"),
+            ConfigError::UnknownNamingProfile(name) => write!(f, "no naming profile is named {name}"),
+            ConfigError::InvalidNamingProfile(name, msg) => write!(f, "invalid naming profile {name}: {msg}"),
+            ConfigError::InvalidPipelines(e) => write!(f, "{e}"),
+            ConfigError::InvalidWatchFolder(path, e) => write!(f, "watched folder {} could not be read: {e}", path.to_string_lossy()),
+            ConfigError::WatchFolderNotADirectory(path) => write!(f, "watched folder {} is not a directory", path.to_string_lossy()),
+            ConfigError::InvalidPollSeconds => write!(f, "watch poll_seconds must be at least 1"),
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,383 @@
+pub mod error;
+use std::{collections::{BTreeMap, BTreeSet}, path::{Component, Path, PathBuf}};
+use glob::glob;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use error::BagItError;
+use crate::{delivery::contact_sheets::describe_counts, qc::final_check::{self, final_check_request::FinalCheckRequest}, reporter::Reporter, utils::{date, hashing, paths}};
+
+#[derive(Deserialize, Debug)]
+struct Data {
+    data: BagOrderRequest
+}
+
+/// A final check request with where to write the bag, since orders are final checked before bagging.
+#[derive(Deserialize, Debug)]
+pub struct BagOrderRequest {
+    /// The bag is written to a folder named after the order inside this folder.
+    pub to_folder: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_organization: Option<String>,
+    #[serde(flatten)]
+    pub order: FinalCheckRequest,
+}
+
+#[derive(Serialize, Debug)]
+pub struct BagSummary {
+    pub bag_name: String,
+    pub payload_files: usize,
+    pub payload_bytes: u64,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct BagValidation {
+    pub payload_files: usize,
+    pub problems: Vec<String>,
+}
+impl BagValidation {
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+const BAGIT_TXT: &str = "bagit.txt";
+const BAG_INFO_TXT: &str = "bag-info.txt";
+const PAYLOAD_MANIFEST: &str = "manifest-sha256.txt";
+const TAG_MANIFEST: &str = "tagmanifest-sha256.txt";
+const PAYLOAD_FOLDER: &str = "data";
+
+
+/// Final checks an order, then copies the checked folder into a BagIt 1.0 bag with SHA-256 payload and tag manifests.
+/// If the request names a group number, only that group's folder is bagged, and the bag is named after the order and group.
+pub fn bag_order(dir: String, request_json: Value, reporter: &mut dyn Reporter) -> Result<BagSummary, BagItError> {
+    let request = serde_json::from_value::<Data>(request_json.clone()).map_err(BagItError::DeserializeError)?.data;
+    reporter.progress("Final checking order before bagging");
+    final_check::final_check(dir.clone(), request_json, reporter).map_err(BagItError::FinalCheckFailed)?;
+
+    let checked_folder = final_check::checked_folder(&dir, &request.order);
+    let root = PathBuf::from(&checked_folder);
+    let order_name = Path::new(&dir).file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let bag_name = match request.order.group_num {
+        Some(_) => format!("{order_name}_{}", root.file_name().unwrap_or_default().to_string_lossy()),
+        None => order_name,
+    };
+    let bag_dir = PathBuf::from(&request.to_folder).join(&bag_name);
+    if bag_dir.exists() {
+        return Err(BagItError::BagAlreadyExists(bag_dir));
+    }
+    let canonical_root = paths::canonical_dir(&root);
+    let mut order_files = Vec::new();
+    for entry in glob(&format!("{checked_folder}\\*")).map_err(BagItError::InvalidDirectory)? {
+        let path = entry.map_err(BagItError::InvalidFile)?;
+        if path.is_file() && !path.file_name().is_some_and(|name| name.eq_ignore_ascii_case("thumbs.db")) && paths::resolves_inside(&path, &canonical_root) {
+            order_files.push(path);
+        }
+    }
+    if order_files.is_empty() {
+        return Err(BagItError::NoFilesInDirectory(checked_folder));
+    }
+    order_files.sort();
+
+    let mut manifest = String::new();
+    let mut payload_bytes = 0;
+    for (number, path) in (1..).zip(&order_files) {
+        let relative_path = path.strip_prefix(&root).unwrap_or(path);
+        let bag_path = bag_dir.join(PAYLOAD_FOLDER).join(relative_path);
+        let manifest_path = bag_relative_path(&bag_path, &bag_dir);
+        reporter.progress(&format!("Bagging {number}/{}: {manifest_path}", order_files.len()));
+
+        if let Some(parent) = bag_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| BagItError::CouldNotCreateFolder(parent.to_path_buf(), e))?;
+        }
+        payload_bytes += std::fs::copy(path, &bag_path).map_err(|e| BagItError::CouldNotCopy(path.clone(), e))?;
+        // Hashing the copy means the manifest describes what actually landed in the bag
+        let hash = hashing::sha256_file(&bag_path).map_err(|e| BagItError::CouldNotHash(bag_path.clone(), e))?;
+        manifest.push_str(&format!("{hash}  {}\n", encode_path(&manifest_path)));
+    }
+
+    write_tag_file(&bag_dir, BAGIT_TXT, String::from("BagIt-Version: 1.0\nTag-File-Character-Encoding: UTF-8\n"))?;
+    write_tag_file(&bag_dir, BAG_INFO_TXT, bag_info(&request, payload_bytes, order_files.len()))?;
+    write_tag_file(&bag_dir, PAYLOAD_MANIFEST, manifest)?;
+    let mut tag_manifest = String::new();
+    for tag_file in [BAGIT_TXT, BAG_INFO_TXT, PAYLOAD_MANIFEST] {
+        let path = bag_dir.join(tag_file);
+        let hash = hashing::sha256_file(&path).map_err(|e| BagItError::CouldNotHash(path.clone(), e))?;
+        tag_manifest.push_str(&format!("{hash}  {tag_file}\n"));
+    }
+    write_tag_file(&bag_dir, TAG_MANIFEST, tag_manifest)?;
+
+    Ok(BagSummary { bag_name, payload_files: order_files.len(), payload_bytes })
+}
+
+/// Checks that a bag is complete and valid as defined by RFC 8493, using its SHA-256 and SHA-512 manifests.
+/// Problems are collected rather than returned as errors so the whole bag is reported at once.
+pub fn validate_bag(dir: String, reporter: &mut dyn Reporter) -> Result<BagValidation, BagItError> {
+    let bag_dir = PathBuf::from(&dir);
+    let mut validation = BagValidation::default();
+
+    match std::fs::read_to_string(bag_dir.join(BAGIT_TXT)) {
+        Ok(bagit_txt) => {
+            let declaration = parse_tag_file(&bagit_txt);
+            for label in ["BagIt-Version", "Tag-File-Character-Encoding"] {
+                if !declaration.contains_key(label) {
+                    validation.problems.push(format!("{BAGIT_TXT} is missing {label}"));
+                }
+            }
+        }
+        Err(e) => {
+            validation.problems.push(format!("could not read {BAGIT_TXT}: {e}"));
+            return Ok(validation);
+        }
+    }
+
+    let canonical_bag_dir = paths::canonical_dir(&bag_dir);
+    let mut payload: BTreeMap<String, PathBuf> = BTreeMap::new();
+    for entry in glob(&format!("{dir}\\{PAYLOAD_FOLDER}\\**\\*")).map_err(BagItError::InvalidDirectory)? {
+        let path = entry.map_err(BagItError::InvalidFile)?;
+        if path.is_file() && paths::resolves_inside(&path, &canonical_bag_dir) {
+            payload.insert(bag_relative_path(&path, &bag_dir), path);
+        }
+    }
+    validation.payload_files = payload.len();
+
+    let mut payload_manifests = 0;
+    for (manifest_name, hash_file) in find_manifests(&bag_dir, "manifest-") {
+        payload_manifests += 1;
+        let listed = check_manifest(&bag_dir, &manifest_name, hash_file, &mut validation, |path| reporter.progress(&format!("Checking {path}")));
+        for unlisted in payload.keys().filter(|path| !listed.contains(*path)) {
+            validation.problems.push(format!("{unlisted} is not listed in {manifest_name}"));
+        }
+    }
+    if payload_manifests == 0 {
+        validation.problems.push(String::from("no SHA-256 or SHA-512 payload manifest found"));
+    }
+    for (manifest_name, hash_file) in find_manifests(&bag_dir, "tagmanifest-") {
+        check_manifest(&bag_dir, &manifest_name, hash_file, &mut validation, |path| reporter.progress(&format!("Checking {path}")));
+    }
+
+    if let Ok(bag_info) = std::fs::read_to_string(bag_dir.join(BAG_INFO_TXT)) {
+        if let Some(oxum) = parse_tag_file(&bag_info).get("Payload-Oxum") {
+            let mut payload_bytes = 0;
+            for path in payload.values() {
+                payload_bytes += std::fs::metadata(path).map_err(|e| BagItError::CouldNotHash(path.clone(), e))?.len();
+            }
+            let actual = format!("{payload_bytes}.{}", payload.len());
+            if *oxum != actual {
+                validation.problems.push(format!("Payload-Oxum is {oxum}, but the payload is {actual}"));
+            }
+        }
+    }
+
+    Ok(validation)
+}
+
+type HashFile = fn(&Path) -> std::io::Result<String>;
+
+/// Manifests with a prefix of `manifest-` or `tagmanifest-` in algorithms we can check.
+fn find_manifests(bag_dir: &Path, prefix: &str) -> Vec<(String, HashFile)> {
+    let mut manifests: Vec<(String, HashFile)> = Vec::new();
+    for (algorithm, hash_file) in [("sha256", hashing::sha256_file as HashFile), ("sha512", hashing::sha512_file as HashFile)] {
+        let manifest_name = format!("{prefix}{algorithm}.txt");
+        if bag_dir.join(&manifest_name).is_file() {
+            manifests.push((manifest_name, hash_file));
+        }
+    }
+    manifests
+}
+
+/// Re-hashes every file a manifest lists, recording problems, and returns the paths it lists.
+fn check_manifest(bag_dir: &Path, manifest_name: &str, hash_file: HashFile, validation: &mut BagValidation, mut on_file: impl FnMut(&str)) -> BTreeSet<String> {
+    let mut listed = BTreeSet::new();
+    let manifest = match std::fs::read_to_string(bag_dir.join(manifest_name)) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            validation.problems.push(format!("could not read {manifest_name}: {e}"));
+            return listed;
+        }
+    };
+
+    let canonical_bag_dir = std::fs::canonicalize(bag_dir);
+    for (line_number, line) in (1..).zip(manifest.lines()) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some((expected_hash, path)) = line.split_once(char::is_whitespace) else {
+            validation.problems.push(format!("line {line_number} of {manifest_name} has no file path"));
+            continue;
+        };
+        let path = decode_path(path.trim_start());
+        // Each component must be a plain name, so a listed path can't climb out of the bag or replace the bag folder
+        let is_plain_name = |component: &str| {
+            let mut components = Path::new(component).components();
+            matches!((components.next(), components.next()), (Some(Component::Normal(_)), None))
+        };
+        if !path.split('/').all(is_plain_name) {
+            validation.problems.push(format!("{path} on line {line_number} of {manifest_name} is not a path inside the bag"));
+            continue;
+        }
+        let file_path = path.split('/').fold(bag_dir.to_path_buf(), |file_path, component| file_path.join(component));
+        if let (Ok(canonical_bag_dir), Ok(canonical_file_path)) = (&canonical_bag_dir, std::fs::canonicalize(&file_path)) {
+            if !canonical_file_path.starts_with(canonical_bag_dir) {
+                validation.problems.push(format!("{path} in {manifest_name} leads outside the bag"));
+                continue;
+            }
+        }
+        on_file(&path);
+        match hash_file(&file_path) {
+            Ok(hash) if hash.eq_ignore_ascii_case(expected_hash) => {}
+            Ok(_) => validation.problems.push(format!("{path} does not match its checksum in {manifest_name}")),
+            Err(_) if !file_path.is_file() => validation.problems.push(format!("{path} is listed in {manifest_name} but missing")),
+            Err(e) => validation.problems.push(format!("could not hash {path}: {e}")),
+        }
+        listed.insert(path);
+    }
+    listed
+}
+
+fn bag_info(request: &BagOrderRequest, payload_bytes: u64, payload_files: usize) -> String {
+    let order = &request.order;
+    let mut fields = Vec::new();
+    if let Some(source_organization) = &request.source_organization {
+        fields.push(("Source-Organization", source_organization.clone()));
+    }
+    fields.push(("Bagging-Date", date::today()));
+    fields.push(("External-Identifier", order.custom_group_name.clone().unwrap_or_else(|| format!("{}, {}", order.last_name, order.first_name))));
+    fields.push(("External-Description", format!("Scans for {} {}. {}", order.first_name, order.last_name, describe_counts(&order.media_group_values))));
+    if order.group_num.is_some() || order.group_char.is_some() {
+        let number = order.group_num.map(|number| format!("{number:0precision$}", precision = order.group_num_precision as usize)).unwrap_or_default();
+        let character = order.group_char.map(String::from).unwrap_or_default();
+        fields.push(("Bag-Group-Identifier", format!("{number}{character}")));
+    }
+    fields.push(("Payload-Oxum", format!("{payload_bytes}.{payload_files}")));
+    fields.push(("Bag-Software-Agent", format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))));
+
+    fields.iter().map(|(label, value)| format!("{label}: {}\n", value.replace(['\r', '\n'], " "))).collect()
+}
+
+fn write_tag_file(bag_dir: &Path, name: &str, contents: String) -> Result<(), BagItError> {
+    let path = bag_dir.join(name);
+    std::fs::write(&path, contents).map_err(|e| BagItError::CouldNotWrite(path, e))
+}
+
+/// Reads `Label: value` lines, joining indented continuation lines onto the previous value.
+fn parse_tag_file(contents: &str) -> BTreeMap<String, String> {
+    let mut tags: BTreeMap<String, String> = BTreeMap::new();
+    let mut last_label: Option<String> = None;
+    for line in contents.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some(value) = last_label.as_ref().and_then(|label| tags.get_mut(label)) {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+        if let Some((label, value)) = line.split_once(':') {
+            tags.insert(label.trim().to_string(), value.trim().to_string());
+            last_label = Some(label.trim().to_string());
+        }
+    }
+    tags
+}
+
+/// Path of a file inside the bag with forward slashes, as manifests list them.
+fn bag_relative_path(path: &Path, bag_dir: &Path) -> String {
+    path.strip_prefix(bag_dir).unwrap_or(path).components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Manifests percent-encode the characters that would break their line format.
+fn encode_path(path: &str) -> String {
+    path.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+fn decode_path(path: &str) -> String {
+    path.replace("%0D", "\r").replace("%0d", "\r").replace("%0A", "\n").replace("%0a", "\n").replace("%25", "%")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use serde_json::json;
+    use crate::{reporter::SilentReporter, utils::{hashing, test_files}};
+    use super::{bag_info, check_manifest, decode_path, encode_path, parse_tag_file, validate_bag, BagOrderRequest, BagValidation, BAGIT_TXT};
+
+    /// Writes a bag holding `data/a.txt` with the given payload manifest, and returns the problems checking the manifest finds.
+    fn check_bag(bag_dir: &Path, manifest: &str) -> Vec<String> {
+        std::fs::create_dir_all(bag_dir.join("data")).unwrap();
+        std::fs::write(bag_dir.join("data").join("a.txt"), "scan").unwrap();
+        std::fs::write(bag_dir.join("manifest-sha256.txt"), manifest).unwrap();
+        let mut validation = BagValidation::default();
+        check_manifest(bag_dir, "manifest-sha256.txt", hashing::sha256_file, &mut validation, |_| {});
+        validation.problems
+    }
+
+    #[test]
+    fn accepts_matching_checksums() {
+        let dir = test_files::temp_dir("bagit-valid");
+        std::fs::write(dir.join("scan.txt"), "scan").unwrap();
+        let hash = hashing::sha256_file(&dir.join("scan.txt")).unwrap();
+        assert_eq!(check_bag(&dir, &format!("{hash}  data/a.txt\n")), Vec::<String>::new());
+    }
+
+    #[test]
+    fn reports_changed_and_missing_files() {
+        let dir = test_files::temp_dir("bagit-changed");
+        let other_hash = hashing::sha256_file(&Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml")).unwrap();
+        assert_eq!(check_bag(&dir, &format!("{other_hash}  data/a.txt\n{other_hash}  data/b.txt\n")), [
+            "data/a.txt does not match its checksum in manifest-sha256.txt",
+            "data/b.txt is listed in manifest-sha256.txt but missing",
+        ]);
+    }
+
+    #[test]
+    fn rejects_manifest_paths_outside_the_bag() {
+        let dir = test_files::temp_dir("bagit-escape");
+        let outside = dir.with_extension("txt");
+        std::fs::write(&outside, "outside").unwrap();
+        let hash = hashing::sha256_file(&outside).unwrap();
+        let name = outside.file_name().unwrap().to_string_lossy().into_owned();
+        let manifest = format!("{hash}  ../{name}\n{hash}  data/../../{name}\n{hash}  {}\n{hash}  data//a.txt\n", outside.to_string_lossy());
+        let problems = check_bag(&dir, &manifest);
+        assert_eq!(problems.len(), 4, "{problems:?}");
+        assert!(problems.iter().all(|problem| problem.ends_with("is not a path inside the bag")), "{problems:?}");
+    }
+
+    #[test]
+    fn requires_a_bag_declaration() {
+        let dir = test_files::temp_dir("bagit-incomplete");
+        let validation = validate_bag(dir.to_string_lossy().into_owned(), &mut SilentReporter).unwrap();
+        assert!(!validation.is_valid());
+        assert!(validation.problems[0].starts_with(&format!("could not read {BAGIT_TXT}")));
+    }
+
+    #[test]
+    fn reads_folded_tag_values() {
+        let tags = parse_tag_file("Source-Organization: Memories\n  Workshop\nPayload-Oxum: 4.1\n");
+        assert_eq!(tags.get("Source-Organization").map(String::as_str), Some("Memories Workshop"));
+        assert_eq!(tags.get("Payload-Oxum").map(String::as_str), Some("4.1"));
+    }
+
+    #[test]
+    fn encodes_line_breaks_in_manifest_paths() {
+        let path = "data/100%\r\nscan.jpg";
+        assert_eq!(encode_path(path), "data/100%25%0D%0Ascan.jpg");
+        assert_eq!(decode_path(&encode_path(path)), path);
+    }
+
+    #[test]
+    fn describes_the_bagged_group() {
+        let request: BagOrderRequest = serde_json::from_value(json!({
+            "to_folder": "bags", "source_organization": "Memories\nWorkshop",
+            "first_name": "John", "last_name": "Smith", "group_num": 3, "group_char": "B", "group_num_precision": 2,
+            "media_group_values": { "slides": { "scanner": 10 } },
+        })).unwrap();
+        let tags = parse_tag_file(&bag_info(&request, 1024, 10));
+        assert_eq!(tags.get("Source-Organization").map(String::as_str), Some("Memories Workshop"));
+        assert_eq!(tags.get("External-Identifier").map(String::as_str), Some("Smith, John"));
+        assert_eq!(tags.get("Bag-Group-Identifier").map(String::as_str), Some("03B"));
+        assert_eq!(tags.get("Payload-Oxum").map(String::as_str), Some("1024.10"));
+        assert_eq!(super::final_check::checked_folder("Smith", &request.order), "Smith\\03");
+    }
+}
\ No newline at end of file
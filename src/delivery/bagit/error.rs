@@ -0,0 +1,34 @@
+use glob::{GlobError, PatternError};
+use crate::qc::final_check::error::FCError;
+
+#[derive(Debug)]
+pub enum BagItError {
+    DeserializeError(serde_json::Error),
+    InvalidDirectory(PatternError),
+    InvalidFile(GlobError),
+    FinalCheckFailed(FCError),
+    NoFilesInDirectory(String),
+    BagAlreadyExists(std::path::PathBuf),
+    CouldNotCreateFolder(std::path::PathBuf, std::io::Error),
+    CouldNotCopy(std::path::PathBuf, std::io::Error),
+    CouldNotHash(std::path::PathBuf, std::io::Error),
+    CouldNotWrite(std::path::PathBuf, std::io::Error),
+}
+impl std::error::Error for BagItError {}
+impl std::fmt::Display for BagItError {
+    fn fmt(&self, f: &mut std::fmt::Formatter)
+    -> std::fmt::Result {
+        match self {
+            BagItError::DeserializeError(err) => write!(f, "could not deserialize request: {err}"),
+            BagItError::InvalidDirectory(err) => write!(f, "invalid directory: {err}"),
+            BagItError::InvalidFile(err) => write!(f, "invalid file path: {err}"),
+            BagItError::FinalCheckFailed(err) => write!(f, "order did not pass final check: {err}"),
+            BagItError::NoFilesInDirectory(dir) => write!(f, "no files found in {dir}"),
+            BagItError::BagAlreadyExists(path) => write!(f, "{} already exists", path.to_string_lossy()),
+            BagItError::CouldNotCreateFolder(path, e) => write!(f, "could not create folder {}: {e}", path.to_string_lossy()),
+            BagItError::CouldNotCopy(path, e) => write!(f, "could not copy {}: {e}", path.to_string_lossy()),
+            BagItError::CouldNotHash(path, e) => write!(f, "could not hash {}: {e}", path.to_string_lossy()),
+            BagItError::CouldNotWrite(path, e) => write!(f, "could not write {}: {e}", path.to_string_lossy()),
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,304 @@
+pub mod error;
+use std::{collections::BTreeMap, path::{Path, PathBuf}};
+use ab_glyph::{Font, FontVec, PxScale};
+use glob::glob;
+use image::{imageops, DynamicImage, Rgb, RgbImage};
+use imageproc::drawing::{draw_text_mut, text_size};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use error::ContactSheetsError;
+use crate::{qc::final_check::{media_file::MediaFile, media_groups::MediaGroupValues, photo_group_options::PhotoGroupOptions}, reporter::Reporter, utils::{image_io, paths, pdf}};
+
+#[derive(Deserialize, Debug)]
+struct Data {
+    data: ContactSheetsRequest
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ContactSheetsRequest {
+    pub to_folder: String,
+    #[serde(default)]
+    pub format: SheetFormat,
+    #[serde(default = "default_columns")]
+    pub columns: u32,
+    #[serde(default = "default_rows")]
+    pub rows: u32,
+    /// TrueType font used for the header and captions. Defaults to Arial in its usual Windows location.
+    #[serde(default = "default_font_path")]
+    pub font_path: String,
+}
+fn default_columns() -> u32 { 4 }
+fn default_rows() -> u32 { 5 }
+fn default_font_path() -> String { String::from("C:\\Windows\\Fonts\\arial.ttf") }
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SheetFormat {
+    /// One multi-page PDF per group.
+    #[default]
+    Pdf,
+    /// One PNG per page.
+    Png,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ContactSheet {
+    pub group: String,
+    pub pages: usize,
+    pub files: Vec<String>,
+}
+
+/// Sheets are US letter at this resolution.
+const SHEET_DPI: u32 = 150;
+const SHEET_WIDTH: u32 = 1275;
+const SHEET_HEIGHT: u32 = 1650;
+const SHEET_MARGIN: u32 = 60;
+const HEADER_HEIGHT: u32 = 120;
+const CAPTION_HEIGHT: u32 = 28;
+const CELL_PADDING: u32 = 8;
+const CONTENT_WIDTH: u32 = SHEET_WIDTH - 2 * SHEET_MARGIN;
+const CONTENT_HEIGHT: u32 = SHEET_HEIGHT - 2 * SHEET_MARGIN - HEADER_HEIGHT;
+/// Past these every cell would be too small to hold its padding, caption and at least a pixel of thumbnail.
+const MAX_COLUMNS: u32 = CONTENT_WIDTH / (2 * CELL_PADDING + 1);
+const MAX_ROWS: u32 = CONTENT_HEIGHT / (2 * CELL_PADDING + CAPTION_HEIGHT + 1);
+const _: () = assert!(CONTENT_WIDTH / MAX_COLUMNS > 2 * CELL_PADDING && CONTENT_HEIGHT / MAX_ROWS > 2 * CELL_PADDING + CAPTION_HEIGHT);
+const TITLE_SCALE: f32 = 40.0;
+const TEXT_SCALE: f32 = 22.0;
+const BLACK: Rgb<u8> = Rgb([0, 0, 0]);
+const WHITE: Rgb<u8> = Rgb([255, 255, 255]);
+
+
+/// Lays out thumbnails of every media file in `dir` and its subfolders onto contact sheets, one set of sheets per group.
+pub fn generate_contact_sheets(dir: String, request_json: Value, reporter: &mut dyn Reporter) -> Result<Vec<ContactSheet>, ContactSheetsError> {
+    let request = serde_json::from_value::<Data>(request_json).map_err(ContactSheetsError::DeserializeError)?.data;
+    validate_layout(&request)?;
+    let font_path = PathBuf::from(&request.font_path);
+    if !font_path.is_file() {
+        return Err(ContactSheetsError::FontNotFound(font_path));
+    }
+    let font_data = std::fs::read(&font_path).map_err(|e| ContactSheetsError::CouldNotLoadFont(font_path.clone(), e.to_string()))?;
+    let font = FontVec::try_from_vec(font_data).map_err(|e| ContactSheetsError::CouldNotLoadFont(font_path.clone(), e.to_string()))?;
+    let to_folder = PathBuf::from(&request.to_folder);
+    std::fs::create_dir_all(&to_folder).map_err(|e| ContactSheetsError::CouldNotCreateFolder(to_folder.clone(), e))?;
+
+    let canonical_root = paths::canonical_dir(Path::new(&dir));
+    let pattern = format!("{dir}\\**\\*");
+    let mut groups: BTreeMap<(Option<u32>, Option<char>), Vec<MediaFile>> = BTreeMap::new();
+    for entry in glob(&pattern).map_err(ContactSheetsError::InvalidDirectory)? {
+        let path = entry.map_err(ContactSheetsError::InvalidFile)?;
+        if !image_io::is_image_file(&path) || !paths::resolves_inside(&path, &canonical_root) {
+            continue;
+        }
+        if let Some(media_file) = MediaFile::from_path(&path).map_err(|e| ContactSheetsError::MediaFileParseError(path.clone(), e))? {
+            groups.entry((media_file.group_number, media_file.group_character)).or_default().push(media_file);
+        }
+    }
+    if groups.is_empty() {
+        return Err(ContactSheetsError::NoFilesInDirectory(pattern));
+    }
+
+    let mut sheets = Vec::new();
+    for media_files in groups.values_mut() {
+        media_files.sort_by_key(|media_file| media_file.index_number);
+        let group = group_label(&media_files[0]);
+        reporter.progress(&format!("Laying out contact sheets for {group}"));
+        sheets.push(write_group_sheets(&group, media_files, &request, &font, &to_folder)?);
+    }
+
+    Ok(sheets)
+}
+
+fn validate_layout(request: &ContactSheetsRequest) -> Result<(), ContactSheetsError> {
+    if !(1..=MAX_COLUMNS).contains(&request.columns) {
+        return Err(ContactSheetsError::InvalidRequest(format!("columns {} is not between 1 and {MAX_COLUMNS}", request.columns)))
+    }
+    if !(1..=MAX_ROWS).contains(&request.rows) {
+        return Err(ContactSheetsError::InvalidRequest(format!("rows {} is not between 1 and {MAX_ROWS}", request.rows)))
+    }
+    Ok(())
+}
+
+/// Renders one group's sheets and writes them to `to_folder` in the requested format.
+fn write_group_sheets(group: &str, media_files: &[MediaFile], request: &ContactSheetsRequest, font: &FontVec, to_folder: &Path) -> Result<ContactSheet, ContactSheetsError> {
+    let counts = MediaGroupValues::from_media_files(media_files).map_err(ContactSheetsError::MediaGroupingError)?;
+    let first = &media_files[0];
+    let title = format!("{}, {} - {group}", first.last_name, first.first_name_initial);
+    let file_stem = format!("{}_{}_{}_contact_sheet", first.last_name, first.first_name_initial, group.replace(' ', "_"));
+
+    let per_page = request.columns.checked_mul(request.rows).and_then(|cells| usize::try_from(cells).ok())
+        .ok_or_else(|| ContactSheetsError::InvalidRequest(String::from("too many cells per page")))?;
+    let page_count = media_files.len().div_ceil(per_page);
+    let mut pages = Vec::with_capacity(page_count);
+    for (page_index, page_files) in media_files.chunks(per_page).enumerate() {
+        let header = Header { title: &title, counts: &describe_counts(&counts), page: page_index + 1, page_count };
+        pages.push(DynamicImage::ImageRgb8(render_sheet(&header, page_files, request, font)?));
+    }
+
+    let mut files = Vec::new();
+    match request.format {
+        SheetFormat::Pdf => {
+            let file_name = format!("{file_stem}.pdf");
+            let path = to_folder.join(&file_name);
+            pdf::write_image_pdf(&pages, f64::from(SHEET_DPI), 90, &path).map_err(|e| ContactSheetsError::ImageError(path.clone(), e))?;
+            files.push(file_name);
+        }
+        SheetFormat::Png => {
+            for (page_number, page) in (1..).zip(&pages) {
+                let file_name = format!("{file_stem}_{page_number}.png");
+                let path = to_folder.join(&file_name);
+                page.save(&path).map_err(|e| ContactSheetsError::ImageError(path.clone(), e))?;
+                files.push(file_name);
+            }
+        }
+    }
+
+    Ok(ContactSheet { group: group.to_string(), pages: page_count, files })
+}
+
+struct Header<'a> {
+    title: &'a str,
+    counts: &'a str,
+    page: usize,
+    page_count: usize,
+}
+
+/// Draws the header and a grid of captioned thumbnails onto a white sheet.
+fn render_sheet(header: &Header, media_files: &[MediaFile], request: &ContactSheetsRequest, font: &FontVec) -> Result<RgbImage, ContactSheetsError> {
+    let mut sheet = RgbImage::from_pixel(SHEET_WIDTH, SHEET_HEIGHT, WHITE);
+    draw_text_mut(&mut sheet, BLACK, SHEET_MARGIN as i32, SHEET_MARGIN as i32, PxScale::from(TITLE_SCALE), font, header.title);
+    draw_text_mut(&mut sheet, BLACK, SHEET_MARGIN as i32, (SHEET_MARGIN + 56) as i32, PxScale::from(TEXT_SCALE), font, &fit_text(header.counts, CONTENT_WIDTH, font));
+    let page = format!("Page {} of {}", header.page, header.page_count);
+    let (page_width, _) = text_size(PxScale::from(TEXT_SCALE), font, &page);
+    draw_text_mut(&mut sheet, BLACK, (SHEET_WIDTH - SHEET_MARGIN - page_width) as i32, (SHEET_MARGIN + 12) as i32, PxScale::from(TEXT_SCALE), font, &page);
+
+    let top = SHEET_MARGIN + HEADER_HEIGHT;
+    let cell_width = CONTENT_WIDTH / request.columns;
+    let cell_height = CONTENT_HEIGHT / request.rows;
+    let thumbnail_width = cell_width.saturating_sub(2 * CELL_PADDING).max(1);
+    let thumbnail_height = cell_height.saturating_sub(2 * CELL_PADDING + CAPTION_HEIGHT).max(1);
+
+    for (cell, media_file) in (0..).zip(media_files) {
+        let cell_x = SHEET_MARGIN + (cell % request.columns) * cell_width;
+        let cell_y = top + (cell / request.columns) * cell_height;
+        let image = image::open(&media_file.path).map_err(|e| ContactSheetsError::ImageError(media_file.path.clone(), e))?;
+        let thumbnail = image.thumbnail(thumbnail_width, thumbnail_height).to_rgb8();
+        // Centered horizontally and sat on top of the caption
+        let x = cell_x + cell_width.saturating_sub(thumbnail.width()) / 2;
+        let y = cell_y + CELL_PADDING + thumbnail_height.saturating_sub(thumbnail.height());
+        imageops::overlay(&mut sheet, &thumbnail, i64::from(x), i64::from(y));
+
+        let caption = fit_text(&media_file.raw_file_name, thumbnail_width, font);
+        let (caption_width, _) = text_size(PxScale::from(TEXT_SCALE), font, &caption);
+        let caption_x = cell_x + cell_width.saturating_sub(caption_width) / 2;
+        draw_text_mut(&mut sheet, BLACK, caption_x as i32, (cell_y + CELL_PADDING + thumbnail_height + 4) as i32, PxScale::from(TEXT_SCALE), font, &caption);
+    }
+
+    Ok(sheet)
+}
+
+/// Shortens text with an ellipsis until it fits within `max_width` pixels.
+fn fit_text(text: &str, max_width: u32, font: &impl Font) -> String {
+    let fits = |text: &str| text_size(PxScale::from(TEXT_SCALE), font, text).0 <= max_width;
+    if fits(text) {
+        return text.to_string();
+    }
+    let mut characters: Vec<char> = text.chars().collect();
+    while !characters.is_empty() {
+        characters.pop();
+        let shortened = format!("{}...", characters.iter().collect::<String>());
+        if fits(&shortened) {
+            return shortened;
+        }
+    }
+    String::new()
+}
+
+/// "Group" followed by the group code, such as "Group 01A".
+pub fn group_label(media_file: &MediaFile) -> String {
+    match group_code(media_file) {
+        Some(code) => format!("Group {code}"),
+        None => String::from("Ungrouped"),
+    }
+}
+
+/// Group number padded to its precision followed by the group character, such as "01A".
+pub fn group_code(media_file: &MediaFile) -> Option<String> {
+    let number = match (media_file.group_number, media_file.group_number_precision) {
+        (Some(number), Some(precision)) => format!("{number:0precision$}"),
+        _ => String::new(),
+    };
+    let character = media_file.group_character.map(String::from).unwrap_or_default();
+    match number.is_empty() && character.is_empty() {
+        true => None,
+        false => Some(format!("{number}{character}")),
+    }
+}
+
+/// Summarizes counted media, such as "Slides: 40 scanner, 2 handscan | Prints: 12 scanner".
+pub fn describe_counts(counts: &MediaGroupValues) -> String {
+    fn describe(media_type: &str, options: &Option<PhotoGroupOptions>) -> Option<String> {
+        let options = options.as_ref()?;
+        let scan_counts: Vec<String> = [(options.scanner, "scanner"), (options.hs, "handscan"), (options.oshs, "oversized")].iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, scan_type)| format!("{count} {scan_type}"))
+            .collect();
+        Some(format!("{media_type}: {}", scan_counts.join(", ")))
+    }
+
+    [describe("Slides", &counts.slides), describe("Prints", &counts.prints), describe("Negatives", &counts.negatives)]
+        .into_iter().flatten().collect::<Vec<_>>().join(" | ")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::{qc::final_check::{media_file::MediaFile, media_groups::MediaGroupValues, photo_group_options::PhotoGroupOptions}, reporter::SilentReporter, utils::test_files};
+    use super::{describe_counts, generate_contact_sheets, group_code, group_label, error::ContactSheetsError, MAX_COLUMNS, MAX_ROWS};
+
+    fn generate(name: &str, data: serde_json::Value) -> Result<Vec<super::ContactSheet>, ContactSheetsError> {
+        let dir = test_files::temp_dir(name);
+        let mut data = data;
+        data["to_folder"] = json!(dir.join("sheets"));
+        generate_contact_sheets(dir.to_string_lossy().into_owned(), json!({ "data": data }), &mut SilentReporter)
+    }
+
+    #[test]
+    fn rejects_grids_that_dont_fit_the_sheet() {
+        for (columns, rows) in [(0, 5), (4, 0), (MAX_COLUMNS + 1, 5), (4, MAX_ROWS + 1), (u32::MAX, u32::MAX)] {
+            let result = generate("contact-sheets-grid", json!({ "columns": columns, "rows": rows }));
+            assert!(matches!(result, Err(ContactSheetsError::InvalidRequest(_))), "{columns}x{rows} was accepted");
+        }
+    }
+
+    #[test]
+    fn names_font_path_when_the_font_is_missing() {
+        let result = generate("contact-sheets-font", json!({ "font_path": "/no/such/font.ttf" }));
+        let Err(e @ ContactSheetsError::FontNotFound(_)) = result else {
+            panic!("a missing font wasn't reported");
+        };
+        assert!(e.to_string().contains("font_path"));
+    }
+
+    #[test]
+    fn labels_groups_by_number_and_character() {
+        let dir = test_files::temp_dir("contact-sheets-labels");
+        let label = |file_name: &str| {
+            let path = dir.join(file_name);
+            test_files::write_scan(&path, &test_files::gradient(8, 8, [255, 255, 255]));
+            let media_file = MediaFile::from_path(&path).unwrap().unwrap();
+            (group_code(&media_file), group_label(&media_file))
+        };
+        assert_eq!(label("SmithJ_Slides_01_A_001.jpg"), (Some(String::from("01A")), String::from("Group 01A")));
+        assert_eq!(label("SmithJ_Slides_001.jpg"), (None, String::from("Ungrouped")));
+    }
+
+    #[test]
+    fn describes_only_counted_scan_types() {
+        let counts = MediaGroupValues {
+            slides: Some(PhotoGroupOptions { scanner: 40, hs: 2, ..PhotoGroupOptions::new() }),
+            prints: Some(PhotoGroupOptions { scanner: 12, ..PhotoGroupOptions::new() }),
+            negatives: None,
+        };
+        assert_eq!(describe_counts(&counts), "Slides: 40 scanner, 2 handscan | Prints: 12 scanner");
+    }
+}
\ No newline at end of file
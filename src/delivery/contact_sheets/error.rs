@@ -0,0 +1,36 @@
+use glob::{GlobError, PatternError};
+use crate::qc::final_check::{media_file::error::MediaFileParseError, media_groups::error::MediaGroupsError};
+
+#[derive(Debug)]
+pub enum ContactSheetsError {
+    DeserializeError(serde_json::Error),
+    InvalidRequest(String),
+    InvalidDirectory(PatternError),
+    InvalidFile(GlobError),
+    MediaFileParseError(std::path::PathBuf, MediaFileParseError),
+    MediaGroupingError(MediaGroupsError),
+    NoFilesInDirectory(String),
+    FontNotFound(std::path::PathBuf),
+    CouldNotLoadFont(std::path::PathBuf, String),
+    CouldNotCreateFolder(std::path::PathBuf, std::io::Error),
+    ImageError(std::path::PathBuf, image::ImageError),
+}
+impl std::error::Error for ContactSheetsError {}
+impl std::fmt::Display for ContactSheetsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter)
+    -> std::fmt::Result {
+        match self {
+            ContactSheetsError::DeserializeError(err) => write!(f, "could not deserialize request: {err}"),
+            ContactSheetsError::InvalidRequest(err) => write!(f, "invalid request: {err}"),
+            ContactSheetsError::InvalidDirectory(err) => write!(f, "invalid directory: {err}"),
+            ContactSheetsError::InvalidFile(err) => write!(f, "invalid file path: {err}"),
+            ContactSheetsError::MediaFileParseError(path, e) => write!(f, "error parsing {}: {e}", path.file_name().unwrap_or(std::ffi::OsStr::new("invalid file name")).to_string_lossy()),
+            ContactSheetsError::MediaGroupingError(e) => write!(f, "{e}"),
+            ContactSheetsError::NoFilesInDirectory(dir) => write!(f, "no media files found in {dir}"),
+            ContactSheetsError::FontNotFound(path) => write!(f, "font {} was not found, set font_path in the request to a TrueType font", path.to_string_lossy()),
+            ContactSheetsError::CouldNotLoadFont(path, e) => write!(f, "could not load font {}: {e}", path.to_string_lossy()),
+            ContactSheetsError::CouldNotCreateFolder(path, e) => write!(f, "could not create folder {}: {e}", path.to_string_lossy()),
+            ContactSheetsError::ImageError(path, e) => write!(f, "error with {}: {e}", path.to_string_lossy()),
+        }
+    }
+}
\ No newline at end of file
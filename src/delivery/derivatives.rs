@@ -0,0 +1,122 @@
+pub mod error;
+use std::path::{Path, PathBuf};
+use glob::glob;
+use image::imageops::FilterType;
+use little_exif::exif_tag::ExifTag;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use error::DerivativesError;
+use crate::{autocorr::auto_correct_request::JpegExportOptions, qc::final_check::media_file::MediaFile, reporter::Reporter, utils::{image_io, types::{file_extension_type::FileExtensionType, media_types::photo_media_data::ImageTags}}};
+
+#[derive(Deserialize, Debug)]
+struct Data {
+    data: DerivativesRequest
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DerivativesRequest {
+    /// Folder the JPEG copies are written to, which can't be the checked folder itself.
+    pub to_folder: String,
+    #[serde(flatten)]
+    pub jpeg: JpegExportOptions,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Derivative {
+    pub from_file: String,
+    pub to_file: String,
+    pub converted: bool,
+}
+
+
+/// Writes a JPEG access copy of every TIFF media file in a checked folder to `to_folder`, keeping file names
+/// apart from the extension. Media files that are already JPEGs are copied so the new folder can be final checked as a whole.
+pub fn generate_jpegs(dir: String, request_json: Value, reporter: &mut dyn Reporter) -> Result<Vec<Derivative>, DerivativesError> {
+    let request = serde_json::from_value::<Data>(request_json).map_err(DerivativesError::DeserializeError)?.data;
+    if !(1..=100).contains(&request.jpeg.quality) {
+        return Err(DerivativesError::InvalidRequest(format!("quality {} is not between 1 and 100", request.jpeg.quality)))
+    }
+    let to_folder = PathBuf::from(&request.to_folder);
+    std::fs::create_dir_all(&to_folder).map_err(|e| DerivativesError::CouldNotCreateFolder(to_folder.clone(), e))?;
+    if image_io::is_same_folder(Path::new(&dir), &to_folder) {
+        return Err(DerivativesError::InvalidRequest(format!("to_folder {} is the checked folder", to_folder.to_string_lossy())))
+    }
+
+    let mut derivatives = Vec::new();
+    let files = glob(&format!("{dir}\\*")).map_err(DerivativesError::InvalidDirectory)?;
+    for entry in files {
+        let path = entry.map_err(DerivativesError::InvalidFile)?;
+        let Some(media_file) = MediaFile::from_path(&path).map_err(|e| DerivativesError::MediaFileParseError(path.clone(), e))? else {
+            continue;
+        };
+
+        let FileExtensionType::Tiff = media_file.file_extension else {
+            std::fs::copy(&path, to_folder.join(&media_file.raw_file_name)).map_err(|e| DerivativesError::CouldNotCopy(path.clone(), e))?;
+            derivatives.push(Derivative { from_file: media_file.raw_file_name.clone(), to_file: media_file.raw_file_name, converted: false });
+            continue;
+        };
+
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let to_file = format!("{stem}.{}", FileExtensionType::Jpeg);
+        let to_path = to_folder.join(&to_file);
+        reporter.progress(&format!("Converting {} to {to_file}", media_file.raw_file_name));
+        write_jpeg(&path, &to_path, &request.jpeg)?;
+        derivatives.push(Derivative { from_file: media_file.raw_file_name, to_file, converted: true });
+    }
+
+    Ok(derivatives)
+}
+
+/// Writes a TIFF as a JPEG, scaled down to `max_dimension` if it's set, keeping its resolution and other tags.
+fn write_jpeg(path: &Path, to_path: &Path, options: &JpegExportOptions) -> Result<(), DerivativesError> {
+    let mut image = image::open(path).map_err(|e| DerivativesError::ImageError(path.to_path_buf(), e))?;
+    if let Some(max_dimension) = options.max_dimension {
+        if image.width() > max_dimension || image.height() > max_dimension {
+            image = image.resize(max_dimension, max_dimension, FilterType::Lanczos3);
+        }
+    }
+    image_io::save_image(&image, to_path, options.quality).map_err(|e| DerivativesError::ImageError(to_path.to_path_buf(), e))?;
+
+    // TIFF tags aren't EXIF, so they're carried over field by field
+    let tags = ImageTags::from_path(path).map_err(|e| DerivativesError::CouldNotReadTags(path.to_path_buf(), e))?;
+    let mut metadata = tags.to_metadata();
+    metadata.set_tag(ExifTag::ExifImageWidth(vec![image.width()]));
+    metadata.set_tag(ExifTag::ExifImageHeight(vec![image.height()]));
+    metadata.write_to_file(to_path).map_err(|e| DerivativesError::MetadataError(to_path.to_path_buf(), e))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::{autocorr::auto_correct_request::JpegExportOptions, qc::final_check::media_file::MediaFile, reporter::SilentReporter, utils::{test_files, types::media_types::MediaType}};
+    use super::{generate_jpegs, write_jpeg, error::DerivativesError};
+
+    #[test]
+    fn rejects_bad_quality_and_the_checked_folder() {
+        let dir = test_files::temp_dir("derivatives-request").to_string_lossy().into_owned();
+        let to_folder = format!("{dir}-jpegs");
+        for quality in [0, 101] {
+            let result = generate_jpegs(dir.clone(), json!({ "data": { "to_folder": to_folder, "quality": quality } }), &mut SilentReporter);
+            assert!(matches!(result, Err(DerivativesError::InvalidRequest(_))), "quality {quality} was accepted");
+        }
+        let result = generate_jpegs(dir.clone(), json!({ "data": { "to_folder": dir } }), &mut SilentReporter);
+        assert!(matches!(result, Err(DerivativesError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn writes_a_scaled_jpeg_that_keeps_its_dpi() {
+        let dir = test_files::temp_dir("derivatives-convert");
+        let (path, to_path) = (dir.join("SmithJ_Slides_001.tif"), dir.join("SmithJ_Slides_001.jpg"));
+        test_files::write_scan(&path, &test_files::gradient(64, 48, [255, 255, 255]));
+
+        write_jpeg(&path, &to_path, &JpegExportOptions { quality: 90, max_dimension: Some(32) }).unwrap();
+
+        let image = image::open(&to_path).unwrap();
+        assert_eq!((image.width(), image.height()), (32, 24));
+        let MediaType::Slides(data) = MediaFile::from_path(&to_path).unwrap().unwrap().media_type else {
+            panic!("not parsed as a slide");
+        };
+        assert_eq!(data.dpi, 300);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,34 @@
+use glob::{GlobError, PatternError};
+use crate::{qc::final_check::media_file::error::MediaFileParseError, utils::types::media_types::photo_media_data::error::PhotoMediaDataError};
+
+#[derive(Debug)]
+pub enum DerivativesError {
+    DeserializeError(serde_json::Error),
+    InvalidRequest(String),
+    InvalidDirectory(PatternError),
+    InvalidFile(GlobError),
+    MediaFileParseError(std::path::PathBuf, MediaFileParseError),
+    CouldNotCreateFolder(std::path::PathBuf, std::io::Error),
+    CouldNotCopy(std::path::PathBuf, std::io::Error),
+    ImageError(std::path::PathBuf, image::ImageError),
+    CouldNotReadTags(std::path::PathBuf, PhotoMediaDataError),
+    MetadataError(std::path::PathBuf, std::io::Error),
+}
+impl std::error::Error for DerivativesError {}
+impl std::fmt::Display for DerivativesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter)
+    -> std::fmt::Result {
+        match self {
+            DerivativesError::DeserializeError(err) => write!(f, "could not deserialize request: {err}"),
+            DerivativesError::InvalidRequest(err) => write!(f, "invalid request: {err}"),
+            DerivativesError::InvalidDirectory(err) => write!(f, "invalid directory: {err}"),
+            DerivativesError::InvalidFile(err) => write!(f, "invalid file path: {err}"),
+            DerivativesError::MediaFileParseError(path, e) => write!(f, "error parsing {}: {e}", path.file_name().unwrap_or(std::ffi::OsStr::new("invalid file name")).to_string_lossy()),
+            DerivativesError::CouldNotCreateFolder(path, e) => write!(f, "could not create folder {}: {e}", path.to_string_lossy()),
+            DerivativesError::CouldNotCopy(path, e) => write!(f, "could not copy {}: {e}", path.to_string_lossy()),
+            DerivativesError::ImageError(path, e) => write!(f, "could not convert {}: {e}", path.to_string_lossy()),
+            DerivativesError::CouldNotReadTags(path, e) => write!(f, "could not read tags of {}: {e}", path.to_string_lossy()),
+            DerivativesError::MetadataError(path, e) => write!(f, "could not write metadata to {}: {e}", path.to_string_lossy()),
+        }
+    }
+}
\ No newline at end of file
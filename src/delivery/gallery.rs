@@ -0,0 +1,276 @@
+pub mod error;
+use std::{collections::BTreeMap, path::{Path, PathBuf}};
+use glob::glob;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use error::GalleryError;
+use crate::{delivery::contact_sheets::group_label, qc::final_check::media_file::MediaFile, reporter::Reporter, utils::{image_io, paths, types::file_extension_type::FileExtensionType}};
+
+#[derive(Deserialize, Debug)]
+struct Data {
+    data: GalleryRequest
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GalleryRequest {
+    /// Folder the page and its images are written to, which can't be inside the order folder.
+    pub to_folder: String,
+    /// Shown at the top of the page. Defaults to the client's name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default = "default_thumbnail_size")]
+    pub thumbnail_size: u32,
+    /// Largest side of the copies shown in the lightbox for images browsers can't display, such as TIFFs.
+    #[serde(default = "default_preview_size")]
+    pub preview_size: u32,
+    #[serde(default = "default_jpeg_quality")]
+    pub jpeg_quality: u8,
+}
+fn default_thumbnail_size() -> u32 { 320 }
+fn default_preview_size() -> u32 { 1600 }
+fn default_jpeg_quality() -> u8 { 85 }
+
+#[derive(Serialize, Debug)]
+pub struct GalleryExport {
+    pub index_file: String,
+    pub images: usize,
+    pub sections: Vec<String>,
+}
+
+/// Name of the gallery's index page, written to the root of `to_folder`.
+const INDEX_FILE: &str = "index.html";
+/// Folder in `to_folder` the thumbnails and previews are written to.
+const ASSETS_FOLDER: &str = "gallery";
+
+struct GalleryImage {
+    name: String,
+    thumbnail: String,
+    preview: String,
+    original: String,
+}
+
+
+/// Writes a self-contained HTML gallery of every media file in an order folder to `to_folder`, grouped by media type and group.
+/// All links are relative, so the gallery can be opened straight from a USB drive holding both folders.
+pub fn export_gallery(dir: String, request_json: Value, reporter: &mut dyn Reporter) -> Result<GalleryExport, GalleryError> {
+    let request = serde_json::from_value::<Data>(request_json).map_err(GalleryError::DeserializeError)?.data;
+    if request.thumbnail_size == 0 || request.preview_size == 0 {
+        return Err(GalleryError::InvalidRequest(String::from("thumbnail_size and preview_size must be at least 1")))
+    }
+    if !(1..=100).contains(&request.jpeg_quality) {
+        return Err(GalleryError::InvalidRequest(format!("jpeg_quality {} is not between 1 and 100", request.jpeg_quality)))
+    }
+    let root = PathBuf::from(&dir);
+    let canonical_root = paths::canonical_dir(&root);
+    // Anything written inside the order folder would be picked up by later checks as an unexpected file
+    let to_folder = paths::canonical_new_dir(Path::new(&request.to_folder));
+    if to_folder.starts_with(&canonical_root) {
+        return Err(GalleryError::InvalidRequest(format!("to_folder {} is inside the order folder", request.to_folder)))
+    }
+    std::fs::create_dir_all(&to_folder).map_err(|e| GalleryError::CouldNotCreateFolder(to_folder.clone(), e))?;
+
+    let pattern = format!("{dir}\\**\\*");
+    let mut sections: BTreeMap<(String, Option<u32>, Option<char>), Vec<MediaFile>> = BTreeMap::new();
+    for entry in glob(&pattern).map_err(GalleryError::InvalidDirectory)? {
+        let path = entry.map_err(GalleryError::InvalidFile)?;
+        if !image_io::is_image_file(&path) || !paths::resolves_inside(&path, &canonical_root) {
+            continue;
+        }
+        if let Some(media_file) = MediaFile::from_path(&path).map_err(|e| GalleryError::MediaFileParseError(path.clone(), e))? {
+            sections.entry((media_file.media_type.to_string(), media_file.group_number, media_file.group_character)).or_default().push(media_file);
+        }
+    }
+    let Some(first_file) = sections.values().next().and_then(|media_files| media_files.first()) else {
+        return Err(GalleryError::NoFilesInDirectory(pattern));
+    };
+    let title = request.title.clone().unwrap_or_else(|| format!("{}, {}", first_file.last_name, first_file.first_name_initial));
+
+    let mut html_sections = Vec::new();
+    let mut section_names = Vec::new();
+    let mut image_count = 0;
+    for ((media_type, _, _), media_files) in sections.iter_mut() {
+        media_files.sort_by_key(|media_file| media_file.index_number);
+        let section_name = format!("{media_type} - {}", group_label(&media_files[0]));
+        reporter.progress(&format!("Building gallery section {section_name}"));
+
+        let mut images = Vec::with_capacity(media_files.len());
+        for media_file in media_files.iter() {
+            images.push(write_gallery_image(media_file, &root, &to_folder, &request)?);
+        }
+        html_sections.push(render_section(&section_name, &images, image_count));
+        image_count += images.len();
+        section_names.push(section_name);
+    }
+
+    let index_path = to_folder.join(INDEX_FILE);
+    std::fs::write(&index_path, render_page(&title, &html_sections, image_count)).map_err(|e| GalleryError::CouldNotWrite(index_path.clone(), e))?;
+
+    Ok(GalleryExport { index_file: INDEX_FILE.to_string(), images: image_count, sections: section_names })
+}
+
+/// Writes the thumbnail, and a preview if browsers can't show the original, mirroring the file's place in the order folder.
+fn write_gallery_image(media_file: &MediaFile, root: &Path, to_folder: &Path, request: &GalleryRequest) -> Result<GalleryImage, GalleryError> {
+    let relative_path = media_file.path.strip_prefix(root).unwrap_or(&media_file.path).to_path_buf();
+    let asset_path = |kind: &str| to_folder.join(ASSETS_FOLDER).join(kind).join(&relative_path).with_extension(FileExtensionType::Jpeg.to_string());
+    let original_path = paths::canonical_dir(&media_file.path);
+
+    let image = image::open(&media_file.path).map_err(|e| GalleryError::ImageError(media_file.path.clone(), e))?;
+    let thumbnail_path = asset_path("thumbnails");
+    save_jpeg(&image.thumbnail(request.thumbnail_size, request.thumbnail_size), &thumbnail_path, request.jpeg_quality)?;
+
+    let preview_path = match media_file.file_extension {
+        FileExtensionType::Jpeg => original_path.clone(),
+        _ => {
+            let preview_path = asset_path("previews");
+            save_jpeg(&image.thumbnail(request.preview_size, request.preview_size), &preview_path, request.jpeg_quality)?;
+            preview_path
+        }
+    };
+
+    Ok(GalleryImage {
+        name: media_file.raw_file_name.clone(),
+        thumbnail: relative_url(&thumbnail_path, to_folder),
+        preview: relative_url(&preview_path, to_folder),
+        original: relative_url(&original_path, to_folder),
+    })
+}
+
+fn save_jpeg(image: &image::DynamicImage, path: &Path, quality: u8) -> Result<(), GalleryError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| GalleryError::CouldNotCreateFolder(parent.to_path_buf(), e))?;
+    }
+    image_io::save_image(image, path, quality).map_err(|e| GalleryError::ImageError(path.to_path_buf(), e))
+}
+
+/// Percent-encoded link to a path from the gallery folder, using forward slashes on every platform. Both paths
+/// must be resolved, and paths that share no root with the gallery, such as ones on another drive, are linked absolutely.
+fn relative_url(path: &Path, from_folder: &Path) -> String {
+    let shared = path.components().zip(from_folder.components()).take_while(|(a, b)| a == b).count();
+    if shared == 0 {
+        let absolute_path = path.to_string_lossy().replace('\\', "/");
+        return format!("file:///{}", absolute_path.trim_start_matches("//?/").trim_start_matches('/').split('/').map(encode_url_segment).collect::<Vec<_>>().join("/"));
+    }
+    let parents = std::iter::repeat_n(String::from(".."), from_folder.components().count() - shared);
+    let remaining = path.components().skip(shared).map(|component| encode_url_segment(&component.as_os_str().to_string_lossy()));
+    parents.chain(remaining).collect::<Vec<_>>().join("/")
+}
+
+fn encode_url_segment(segment: &str) -> String {
+    segment.bytes().map(|byte| match byte {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b':' => (byte as char).to_string(),
+        _ => format!("%{byte:02X}"),
+    }).collect()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&#39;")
+}
+
+/// Renders a section's thumbnail grid and a lightbox for each image, numbered from `first_index`.
+fn render_section(name: &str, images: &[GalleryImage], first_index: usize) -> String {
+    let mut html = format!("<section>\n<h2>{}</h2>\n<div class=\"grid\">\n", escape_html(name));
+    for (index, image) in (first_index..).zip(images) {
+        html.push_str(&format!(
+            "<a class=\"thumb\" href=\"#image-{index}\"><img src=\"{}\" alt=\"{name}\" loading=\"lazy\"><span>{name}</span></a>\n",
+            image.thumbnail, name = escape_html(&image.name)));
+    }
+    html.push_str("</div>\n");
+    for (index, image) in (first_index..).zip(images) {
+        html.push_str(&format!(
+            "<div class=\"lightbox\" id=\"image-{index}\">\n<a class=\"close\" href=\"#\" title=\"Close\"></a>\n<figure><img src=\"{}\" alt=\"{name}\" loading=\"lazy\">\
+            <figcaption>{name} &middot; <a href=\"{}\" download>Download original</a></figcaption></figure>\n\
+            <a class=\"nav previous\" href=\"#image-{}\">&#8249;</a><a class=\"nav next\" href=\"#image-{}\">&#8250;</a>\n</div>\n",
+            image.preview, image.original, index.saturating_sub(1), index + 1, name = escape_html(&image.name)));
+    }
+    html.push_str("</section>\n");
+    html
+}
+
+/// Wraps the sections in a page with inline styles and keyboard navigation, so the gallery needs no other files.
+fn render_page(title: &str, sections: &[String], image_count: usize) -> String {
+    let title = escape_html(title);
+    format!(r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title}</title>
+<style>
+body {{ margin: 0; padding: 24px; font-family: system-ui, sans-serif; background: #f4f4f4; color: #222; }}
+h1 {{ margin-top: 0; }}
+h2 {{ border-bottom: 1px solid #ccc; padding-bottom: 4px; }}
+.grid {{ display: grid; grid-template-columns: repeat(auto-fill, minmax(200px, 1fr)); gap: 16px; }}
+.thumb {{ display: flex; flex-direction: column; align-items: center; text-decoration: none; color: inherit; background: #fff; padding: 8px; border-radius: 4px; }}
+.thumb img {{ max-width: 100%; height: 180px; object-fit: contain; }}
+.thumb span {{ margin-top: 6px; font-size: 12px; word-break: break-all; text-align: center; }}
+.lightbox {{ display: none; position: fixed; inset: 0; background: rgba(0, 0, 0, 0.9); z-index: 10; align-items: center; justify-content: center; }}
+.lightbox:target {{ display: flex; }}
+.lightbox .close {{ position: absolute; inset: 0; }}
+.lightbox figure {{ position: relative; margin: 0; text-align: center; color: #eee; pointer-events: none; }}
+.lightbox figure img {{ max-width: 90vw; max-height: 85vh; }}
+.lightbox figcaption {{ margin-top: 8px; pointer-events: auto; }}
+.lightbox figcaption a {{ color: #9cf; }}
+.lightbox .nav {{ position: absolute; top: 50%; transform: translateY(-50%); font-size: 64px; color: #eee; text-decoration: none; padding: 16px; }}
+.lightbox .previous {{ left: 8px; }}
+.lightbox .next {{ right: 8px; }}
+#image-0 .previous, #image-{last} .next {{ display: none; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<p>{image_count} images</p>
+{sections}<script>
+document.addEventListener("keydown", function (event) {{
+    var current = document.querySelector(".lightbox:target");
+    if (!current) return;
+    var link = {{ ArrowLeft: ".previous", ArrowRight: ".next", Escape: ".close" }}[event.key];
+    if (link) current.querySelector(link).click();
+}});
+</script>
+</body>
+</html>
+"##, last = image_count.saturating_sub(1), sections = sections.concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use serde_json::json;
+    use crate::{reporter::SilentReporter, utils::test_files};
+    use super::{escape_html, export_gallery, relative_url, error::GalleryError};
+
+    #[test]
+    fn refuses_to_write_inside_the_order_folder() {
+        let dir = test_files::temp_dir("gallery-to-folder");
+        for to_folder in [dir.clone(), dir.join("gallery"), dir.join("..").join(dir.file_name().unwrap()).join("new")] {
+            let result = export_gallery(dir.to_string_lossy().into_owned(), json!({ "data": { "to_folder": to_folder } }), &mut SilentReporter);
+            assert!(matches!(result, Err(GalleryError::InvalidRequest(_))), "{} was accepted", to_folder.to_string_lossy());
+        }
+        assert!(!dir.join("gallery").exists());
+    }
+
+    #[test]
+    fn rejects_bad_sizes_and_quality() {
+        let dir = test_files::temp_dir("gallery-sizes");
+        let to_folder = dir.with_extension("out");
+        for data in [json!({ "thumbnail_size": 0 }), json!({ "preview_size": 0 }), json!({ "jpeg_quality": 101 })] {
+            let mut data = data;
+            data["to_folder"] = json!(to_folder);
+            let result = export_gallery(dir.to_string_lossy().into_owned(), json!({ "data": data }), &mut SilentReporter);
+            assert!(matches!(result, Err(GalleryError::InvalidRequest(_))), "{data} was accepted");
+        }
+    }
+
+    #[test]
+    fn links_relative_to_the_gallery_folder() {
+        let gallery = Path::new("/drive/Smith gallery");
+        assert_eq!(relative_url(Path::new("/drive/Smith gallery/gallery/thumbnails/a.jpg"), gallery), "gallery/thumbnails/a.jpg");
+        assert_eq!(relative_url(Path::new("/drive/Smith order/01/SmithJ_Slides_01_001.tif"), gallery), "../Smith%20order/01/SmithJ_Slides_01_001.tif");
+        assert_eq!(relative_url(Path::new("D:/Orders/a#1.jpg"), gallery), "file:///D:/Orders/a%231.jpg");
+    }
+
+    #[test]
+    fn escapes_html() {
+        assert_eq!(escape_html("<b>\"O'Neil\" & co</b>"), "&lt;b&gt;&quot;O&#39;Neil&quot; &amp; co&lt;/b&gt;");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,32 @@
+use glob::{GlobError, PatternError};
+use crate::qc::final_check::media_file::error::MediaFileParseError;
+
+#[derive(Debug)]
+pub enum GalleryError {
+    DeserializeError(serde_json::Error),
+    InvalidRequest(String),
+    InvalidDirectory(PatternError),
+    InvalidFile(GlobError),
+    MediaFileParseError(std::path::PathBuf, MediaFileParseError),
+    NoFilesInDirectory(String),
+    CouldNotCreateFolder(std::path::PathBuf, std::io::Error),
+    ImageError(std::path::PathBuf, image::ImageError),
+    CouldNotWrite(std::path::PathBuf, std::io::Error),
+}
+impl std::error::Error for GalleryError {}
+impl std::fmt::Display for GalleryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter)
+    -> std::fmt::Result {
+        match self {
+            GalleryError::DeserializeError(err) => write!(f, "could not deserialize request: {err}"),
+            GalleryError::InvalidRequest(err) => write!(f, "invalid request: {err}"),
+            GalleryError::InvalidDirectory(err) => write!(f, "invalid directory: {err}"),
+            GalleryError::InvalidFile(err) => write!(f, "invalid file path: {err}"),
+            GalleryError::MediaFileParseError(path, e) => write!(f, "error parsing {}: {e}", path.file_name().unwrap_or(std::ffi::OsStr::new("invalid file name")).to_string_lossy()),
+            GalleryError::NoFilesInDirectory(dir) => write!(f, "no media files found in {dir}"),
+            GalleryError::CouldNotCreateFolder(path, e) => write!(f, "could not create folder {}: {e}", path.to_string_lossy()),
+            GalleryError::ImageError(path, e) => write!(f, "error with {}: {e}", path.to_string_lossy()),
+            GalleryError::CouldNotWrite(path, e) => write!(f, "could not write {}: {e}", path.to_string_lossy()),
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,235 @@
+pub mod error;
+use std::{collections::BTreeMap, path::{Component, Path, PathBuf}};
+use glob::glob;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use error::ManifestError;
+use crate::{reporter::Reporter, utils::{hashing, paths}};
+
+#[derive(Deserialize, Debug)]
+struct WriteData {
+    data: WriteManifestRequest
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WriteManifestRequest {
+    /// File name the manifest is written under at the root of the order folder. An earlier manifest with
+    /// the name is replaced, but any other file is left alone.
+    #[serde(default = "default_manifest_name")]
+    pub manifest_name: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct VerifyData {
+    data: VerifyManifestRequest
+}
+
+#[derive(Deserialize, Debug)]
+pub struct VerifyManifestRequest {
+    /// Defaults to the manifest at the root of the copy being verified.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manifest_path: Option<String>,
+}
+fn default_manifest_name() -> String { String::from(DEFAULT_MANIFEST_NAME) }
+
+#[derive(Serialize, Debug)]
+pub struct ManifestSummary {
+    pub manifest_file: String,
+    pub files: usize,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct ManifestVerification {
+    pub verified: usize,
+    /// Listed in the manifest but not in the copy.
+    pub missing: Vec<String>,
+    /// In the copy but not listed in the manifest.
+    pub extra: Vec<String>,
+    /// In both, but with different contents.
+    pub mismatched: Vec<String>,
+}
+impl ManifestVerification {
+    pub fn is_intact(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+pub const DEFAULT_MANIFEST_NAME: &str = "manifest-sha256.txt";
+
+
+/// Writes a SHA-256 manifest of every file in an order folder, in the format `sha256sum -c` checks.
+pub fn write_manifest(dir: String, request_json: Value, reporter: &mut dyn Reporter) -> Result<ManifestSummary, ManifestError> {
+    let request = serde_json::from_value::<WriteData>(request_json).map_err(ManifestError::DeserializeError)?.data;
+    let root = PathBuf::from(&dir);
+    let mut components = Path::new(&request.manifest_name).components();
+    if !matches!((components.next(), components.next()), (Some(Component::Normal(_)), None)) {
+        return Err(ManifestError::InvalidManifestName(request.manifest_name))
+    }
+    let manifest_path = root.join(&request.manifest_name);
+    if manifest_path.exists() && !std::fs::read_to_string(&manifest_path).is_ok_and(|manifest| parse_manifest(&manifest).is_ok()) {
+        return Err(ManifestError::NotAManifest(manifest_path))
+    }
+
+    let files = collect_files(&dir, &root, &manifest_path)?;
+    if files.is_empty() {
+        return Err(ManifestError::NoFilesInDirectory(dir));
+    }
+    let mut manifest = String::new();
+    for (number, (relative_path, path)) in (1..).zip(&files) {
+        reporter.progress(&format!("Hashing {number}/{}: {relative_path}", files.len()));
+        let hash = hashing::sha256_file(path).map_err(|e| ManifestError::CouldNotHash(path.clone(), e))?;
+        manifest.push_str(&format_line(&hash, relative_path));
+    }
+    std::fs::write(&manifest_path, manifest).map_err(|e| ManifestError::CouldNotWriteManifest(manifest_path.clone(), e))?;
+
+    Ok(ManifestSummary { manifest_file: request.manifest_name, files: files.len() })
+}
+
+/// Re-hashes a copy of an order against its manifest, reporting missing, extra and mismatched files.
+pub fn verify_manifest(dir: String, request_json: Value, reporter: &mut dyn Reporter) -> Result<ManifestVerification, ManifestError> {
+    let request = serde_json::from_value::<VerifyData>(request_json).map_err(ManifestError::DeserializeError)?.data;
+    let root = PathBuf::from(&dir);
+    let manifest_path = request.manifest_path.map(PathBuf::from).unwrap_or_else(|| root.join(DEFAULT_MANIFEST_NAME));
+    let manifest = std::fs::read_to_string(&manifest_path).map_err(|e| ManifestError::CouldNotReadManifest(manifest_path.clone(), e))?;
+    let mut expected = parse_manifest(&manifest)?;
+
+    let files = collect_files(&dir, &root, &manifest_path)?;
+    let mut verification = ManifestVerification::default();
+    for (number, (relative_path, path)) in (1..).zip(&files) {
+        let Some(expected_hash) = expected.remove(relative_path) else {
+            verification.extra.push(relative_path.clone());
+            continue;
+        };
+        reporter.progress(&format!("Verifying {number}/{}: {relative_path}", files.len()));
+        let hash = hashing::sha256_file(path).map_err(|e| ManifestError::CouldNotHash(path.clone(), e))?;
+        match hash.eq_ignore_ascii_case(&expected_hash) {
+            true => verification.verified += 1,
+            false => verification.mismatched.push(relative_path.clone()),
+        }
+    }
+    verification.missing = expected.into_keys().collect();
+
+    Ok(verification)
+}
+
+/// Every file under the order folder keyed by its path relative to the root with forward slashes, besides the manifest,
+/// thumbnail caches and symlinks leading outside the folder.
+fn collect_files(dir: &str, root: &Path, manifest_path: &Path) -> Result<BTreeMap<String, PathBuf>, ManifestError> {
+    let mut files = BTreeMap::new();
+    let canonical_root = paths::canonical_dir(root);
+    for entry in glob(&format!("{dir}\\**\\*")).map_err(ManifestError::InvalidDirectory)? {
+        let path = entry.map_err(ManifestError::InvalidFile)?;
+        if !path.is_file() || path == manifest_path || path.file_name().is_some_and(|name| name.eq_ignore_ascii_case("thumbs.db"))
+            || !paths::resolves_inside(&path, &canonical_root) {
+            continue;
+        }
+        let relative_path = path.strip_prefix(root).unwrap_or(&path).components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("/");
+        files.insert(relative_path, path);
+    }
+
+    Ok(files)
+}
+
+/// Formats a line as `sha256sum` does, escaping backslashes and newlines in the name and marking the line with a leading backslash if it had any.
+fn format_line(hash: &str, relative_path: &str) -> String {
+    match relative_path.contains(['\\', '\n']) {
+        true => format!("\\{hash}  {}\n", relative_path.replace('\\', "\\\\").replace('\n', "\\n")),
+        false => format!("{hash}  {relative_path}\n"),
+    }
+}
+
+/// Reads `sha256sum` output, in text or binary mode, into a map of relative paths to hashes.
+fn parse_manifest(manifest: &str) -> Result<BTreeMap<String, String>, ManifestError> {
+    let mut hashes = BTreeMap::new();
+    for (line_number, line) in (1..).zip(manifest.lines()) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let invalid_line = || ManifestError::InvalidManifestLine(line_number, line.to_string());
+        let (escaped, line) = match line.strip_prefix('\\') {
+            Some(line) => (true, line),
+            None => (false, line),
+        };
+        let hash = line.get(..64).filter(|hash| hash.chars().all(|c| c.is_ascii_hexdigit())).ok_or_else(invalid_line)?;
+        let name = line.get(64..).and_then(|rest| rest.strip_prefix("  ").or_else(|| rest.strip_prefix(" *"))).ok_or_else(invalid_line)?;
+        let name = match escaped {
+            true => unescape_name(name),
+            false => name.to_string(),
+        };
+        hashes.insert(name.strip_prefix("./").unwrap_or(&name).to_string(), hash.to_lowercase());
+    }
+
+    Ok(hashes)
+}
+
+fn unescape_name(name: &str) -> String {
+    let mut unescaped = String::with_capacity(name.len());
+    let mut characters = name.chars();
+    while let Some(character) = characters.next() {
+        match character {
+            '\\' => match characters.next() {
+                Some('n') => unescaped.push('\n'),
+                Some(next) => unescaped.push(next),
+                None => unescaped.push('\\'),
+            },
+            character => unescaped.push(character),
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::{reporter::SilentReporter, utils::test_files};
+    use super::{format_line, parse_manifest, verify_manifest, write_manifest, error::ManifestError};
+
+    const HASH: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    #[test]
+    fn rejects_manifest_names_with_folders() {
+        let dir = test_files::temp_dir("manifest-names");
+        for name in ["../manifest.txt", "/tmp/manifest.txt", "sub/manifest.txt", ""] {
+            let result = write_manifest(dir.to_string_lossy().into_owned(), json!({ "data": { "manifest_name": name } }), &mut SilentReporter);
+            assert!(matches!(result, Err(ManifestError::InvalidManifestName(_))), "{name} was accepted");
+        }
+    }
+
+    #[test]
+    fn leaves_files_that_are_not_manifests_alone() {
+        let dir = test_files::temp_dir("manifest-overwrite");
+        std::fs::write(dir.join("notes.txt"), "call the customer").unwrap();
+        let result = write_manifest(dir.to_string_lossy().into_owned(), json!({ "data": { "manifest_name": "notes.txt" } }), &mut SilentReporter);
+        assert!(matches!(result, Err(ManifestError::NotAManifest(_))));
+        assert_eq!(std::fs::read_to_string(dir.join("notes.txt")).unwrap(), "call the customer");
+    }
+
+    #[test]
+    fn reads_back_escaped_names() {
+        let manifest = [format_line(HASH, "01/a.jpg"), format_line(HASH, "01\\b\nc.jpg")].concat();
+        assert!(manifest.ends_with(&format!("\\{HASH}  01\\\\b\\nc.jpg\n")));
+        let hashes = parse_manifest(&manifest).unwrap();
+        assert_eq!(hashes.keys().collect::<Vec<_>>(), ["01/a.jpg", "01\\b\nc.jpg"]);
+
+        let binary_mode = format!("{} *./01/a.jpg\n", HASH.to_uppercase());
+        assert_eq!(parse_manifest(&binary_mode).unwrap().get("01/a.jpg").map(String::as_str), Some(HASH));
+    }
+
+    #[test]
+    fn rejects_lines_that_are_not_checksums() {
+        for line in ["not a checksum", &format!("{}  a.jpg", &HASH[1..]), &format!("{HASH}a.jpg"), &format!("{}g  a.jpg", &HASH[1..])] {
+            let manifest = format!("{HASH}  ok.jpg\n{line}\n");
+            assert!(matches!(parse_manifest(&manifest), Err(ManifestError::InvalidManifestLine(2, _))), "{line} was accepted");
+        }
+    }
+
+    #[test]
+    fn reports_a_missing_manifest() {
+        let dir = test_files::temp_dir("manifest-missing");
+        let result = verify_manifest(dir.to_string_lossy().into_owned(), json!({ "data": {} }), &mut SilentReporter);
+        assert!(matches!(result, Err(ManifestError::CouldNotReadManifest(..))));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,33 @@
+use glob::{GlobError, PatternError};
+
+#[derive(Debug)]
+pub enum ManifestError {
+    DeserializeError(serde_json::Error),
+    InvalidDirectory(PatternError),
+    InvalidFile(GlobError),
+    NoFilesInDirectory(String),
+    InvalidManifestName(String),
+    NotAManifest(std::path::PathBuf),
+    CouldNotHash(std::path::PathBuf, std::io::Error),
+    CouldNotReadManifest(std::path::PathBuf, std::io::Error),
+    CouldNotWriteManifest(std::path::PathBuf, std::io::Error),
+    InvalidManifestLine(usize, String),
+}
+impl std::error::Error for ManifestError {}
+impl std::fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter)
+    -> std::fmt::Result {
+        match self {
+            ManifestError::DeserializeError(err) => write!(f, "could not deserialize request: {err}"),
+            ManifestError::InvalidDirectory(err) => write!(f, "invalid directory: {err}"),
+            ManifestError::InvalidFile(err) => write!(f, "invalid file path: {err}"),
+            ManifestError::NoFilesInDirectory(dir) => write!(f, "no files found in {dir}"),
+            ManifestError::InvalidManifestName(name) => write!(f, "manifest name \"{name}\" must be a file name without a folder"),
+            ManifestError::NotAManifest(path) => write!(f, "{} already exists and is not a manifest, so it won't be overwritten", path.to_string_lossy()),
+            ManifestError::CouldNotHash(path, e) => write!(f, "could not hash {}: {e}", path.to_string_lossy()),
+            ManifestError::CouldNotReadManifest(path, e) => write!(f, "could not read manifest {}: {e}", path.to_string_lossy()),
+            ManifestError::CouldNotWriteManifest(path, e) => write!(f, "could not write manifest {}: {e}", path.to_string_lossy()),
+            ManifestError::InvalidManifestLine(line_number, line) => write!(f, "line {line_number} of the manifest is not a SHA-256 checksum line: {line}"),
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,7 @@
+pub mod derivatives;
+pub mod contact_sheets;
+pub mod gallery;
+pub mod packaging;
+pub mod manifest;
+pub mod bagit;
+pub mod order_manifest;
\ No newline at end of file
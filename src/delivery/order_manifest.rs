@@ -0,0 +1,232 @@
+pub mod error;
+use std::path::{Path, PathBuf};
+use glob::glob;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use error::OrderManifestError;
+use crate::{delivery::contact_sheets::group_code, qc::final_check::{media_file::MediaFile, media_groups::MediaGroupValues}, reporter::Reporter, utils::{hashing, image_io, paths, types::{media_types::MediaType, scan_type::ScanType}}};
+
+#[derive(Deserialize, Debug)]
+struct Data {
+    data: OrderManifestRequest
+}
+
+#[derive(Deserialize, Debug)]
+pub struct OrderManifestRequest {
+    pub to_folder: String,
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    #[default]
+    Both,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ManifestEntry {
+    pub file_name: String,
+    pub media_type: String,
+    pub scan_type: String,
+    pub group: String,
+    pub index: u32,
+    pub dpi: u64,
+    pub width: u32,
+    pub height: u32,
+    pub size_bytes: u64,
+    /// SHA-256 of the file's contents, so a later delivery of the order can be compared against this one.
+    pub content_hash: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ManifestTotal {
+    pub media_type: String,
+    pub scan_type: String,
+    pub count: u64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct OrderManifest {
+    pub files: Vec<ManifestEntry>,
+    pub totals: Vec<ManifestTotal>,
+    pub total_files: usize,
+    pub total_bytes: u64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct OrderManifestExport {
+    pub files: Vec<String>,
+    pub total_files: usize,
+    pub totals: Vec<ManifestTotal>,
+}
+
+
+/// Writes every media file of an order with its totals per media and scan type, as CSV and/or JSON for accounting to import.
+/// CSV exports are split into one file for the media files and one for the totals, so each imports as a single table.
+pub fn export_order_manifest(dir: String, request_json: Value, reporter: &mut dyn Reporter) -> Result<OrderManifestExport, OrderManifestError> {
+    let request = serde_json::from_value::<Data>(request_json).map_err(OrderManifestError::DeserializeError)?.data;
+    let to_folder = PathBuf::from(&request.to_folder);
+    std::fs::create_dir_all(&to_folder).map_err(|e| OrderManifestError::CouldNotCreateFolder(to_folder.clone(), e))?;
+
+    let canonical_root = paths::canonical_dir(Path::new(&dir));
+    let pattern = format!("{dir}\\**\\*");
+    let mut media_files = Vec::new();
+    for entry in glob(&pattern).map_err(OrderManifestError::InvalidDirectory)? {
+        let path = entry.map_err(OrderManifestError::InvalidFile)?;
+        if !image_io::is_image_file(&path) || !paths::resolves_inside(&path, &canonical_root) {
+            continue;
+        }
+        if let Some(media_file) = MediaFile::from_path(&path).map_err(|e| OrderManifestError::MediaFileParseError(path.clone(), e))? {
+            media_files.push(media_file);
+        }
+    }
+    if media_files.is_empty() {
+        return Err(OrderManifestError::NoFilesInDirectory(pattern));
+    }
+    media_files.sort_by_key(|media_file| (media_file.media_type.to_string(), media_file.group_number, media_file.group_character, media_file.index_number));
+
+    reporter.progress(&format!("Reading {} media files", media_files.len()));
+    let manifest = build_manifest(&media_files)?;
+    let order_name = Path::new(&dir).file_name().unwrap_or_default().to_string_lossy().into_owned();
+
+    let mut files = Vec::new();
+    if matches!(request.format, ExportFormat::Csv | ExportFormat::Both) {
+        let entry_rows = manifest.files.iter().map(|entry| vec![
+            entry.file_name.clone(), entry.media_type.clone(), entry.scan_type.clone(), entry.group.clone(), entry.index.to_string(),
+            entry.dpi.to_string(), entry.width.to_string(), entry.height.to_string(), entry.size_bytes.to_string(), entry.content_hash.clone(),
+        ]).collect();
+        let total_rows = manifest.totals.iter()
+            .map(|total| vec![total.media_type.clone(), total.scan_type.clone(), total.count.to_string()])
+            .chain(std::iter::once(vec![String::from("All"), String::from("All"), manifest.total_files.to_string()]))
+            .collect();
+        files.push(write_csv(&to_folder, &format!("{order_name}_order_manifest.csv"),
+            &["file_name", "media_type", "scan_type", "group", "index", "dpi", "width", "height", "size_bytes", "content_hash"], entry_rows)?);
+        files.push(write_csv(&to_folder, &format!("{order_name}_order_totals.csv"), &["media_type", "scan_type", "count"], total_rows)?);
+    }
+    if matches!(request.format, ExportFormat::Json | ExportFormat::Both) {
+        let file_name = format!("{order_name}_order_manifest.json");
+        let path = to_folder.join(&file_name);
+        let json = serde_json::to_string_pretty(&manifest).map_err(OrderManifestError::SerializeError)?;
+        std::fs::write(&path, json).map_err(|e| OrderManifestError::CouldNotWrite(path, e))?;
+        files.push(file_name);
+    }
+
+    Ok(OrderManifestExport { files, total_files: manifest.total_files, totals: manifest.totals })
+}
+
+fn build_manifest(media_files: &[MediaFile]) -> Result<OrderManifest, OrderManifestError> {
+    let mut entries = Vec::with_capacity(media_files.len());
+    let mut total_bytes = 0;
+    for media_file in media_files {
+        let (MediaType::Slides(photo_data) | MediaType::Prints(photo_data) | MediaType::Negatives(photo_data)) = media_file.media_type;
+        let (width, height) = image::image_dimensions(&media_file.path).map_err(|e| OrderManifestError::ImageError(media_file.path.clone(), e))?;
+        let size_bytes = std::fs::metadata(&media_file.path).map_err(|e| OrderManifestError::MetadataError(media_file.path.clone(), e))?.len();
+        let content_hash = hashing::sha256_file(&media_file.path).map_err(|e| OrderManifestError::CouldNotHash(media_file.path.clone(), e))?;
+        total_bytes += size_bytes;
+        entries.push(ManifestEntry {
+            file_name: media_file.raw_file_name.clone(),
+            media_type: media_file.media_type.to_string(),
+            scan_type: media_file.scan_type.to_string(),
+            group: group_code(media_file).unwrap_or_default(),
+            index: media_file.index_number,
+            dpi: photo_data.dpi,
+            width,
+            height,
+            size_bytes,
+            content_hash,
+        });
+    }
+
+    Ok(OrderManifest { files: entries, totals: totals(media_files)?, total_files: media_files.len(), total_bytes })
+}
+
+/// Counts per media and scan type, grouped the same way the final check counts them.
+fn totals(media_files: &[MediaFile]) -> Result<Vec<ManifestTotal>, OrderManifestError> {
+    let counts = MediaGroupValues::from_media_files(media_files).map_err(OrderManifestError::MediaGroupingError)?;
+    let mut totals = Vec::new();
+    let mut push = |media_type: &str, scan_type: ScanType, count: u64| {
+        if count > 0 {
+            totals.push(ManifestTotal { media_type: media_type.to_string(), scan_type: scan_type.to_string(), count });
+        }
+    };
+    if let Some(slides) = counts.slides {
+        push("Slides", ScanType::Default, slides.scanner);
+        push("Slides", ScanType::HandScan, slides.hs);
+    }
+    if let Some(prints) = counts.prints {
+        push("Prints", ScanType::Default, prints.scanner);
+        push("Prints", ScanType::HandScan, prints.hs);
+        push("Prints", ScanType::OversizedHandScan, prints.oshs);
+    }
+    if let Some(negatives) = counts.negatives {
+        push("Negatives", ScanType::Default, negatives.scanner);
+        push("Negatives", ScanType::HandScan, negatives.hs);
+    }
+
+    Ok(totals)
+}
+
+fn write_csv(to_folder: &Path, file_name: &str, header: &[&str], rows: Vec<Vec<String>>) -> Result<String, OrderManifestError> {
+    let mut csv = header.join(",");
+    csv.push_str("\r\n");
+    for row in rows {
+        csv.push_str(&row.iter().map(|field| escape_csv(field)).collect::<Vec<_>>().join(","));
+        csv.push_str("\r\n");
+    }
+    let path = to_folder.join(file_name);
+    std::fs::write(&path, csv).map_err(|e| OrderManifestError::CouldNotWrite(path, e))?;
+    Ok(file_name.to_string())
+}
+
+/// Quotes a field if it contains a comma, quote or line break, doubling any quotes.
+fn escape_csv(field: &str) -> String {
+    match field.contains([',', '"', '\r', '\n']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{qc::final_check::media_file::MediaFile, utils::{hashing, test_files}};
+    use super::{build_manifest, escape_csv, write_csv};
+
+    #[test]
+    fn lists_files_and_totals_per_scan_type() {
+        let dir = test_files::temp_dir("order-manifest-totals");
+        let media_files = ["SmithJ_Prints_001.jpg", "SmithJ_Prints_01_002_HS.jpg", "SmithJ_Prints_01_003_HS.jpg", "SmithJ_Slides_01_A_001.jpg"].map(|file_name| {
+            let path = dir.join(file_name);
+            test_files::write_scan(&path, &test_files::gradient(12, 8, [255, 255, 255]));
+            MediaFile::from_path(&path).unwrap().unwrap()
+        });
+        let manifest = build_manifest(&media_files).unwrap();
+
+        let slide = &manifest.files[3];
+        assert_eq!((slide.group.as_str(), slide.index, slide.dpi, slide.width, slide.height), ("01A", 1, 300, 12, 8));
+        assert_eq!(slide.content_hash, hashing::sha256_file(&dir.join("SmithJ_Slides_01_A_001.jpg")).unwrap());
+        let totals = manifest.totals.iter().map(|total| (total.media_type.as_str(), total.scan_type.as_str(), total.count)).collect::<Vec<_>>();
+        assert_eq!(totals, [("Slides", "Default", 1), ("Prints", "Default", 1), ("Prints", "Hand Scan", 2)]);
+        assert_eq!(manifest.total_files, 4);
+        assert_eq!(manifest.total_bytes, manifest.files.iter().map(|entry| entry.size_bytes).sum::<u64>());
+    }
+
+    #[test]
+    fn quotes_csv_fields_that_need_it() {
+        assert_eq!(escape_csv("SmithJ_Slides_001.jpg"), "SmithJ_Slides_001.jpg");
+        assert_eq!(escape_csv("Smith, Jr"), "\"Smith, Jr\"");
+        assert_eq!(escape_csv("the \"good\" album"), "\"the \"\"good\"\" album\"");
+        assert_eq!(escape_csv("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn writes_a_header_and_crlf_rows() {
+        let dir = test_files::temp_dir("order-manifest-csv");
+        let rows = vec![vec![String::from("Slides"), String::from("3")], vec![String::from("Smith, Jr"), String::from("1")]];
+        assert_eq!(write_csv(&dir, "totals.csv", &["media_type", "count"], rows).unwrap(), "totals.csv");
+        assert_eq!(std::fs::read_to_string(dir.join("totals.csv")).unwrap(), "media_type,count\r\nSlides,3\r\n\"Smith, Jr\",1\r\n");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,38 @@
+use glob::{GlobError, PatternError};
+use crate::qc::final_check::{media_file::error::MediaFileParseError, media_groups::error::MediaGroupsError};
+
+#[derive(Debug)]
+pub enum OrderManifestError {
+    DeserializeError(serde_json::Error),
+    SerializeError(serde_json::Error),
+    InvalidDirectory(PatternError),
+    InvalidFile(GlobError),
+    MediaFileParseError(std::path::PathBuf, MediaFileParseError),
+    MediaGroupingError(MediaGroupsError),
+    NoFilesInDirectory(String),
+    ImageError(std::path::PathBuf, image::ImageError),
+    MetadataError(std::path::PathBuf, std::io::Error),
+    CouldNotHash(std::path::PathBuf, std::io::Error),
+    CouldNotCreateFolder(std::path::PathBuf, std::io::Error),
+    CouldNotWrite(std::path::PathBuf, std::io::Error),
+}
+impl std::error::Error for OrderManifestError {}
+impl std::fmt::Display for OrderManifestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter)
+    -> std::fmt::Result {
+        match self {
+            OrderManifestError::DeserializeError(err) => write!(f, "could not deserialize request: {err}"),
+            OrderManifestError::SerializeError(err) => write!(f, "could not serialize manifest: {err}"),
+            OrderManifestError::InvalidDirectory(err) => write!(f, "invalid directory: {err}"),
+            OrderManifestError::InvalidFile(err) => write!(f, "invalid file path: {err}"),
+            OrderManifestError::MediaFileParseError(path, e) => write!(f, "error parsing {}: {e}", path.file_name().unwrap_or(std::ffi::OsStr::new("invalid file name")).to_string_lossy()),
+            OrderManifestError::MediaGroupingError(e) => write!(f, "{e}"),
+            OrderManifestError::NoFilesInDirectory(dir) => write!(f, "no media files found in {dir}"),
+            OrderManifestError::ImageError(path, e) => write!(f, "could not read dimensions of {}: {e}", path.to_string_lossy()),
+            OrderManifestError::MetadataError(path, e) => write!(f, "could not read size of {}: {e}", path.to_string_lossy()),
+            OrderManifestError::CouldNotHash(path, e) => write!(f, "could not hash {}: {e}", path.to_string_lossy()),
+            OrderManifestError::CouldNotCreateFolder(path, e) => write!(f, "could not create folder {}: {e}", path.to_string_lossy()),
+            OrderManifestError::CouldNotWrite(path, e) => write!(f, "could not write {}: {e}", path.to_string_lossy()),
+        }
+    }
+}
\ No newline at end of file
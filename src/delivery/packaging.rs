@@ -0,0 +1,258 @@
+pub mod error;
+use std::{fs::File, io::{self, BufReader, BufWriter}, path::{Path, PathBuf}};
+use glob::glob;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+use error::PackagingError;
+use crate::{reporter::Reporter, utils::paths};
+
+#[derive(Deserialize, Debug)]
+struct Data {
+    data: PackageOrderRequest
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PackageOrderRequest {
+    /// Folder the archives are written to. Skipped when packing if it's inside the order folder.
+    pub to_folder: String,
+    /// Largest size of each archive in bytes. Defaults to the FAT32 file size limit, and `null` writes a single archive.
+    #[serde(default = "default_max_archive_size")]
+    pub max_archive_size: Option<u64>,
+    #[serde(default)]
+    pub compression: ArchiveCompression,
+    #[serde(default = "default_true")]
+    pub verify: bool,
+}
+fn default_max_archive_size() -> Option<u64> { Some(FAT32_MAX_FILE_SIZE) }
+fn default_true() -> bool { true }
+
+#[derive(Deserialize, Debug, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveCompression {
+    /// Fastest, and JPEGs barely compress anyway.
+    Stored,
+    #[default]
+    Deflated,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PackagedArchive {
+    pub file_name: String,
+    pub entries: usize,
+    pub size: u64,
+    pub verified: bool,
+}
+
+const FAT32_MAX_FILE_SIZE: u64 = 4 * 1024 * 1024 * 1024 - 1;
+/// Upper bound on the local header, central directory record and ZIP64 extra fields of an entry, besides its name.
+const ENTRY_OVERHEAD: u64 = 256;
+/// Upper bound on the end of central directory records.
+const ARCHIVE_OVERHEAD: u64 = 256;
+
+struct OrderFile {
+    path: PathBuf,
+    /// Path inside the archive, always under a folder named after the order.
+    entry_name: String,
+    size: u64,
+}
+
+
+/// Zips every file of an order folder into `to_folder`, splitting into numbered parts so no archive exceeds `max_archive_size`.
+/// Entries keep the order's folder structure under a top-level folder named after the order.
+pub fn package_order(dir: String, request_json: Value, reporter: &mut dyn Reporter) -> Result<Vec<PackagedArchive>, PackagingError> {
+    let request = serde_json::from_value::<Data>(request_json).map_err(PackagingError::DeserializeError)?.data;
+    if request.max_archive_size.is_some_and(|max_size| max_size <= ARCHIVE_OVERHEAD + ENTRY_OVERHEAD) {
+        return Err(PackagingError::InvalidRequest(String::from("max_archive_size is too small to hold any files")))
+    }
+    let root = PathBuf::from(&dir);
+    let order_name = root.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let to_folder = PathBuf::from(&request.to_folder);
+    std::fs::create_dir_all(&to_folder).map_err(|e| PackagingError::CouldNotCreateFolder(to_folder.clone(), e))?;
+
+    let files = collect_order_files(&dir, &root, &order_name, &to_folder)?;
+    let parts = split_into_parts(files, &request)?;
+    let total_files: usize = parts.iter().map(|part| part.len()).sum();
+
+    let mut archives = Vec::with_capacity(parts.len());
+    let mut packed_files = 0;
+    for (part_number, part) in (1..).zip(&parts) {
+        let file_name = match parts.len() {
+            1 => format!("{order_name}.zip"),
+            _ => format!("{order_name}.part{part_number:02}.zip"),
+        };
+        let path = to_folder.join(&file_name);
+        write_archive(&path, part, request.compression, |entry_name| {
+            packed_files += 1;
+            reporter.progress(&format!("Packing {packed_files}/{total_files}: {entry_name}"));
+        })?;
+        if request.verify {
+            reporter.progress(&format!("Verifying {file_name}"));
+            verify_archive(&path, part)?;
+        }
+        let size = std::fs::metadata(&path).map_err(|e| PackagingError::CouldNotRead(path.clone(), e))?.len();
+        archives.push(PackagedArchive { file_name, entries: part.len(), size, verified: request.verify });
+    }
+
+    Ok(archives)
+}
+
+/// Every file under the order folder in a stable order, besides thumbnail caches and anything already in `to_folder`.
+fn collect_order_files(dir: &str, root: &Path, order_name: &str, to_folder: &Path) -> Result<Vec<OrderFile>, PackagingError> {
+    let pattern = format!("{dir}\\**\\*");
+    let canonical_root = paths::canonical_dir(root);
+    let mut files = Vec::new();
+    for entry in glob(&pattern).map_err(PackagingError::InvalidDirectory)? {
+        let path = entry.map_err(PackagingError::InvalidFile)?;
+        if !path.is_file() || path.starts_with(to_folder) || path.file_name().is_some_and(|name| name.eq_ignore_ascii_case("thumbs.db"))
+            || !paths::resolves_inside(&path, &canonical_root) {
+            continue;
+        }
+        let relative_path = path.strip_prefix(root).unwrap_or(&path);
+        let entry_name = std::iter::once(order_name.to_string())
+            .chain(relative_path.components().map(|component| component.as_os_str().to_string_lossy().into_owned()))
+            .collect::<Vec<_>>()
+            .join("/");
+        let size = std::fs::metadata(&path).map_err(|e| PackagingError::CouldNotRead(path.clone(), e))?.len();
+        files.push(OrderFile { path, entry_name, size });
+    }
+    if files.is_empty() {
+        return Err(PackagingError::NoFilesInDirectory(pattern));
+    }
+    files.sort_by(|a, b| a.entry_name.cmp(&b.entry_name));
+
+    Ok(files)
+}
+
+/// Fills parts in order, starting a new part whenever the next file's worst-case size wouldn't fit.
+fn split_into_parts(files: Vec<OrderFile>, request: &PackageOrderRequest) -> Result<Vec<Vec<OrderFile>>, PackagingError> {
+    let Some(max_size) = request.max_archive_size else {
+        return Ok(vec![files]);
+    };
+    let mut parts: Vec<Vec<OrderFile>> = vec![Vec::new()];
+    let mut part_size = ARCHIVE_OVERHEAD;
+    for file in files {
+        let entry_size = worst_case_entry_size(&file, request.compression);
+        if ARCHIVE_OVERHEAD + entry_size > max_size {
+            return Err(PackagingError::FileTooLarge(file.path, file.size, max_size));
+        }
+        if part_size + entry_size > max_size {
+            parts.push(Vec::new());
+            part_size = ARCHIVE_OVERHEAD;
+        }
+        part_size += entry_size;
+        parts.last_mut().unwrap().push(file);
+    }
+
+    Ok(parts)
+}
+
+/// Deflate can grow data that doesn't compress, such as JPEGs, by a few bytes per block.
+fn worst_case_entry_size(file: &OrderFile, compression: ArchiveCompression) -> u64 {
+    let name_size = 2 * file.entry_name.len() as u64;
+    let data_size = match compression {
+        ArchiveCompression::Stored => file.size,
+        ArchiveCompression::Deflated => file.size + file.size / 1000 + 64,
+    };
+    ENTRY_OVERHEAD + name_size + data_size
+}
+
+fn write_archive(path: &Path, files: &[OrderFile], compression: ArchiveCompression, mut on_entry: impl FnMut(&str)) -> Result<(), PackagingError> {
+    let archive_file = File::create(path).map_err(|e| PackagingError::ZipError(path.to_path_buf(), e.into()))?;
+    let mut writer = ZipWriter::new(BufWriter::new(archive_file));
+    let method = match compression {
+        ArchiveCompression::Stored => CompressionMethod::Stored,
+        ArchiveCompression::Deflated => CompressionMethod::Deflated,
+    };
+
+    for file in files {
+        on_entry(&file.entry_name);
+        let options = SimpleFileOptions::default().compression_method(method).large_file(file.size > u64::from(u32::MAX));
+        writer.start_file(file.entry_name.as_str(), options).map_err(|e| PackagingError::ZipError(path.to_path_buf(), e))?;
+        let mut source = BufReader::new(File::open(&file.path).map_err(|e| PackagingError::CouldNotRead(file.path.clone(), e))?);
+        io::copy(&mut source, &mut writer).map_err(|e| PackagingError::ZipError(path.to_path_buf(), e.into()))?;
+    }
+    writer.finish().map_err(|e| PackagingError::ZipError(path.to_path_buf(), e))?;
+
+    Ok(())
+}
+
+/// Re-reads every entry of a written archive, which checks its CRC, and compares names and sizes with the source files.
+fn verify_archive(path: &Path, files: &[OrderFile]) -> Result<(), PackagingError> {
+    let failed = |reason: String| PackagingError::VerificationFailed(path.to_path_buf(), reason);
+    let archive_file = File::open(path).map_err(|e| PackagingError::CouldNotRead(path.to_path_buf(), e))?;
+    let mut archive = ZipArchive::new(BufReader::new(archive_file)).map_err(|e| failed(e.to_string()))?;
+    if archive.len() != files.len() {
+        return Err(failed(format!("expected {} entries, found {}", files.len(), archive.len())));
+    }
+
+    for (index, file) in files.iter().enumerate() {
+        let mut entry = archive.by_index(index).map_err(|e| failed(e.to_string()))?;
+        let entry_name = entry.name().map_err(|e| failed(e.to_string()))?.into_owned();
+        if entry_name != file.entry_name {
+            return Err(failed(format!("expected entry {}, found {entry_name}", file.entry_name)));
+        }
+        let read = io::copy(&mut entry, &mut io::sink()).map_err(|e| failed(format!("{entry_name}: {e}")))?;
+        if read != file.size {
+            return Err(failed(format!("{entry_name} is {read} bytes, expected {}", file.size)));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use serde_json::json;
+    use crate::{reporter::SilentReporter, utils::test_files};
+    use super::{package_order, split_into_parts, verify_archive, write_archive, worst_case_entry_size, ArchiveCompression, OrderFile, PackageOrderRequest, ARCHIVE_OVERHEAD, error::PackagingError};
+
+    fn order_file(path: PathBuf, entry_name: &str, size: u64) -> OrderFile {
+        OrderFile { path, entry_name: String::from(entry_name), size }
+    }
+
+    fn request(max_archive_size: Option<u64>) -> PackageOrderRequest {
+        PackageOrderRequest { to_folder: String::new(), max_archive_size, compression: ArchiveCompression::Stored, verify: true }
+    }
+
+    #[test]
+    fn rejects_archive_sizes_too_small_for_any_file() {
+        let dir = test_files::temp_dir("packaging-small");
+        let to_folder = dir.with_extension("out");
+        let result = package_order(dir.to_string_lossy().into_owned(), json!({ "data": { "to_folder": to_folder, "max_archive_size": 512 } }), &mut SilentReporter);
+        assert!(matches!(result, Err(PackagingError::InvalidRequest(_))));
+        assert!(!to_folder.exists());
+    }
+
+    #[test]
+    fn splits_files_into_parts_that_fit() {
+        let files = || (1..=3).map(|i| order_file(PathBuf::from(format!("{i}.jpg")), &format!("Smith/{i}.jpg"), 1000)).collect::<Vec<_>>();
+        let entry_size = worst_case_entry_size(&files()[0], ArchiveCompression::Stored);
+
+        let parts = split_into_parts(files(), &request(Some(ARCHIVE_OVERHEAD + 2 * entry_size))).unwrap();
+        assert_eq!(parts.iter().map(Vec::len).collect::<Vec<_>>(), [2, 1]);
+        assert_eq!(split_into_parts(files(), &request(None)).unwrap().len(), 1);
+
+        let result = split_into_parts(files(), &request(Some(ARCHIVE_OVERHEAD + entry_size - 1)));
+        assert!(matches!(result, Err(PackagingError::FileTooLarge(_, 1000, _))));
+    }
+
+    #[test]
+    fn verifies_written_archives() {
+        let dir = test_files::temp_dir("packaging-verify");
+        let path = dir.join("a.txt");
+        std::fs::write(&path, "scan").unwrap();
+        let files = [order_file(path.clone(), "Smith/a.txt", 4)];
+        let archive = dir.join("Smith.zip");
+        let mut entries = Vec::new();
+        write_archive(&archive, &files, ArchiveCompression::Deflated, |name| entries.push(name.to_string())).unwrap();
+        assert_eq!(entries, ["Smith/a.txt"]);
+        verify_archive(&archive, &files).unwrap();
+
+        let renamed = [order_file(path.clone(), "Smith/b.txt", 4)];
+        assert!(matches!(verify_archive(&archive, &renamed), Err(PackagingError::VerificationFailed(..))));
+        let resized = [order_file(path, "Smith/a.txt", 5)];
+        assert!(matches!(verify_archive(&archive, &resized), Err(PackagingError::VerificationFailed(..))));
+    }
+}
\ No newline at end of file
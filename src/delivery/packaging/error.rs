@@ -0,0 +1,33 @@
+use glob::{GlobError, PatternError};
+
+#[derive(Debug)]
+pub enum PackagingError {
+    DeserializeError(serde_json::Error),
+    InvalidRequest(String),
+    InvalidDirectory(PatternError),
+    InvalidFile(GlobError),
+    NoFilesInDirectory(String),
+    FileTooLarge(std::path::PathBuf, u64, u64),
+    CouldNotCreateFolder(std::path::PathBuf, std::io::Error),
+    CouldNotRead(std::path::PathBuf, std::io::Error),
+    ZipError(std::path::PathBuf, zip::result::ZipError),
+    VerificationFailed(std::path::PathBuf, String),
+}
+impl std::error::Error for PackagingError {}
+impl std::fmt::Display for PackagingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter)
+    -> std::fmt::Result {
+        match self {
+            PackagingError::DeserializeError(err) => write!(f, "could not deserialize request: {err}"),
+            PackagingError::InvalidRequest(err) => write!(f, "invalid request: {err}"),
+            PackagingError::InvalidDirectory(err) => write!(f, "invalid directory: {err}"),
+            PackagingError::InvalidFile(err) => write!(f, "invalid file path: {err}"),
+            PackagingError::NoFilesInDirectory(dir) => write!(f, "no files found in {dir}"),
+            PackagingError::FileTooLarge(path, size, max_size) => write!(f, "{} is {size} bytes, which can't fit in an archive of at most {max_size} bytes", path.to_string_lossy()),
+            PackagingError::CouldNotCreateFolder(path, e) => write!(f, "could not create folder {}: {e}", path.to_string_lossy()),
+            PackagingError::CouldNotRead(path, e) => write!(f, "could not read {}: {e}", path.to_string_lossy()),
+            PackagingError::ZipError(path, e) => write!(f, "error writing {}: {e}", path.to_string_lossy()),
+            PackagingError::VerificationFailed(path, e) => write!(f, "{} failed verification: {e}", path.to_string_lossy()),
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,127 @@
+pub mod error;
+use std::{io::ErrorKind, path::{Component, Path, PathBuf}};
+use serde_json::Value;
+use error::AllowedRootsError;
+
+/// Request fields, at any depth, holding a path a job reads or writes.
+const PATH_KEYS: [&str; 7] = ["dir", "from_folder", "from_path", "to_folder", "order_sheet", "manifest_path", "font_path"];
+/// Request fields holding a path inside the request's `dir`.
+const DIR_RELATIVE_KEYS: [&str; 1] = ["manifest_name"];
+
+
+/// Checks that every path in a job request resolves inside one of `roots`, which must already be canonical.
+/// Any path is allowed if there are no roots.
+pub fn check_request(roots: &[PathBuf], request_json: &Value) -> Result<(), AllowedRootsError> {
+    if roots.is_empty() {
+        return Ok(());
+    }
+    let dir = request_json.get("dir").and_then(Value::as_str).map(PathBuf::from).unwrap_or_default();
+    let mut paths = Vec::new();
+    collect_paths(request_json, &dir, &mut paths);
+    for (key, path) in paths {
+        let resolved = resolve(&key, &path)?;
+        if !roots.iter().any(|root| resolved.starts_with(root)) {
+            return Err(AllowedRootsError::OutsideAllowedRoots(key, path));
+        }
+    }
+    Ok(())
+}
+
+fn collect_paths(value: &Value, dir: &Path, paths: &mut Vec<(String, PathBuf)>) {
+    match value {
+        Value::Object(fields) => {
+            for (key, value) in fields {
+                match (value.as_str(), PATH_KEYS.contains(&key.as_str()), DIR_RELATIVE_KEYS.contains(&key.as_str())) {
+                    (Some(path), true, _) => paths.push((key.clone(), PathBuf::from(path))),
+                    (Some(path), _, true) => paths.push((key.clone(), dir.join(path))),
+                    _ => collect_paths(value, dir, paths),
+                }
+            }
+        }
+        Value::Array(values) => values.iter().for_each(|value| collect_paths(value, dir, paths)),
+        _ => {}
+    }
+}
+
+/// Resolves `..` and symlinks in a path that may not exist yet, such as a folder a job will create,
+/// by canonicalizing its deepest existing ancestor. Nothing past that ancestor may be `..`, since it can't be resolved.
+fn resolve(key: &str, path: &Path) -> Result<PathBuf, AllowedRootsError> {
+    let components: Vec<Component> = path.components().collect();
+    for existing_count in (0..=components.len()).rev() {
+        let existing: PathBuf = components[..existing_count].iter().collect();
+        let existing = if existing.as_os_str().is_empty() { PathBuf::from(".") } else { existing };
+        match std::fs::canonicalize(&existing) {
+            Ok(mut resolved) => {
+                for component in &components[existing_count..] {
+                    match component {
+                        Component::Normal(name) => resolved.push(name),
+                        Component::CurDir => {}
+                        _ => return Err(AllowedRootsError::ParentTraversal(key.to_string(), path.to_path_buf())),
+                    }
+                }
+                return Ok(resolved);
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(AllowedRootsError::CouldNotResolve(key.to_string(), path.to_path_buf(), e)),
+        }
+    }
+    Err(AllowedRootsError::CouldNotResolve(key.to_string(), path.to_path_buf(), ErrorKind::NotFound.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use serde_json::json;
+    use super::{check_request, error::AllowedRootsError};
+
+    /// Makes an allowed root holding an order folder, and a folder beside it that isn't allowed.
+    fn roots(name: &str) -> (PathBuf, PathBuf) {
+        let folder = std::env::temp_dir().join(format!("mw-files-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(folder.join("orders").join("SmithJ")).unwrap();
+        std::fs::create_dir_all(folder.join("private")).unwrap();
+        let folder = std::fs::canonicalize(folder).unwrap();
+        (folder.join("orders"), folder.join("private"))
+    }
+
+    #[test]
+    fn allows_paths_inside_the_roots() {
+        let (root, private) = roots("allowed-roots-inside");
+        let order = root.join("SmithJ");
+        let allowed = [root.clone()];
+        assert!(check_request(&[], &json!({ "dir": private })).is_ok());
+        assert!(check_request(&allowed, &json!({ "dir": order, "data": { "to_folder": root.join("new").join("folder"), "manifest_name": "manifest.txt" } })).is_ok());
+        assert!(check_request(&allowed, &json!({ "dir": order.join("..").join("SmithJ") })).is_ok());
+    }
+
+    #[test]
+    fn rejects_paths_outside_the_roots() {
+        let (root, private) = roots("allowed-roots-outside");
+        let order = root.join("SmithJ");
+        let allowed = [root];
+        for request in [
+            json!({ "dir": private }),
+            json!({ "dir": order, "data": { "to_folder": order.join("..").join("..").join("private") } }),
+            json!({ "dir": order, "data": { "steps": [{ "font_path": private.join("font.ttf") }] } }),
+            json!({ "dir": order, "data": { "manifest_name": "../../private/manifest.txt" } }),
+        ] {
+            assert!(matches!(check_request(&allowed, &request), Err(AllowedRootsError::OutsideAllowedRoots(..))), "{request} was allowed");
+        }
+    }
+
+    #[test]
+    fn rejects_climbing_out_of_folders_that_dont_exist() {
+        let (root, _) = roots("allowed-roots-traversal");
+        let request = json!({ "dir": root.join("SmithJ"), "data": { "to_folder": root.join("missing").join("..").join("..").join("private") } });
+        assert!(matches!(check_request(&[root], &request), Err(AllowedRootsError::ParentTraversal(key, _)) if key == "to_folder"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follows_symlinks_out_of_the_roots() {
+        let (root, private) = roots("allowed-roots-symlink");
+        std::os::unix::fs::symlink(&private, root.join("link")).unwrap();
+        let request = json!({ "dir": root.join("link").join("SmithJ") });
+        assert!(matches!(check_request(&[root], &request), Err(AllowedRootsError::OutsideAllowedRoots(..))));
+    }
+}
\ No newline at end of file
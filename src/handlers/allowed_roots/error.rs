@@ -0,0 +1,17 @@
+#[derive(Debug)]
+pub enum AllowedRootsError {
+    OutsideAllowedRoots(String, std::path::PathBuf),
+    ParentTraversal(String, std::path::PathBuf),
+    CouldNotResolve(String, std::path::PathBuf, std::io::Error),
+}
+impl std::error::Error for AllowedRootsError {}
+impl std::fmt::Display for AllowedRootsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter)
+    -> std::fmt::Result {
+        match self {
+            AllowedRootsError::OutsideAllowedRoots(key, path) => write!(f, "{key} {} is outside of the allowed root directories", path.to_string_lossy()),
+            AllowedRootsError::ParentTraversal(key, path) => write!(f, "{key} {} climbs out of a folder that doesn't exist with ..", path.to_string_lossy()),
+            AllowedRootsError::CouldNotResolve(key, path, e) => write!(f, "could not resolve {key} {}: {e}", path.to_string_lossy()),
+        }
+    }
+}
\ No newline at end of file
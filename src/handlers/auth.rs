@@ -0,0 +1,129 @@
+pub mod error;
+use actix_web::{http::header, HttpRequest};
+use serde::Deserialize;
+use error::AuthError;
+use crate::config::TokenConfig;
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Can run checks and bag orders into new folders.
+    #[default]
+    Operator,
+    /// Can also run corrections and build deliverables, which write files that could replace customer files.
+    QcLead,
+}
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Role::Operator => write!(f, "operator"),
+            Role::QcLead => write!(f, "qc_lead"),
+        }
+    }
+}
+
+/// Who a websocket client authenticated as.
+#[derive(Debug, Clone)]
+pub struct Client {
+    pub name: String,
+    pub role: Role,
+}
+
+/// Jobs that rewrite images or write files into a folder of the client's choosing, so can overwrite customer files if pointed at them.
+const QC_LEAD_JOBS: [&str; 12] = [
+    "bake_orientation", "correct_slides", "restore_colors", "invert_negatives", "remove_dust", "run_pipeline",
+    "write_manifest", "export_gallery", "generate_jpegs", "package_order", "contact_sheets", "export_order_manifest",
+];
+
+
+/// Finds the client a connection's token belongs to. The token is read from an `Authorization: Bearer` header,
+/// or the `token` query parameter since browsers can't set headers on websockets.
+/// Every connection is a QC lead if no tokens are configured.
+pub fn authenticate(req: &HttpRequest, tokens: &[TokenConfig]) -> Result<Client, AuthError> {
+    if tokens.is_empty() {
+        return Ok(Client { name: String::from("anonymous"), role: Role::QcLead });
+    }
+    let header_token = req.headers().get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let query_token = req.query_string().split('&').find_map(|pair| pair.strip_prefix("token="));
+    let given = header_token.or(query_token).ok_or(AuthError::MissingToken)?;
+
+    tokens.iter()
+        .find(|token| constant_time_eq(token.token.as_bytes(), given.as_bytes()))
+        .map(|token| Client { name: token.name.clone(), role: token.role })
+        .ok_or(AuthError::UnknownToken)
+}
+
+/// Checks that a client's role may run a job.
+pub fn authorize(client: &Client, job: &str) -> Result<(), AuthError> {
+    match QC_LEAD_JOBS.contains(&job) && client.role != Role::QcLead {
+        true => Err(AuthError::RoleNotAllowed(job.to_string(), client.role.to_string())),
+        false => Ok(()),
+    }
+}
+
+/// Compares every byte, so how long a comparison takes doesn't reveal how much of a token was guessed.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |difference, (x, y)| difference | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::header, test::TestRequest};
+    use crate::config::TokenConfig;
+    use super::{authenticate, authorize, constant_time_eq, error::AuthError, Client, Role, QC_LEAD_JOBS};
+
+    fn tokens() -> Vec<TokenConfig> {
+        vec![
+            TokenConfig { name: String::from("front desk"), token: String::from("desk-token"), role: Role::Operator },
+            TokenConfig { name: String::from("lab"), token: String::from("lab-token"), role: Role::QcLead },
+        ]
+    }
+
+    #[test]
+    fn reads_tokens_from_the_header_or_query() {
+        let by_header = TestRequest::default().insert_header((header::AUTHORIZATION, "Bearer lab-token")).to_http_request();
+        let client = authenticate(&by_header, &tokens()).unwrap();
+        assert_eq!((client.name.as_str(), client.role), ("lab", Role::QcLead));
+
+        let by_query = TestRequest::with_uri("/ws?format=json&token=desk-token").to_http_request();
+        let client = authenticate(&by_query, &tokens()).unwrap();
+        assert_eq!((client.name.as_str(), client.role), ("front desk", Role::Operator));
+    }
+
+    #[test]
+    fn rejects_missing_and_unknown_tokens() {
+        assert!(matches!(authenticate(&TestRequest::default().to_http_request(), &tokens()), Err(AuthError::MissingToken)));
+        let wrong = TestRequest::default().insert_header((header::AUTHORIZATION, "Bearer lab-tokem")).to_http_request();
+        assert!(matches!(authenticate(&wrong, &tokens()), Err(AuthError::UnknownToken)));
+        let prefix = TestRequest::with_uri("/ws?token=lab").to_http_request();
+        assert!(matches!(authenticate(&prefix, &tokens()), Err(AuthError::UnknownToken)));
+    }
+
+    #[test]
+    fn lets_anyone_in_without_tokens() {
+        let client = authenticate(&TestRequest::default().to_http_request(), &[]).unwrap();
+        assert_eq!(client.role, Role::QcLead);
+    }
+
+    #[test]
+    fn keeps_writing_jobs_to_qc_leads() {
+        let operator = Client { name: String::from("front desk"), role: Role::Operator };
+        let qc_lead = Client { name: String::from("lab"), role: Role::QcLead };
+        for job in QC_LEAD_JOBS {
+            assert!(matches!(authorize(&operator, job), Err(AuthError::RoleNotAllowed(..))), "{job} was allowed");
+            assert!(authorize(&qc_lead, job).is_ok());
+        }
+        assert!(authorize(&operator, "final_check").is_ok());
+        assert!(authorize(&operator, "bag_order").is_ok());
+    }
+
+    #[test]
+    fn compares_whole_tokens() {
+        assert!(constant_time_eq(b"lab-token", b"lab-token"));
+        assert!(!constant_time_eq(b"lab-token", b"lab-tokem"));
+        assert!(!constant_time_eq(b"lab-token", b"lab-token2"));
+        assert!(!constant_time_eq(b"", b"lab-token"));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,17 @@
+#[derive(Debug)]
+pub enum AuthError {
+    MissingToken,
+    UnknownToken,
+    RoleNotAllowed(String, String),
+}
+impl std::error::Error for AuthError {}
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter)
+    -> std::fmt::Result {
+        match self {
+            AuthError::MissingToken => write!(f, "no token was given"),
+            AuthError::UnknownToken => write!(f, "token is not recognized"),
+            AuthError::RoleNotAllowed(job, role) => write!(f, "{job} requires the qc_lead role, but this client is {role}"),
+        }
+    }
+}
\ No newline at end of file
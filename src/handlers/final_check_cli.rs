@@ -0,0 +1,244 @@
+pub mod error;
+use std::path::PathBuf;
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use error::FinalCheckCliError;
+use crate::config::NamingConfig;
+use mw_files::{qc::final_check::{self, duplicates::DuplicateScope, error::FCError}, reporter::SilentReporter};
+
+/// Runs a final check from the command line, such as:
+/// ```text
+/// mw-files final-check D:\Orders\SmithJ --request SmithJ.final_check.json
+/// mw-files final-check D:\Orders\SmithJ --first-name John --last-name Smith --slides scanner=40,hs=2,dpi=3000
+/// ```
+/// Flags override the matching fields of the request file.
+#[derive(Args, Debug)]
+pub struct FinalCheckArgs {
+    /// Order directory to check.
+    pub dir: String,
+    /// JSON file holding the same `data` a final check job would be sent with.
+    #[arg(long)]
+    pub request: Option<PathBuf>,
+    #[arg(long)]
+    pub first_name: Option<String>,
+    #[arg(long)]
+    pub last_name: Option<String>,
+    #[arg(long)]
+    pub custom_group_name: Option<String>,
+    #[arg(long)]
+    pub group_num: Option<u64>,
+    #[arg(long)]
+    pub group_char: Option<char>,
+    #[arg(long)]
+    pub group_num_precision: Option<u64>,
+    #[arg(long)]
+    pub index_num_precision: Option<u64>,
+    /// Naming profile from the server config to take missing precisions from.
+    #[arg(long)]
+    pub naming_profile: Option<String>,
+    #[arg(long, value_enum)]
+    pub duplicate_scope: Option<DuplicateScopeArg>,
+    /// Intake order sheet (.csv or .xlsx) to read expected counts and DPIs from.
+    #[arg(long)]
+    pub order_sheet: Option<String>,
+    /// Expected slides as comma separated options, such as `scanner=40,hs=2,dpi=3000,is_corrected=true`.
+    #[arg(long)]
+    pub slides: Option<String>,
+    /// Expected prints, in the same form as `--slides`.
+    #[arg(long)]
+    pub prints: Option<String>,
+    /// Expected negatives, in the same form as `--slides`.
+    #[arg(long)]
+    pub negatives: Option<String>,
+    #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+    pub format: ReportFormat,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    Text,
+    Json,
+}
+
+/// The library builds without clap, so [`DuplicateScope`] gets its flag values here.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum DuplicateScopeArg {
+    Folder,
+    Order,
+}
+impl From<DuplicateScopeArg> for DuplicateScope {
+    fn from(scope: DuplicateScopeArg) -> Self {
+        match scope {
+            DuplicateScopeArg::Folder => DuplicateScope::Folder,
+            DuplicateScopeArg::Order => DuplicateScope::Order,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct CheckReport {
+    pub dir: String,
+    pub passed: bool,
+    pub message: String,
+}
+
+/// Exit codes for a passed check, a failed check, and a request that couldn't be built.
+const EXIT_PASSED: i32 = 0;
+const EXIT_FAILED: i32 = 1;
+const EXIT_INVALID_REQUEST: i32 = 2;
+
+
+/// Prints a report of the final check and returns the process exit code.
+pub fn run(args: FinalCheckArgs, naming: &NamingConfig) -> i32 {
+    let request = match build_request(&args).and_then(|request| naming.apply(json!({ "data": request })).map_err(FinalCheckCliError::InvalidNamingProfile)) {
+        Ok(request) => request,
+        Err(e) => {
+            eprintln!("{e}");
+            return EXIT_INVALID_REQUEST;
+        }
+    };
+    let (report, exit_code) = match final_check::final_check(args.dir.clone(), request, &mut SilentReporter) {
+        Ok(()) => (CheckReport { dir: args.dir, passed: true, message: String::from("passed final check") }, EXIT_PASSED),
+        Err(e) => {
+            let exit_code = if is_request_error(&e) { EXIT_INVALID_REQUEST } else { EXIT_FAILED };
+            (CheckReport { dir: args.dir, passed: false, message: e.to_string() }, exit_code)
+        }
+    };
+
+    match args.format {
+        ReportFormat::Text => match report.passed {
+            true => println!("PASSED {}", report.dir),
+            false => println!("FAILED {}: {}", report.dir, report.message),
+        },
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default()),
+    }
+    exit_code
+}
+
+/// Whether the check couldn't run at all, rather than the directory failing it.
+fn is_request_error(e: &FCError) -> bool {
+    matches!(e, FCError::DeserializeError(_) | FCError::InvalidRequest(_) | FCError::InsufficientGroupNumberPrecision(..)
+        | FCError::GroupNumberPrecisionTooHigh(_) | FCError::OrderSheetError(_))
+}
+
+/// Reads the request file, if any, then applies each flag that was given on top of it.
+fn build_request(args: &FinalCheckArgs) -> Result<Value, FinalCheckCliError> {
+    let mut request = match &args.request {
+        Some(path) => {
+            let text = std::fs::read_to_string(path).map_err(|e| FinalCheckCliError::CouldNotReadRequest(path.clone(), e))?;
+            match serde_json::from_str(&text).map_err(|e| FinalCheckCliError::InvalidRequest(path.clone(), e))? {
+                Value::Object(request) => request,
+                _ => return Err(FinalCheckCliError::RequestNotAnObject(path.clone())),
+            }
+        }
+        None => Map::new(),
+    };
+
+    let fields = [
+        ("first_name", args.first_name.clone().map(Value::from)),
+        ("last_name", args.last_name.clone().map(Value::from)),
+        ("custom_group_name", args.custom_group_name.clone().map(Value::from)),
+        ("group_num", args.group_num.map(Value::from)),
+        ("group_char", args.group_char.map(|group_char| Value::from(group_char.to_string()))),
+        ("group_num_precision", args.group_num_precision.map(Value::from)),
+        ("index_num_precision", args.index_num_precision.map(Value::from)),
+        ("naming_profile", args.naming_profile.clone().map(Value::from)),
+        ("duplicate_scope", args.duplicate_scope.map(|scope| json!(DuplicateScope::from(scope)))),
+        ("order_sheet", args.order_sheet.clone().map(Value::from)),
+    ];
+    for (field, value) in fields {
+        if let Some(value) = value {
+            request.insert(field.to_string(), value);
+        }
+    }
+
+    for (media_type, options) in [("slides", &args.slides), ("prints", &args.prints), ("negatives", &args.negatives)] {
+        let Some(options) = options else { continue };
+        let media_group_values = request.entry("media_group_values").or_insert_with(|| json!({}));
+        let Value::Object(media_group_values) = media_group_values else {
+            return Err(FinalCheckCliError::InvalidOption(media_type.to_string(), String::from("request file's media_group_values is not an object")));
+        };
+        let group_options = media_group_values.entry(media_type).or_insert_with(|| json!({}));
+        let Value::Object(group_options) = group_options else {
+            return Err(FinalCheckCliError::InvalidOption(media_type.to_string(), format!("request file's {media_type} is not an object")));
+        };
+        group_options.extend(parse_group_options(media_type, options)?);
+    }
+
+    Ok(Value::Object(request))
+}
+
+/// Parses `key=value` pairs, reading values as JSON where possible so numbers and booleans keep their types.
+fn parse_group_options(media_type: &str, options: &str) -> Result<Map<String, Value>, FinalCheckCliError> {
+    let mut ret = Map::new();
+    for option in options.split(',').map(str::trim).filter(|option| !option.is_empty()) {
+        let (key, value) = option.split_once('=')
+            .ok_or_else(|| FinalCheckCliError::InvalidOption(media_type.to_string(), format!("expected key=value but got {option}")))?;
+        let value = serde_json::from_str(value.trim()).unwrap_or_else(|_| Value::from(value.trim()));
+        ret.insert(key.trim().to_string(), value);
+    }
+    Ok(ret)
+
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use clap::Parser;
+    use serde_json::json;
+    use crate::config::NamingConfig;
+    use super::{build_request, run, FinalCheckArgs, EXIT_FAILED, EXIT_INVALID_REQUEST};
+
+    #[derive(Parser)]
+    struct Command {
+        #[command(flatten)]
+        args: FinalCheckArgs,
+    }
+
+    fn args(argv: &[&str]) -> FinalCheckArgs {
+        Command::parse_from(std::iter::once("final-check").chain(argv.iter().copied())).args
+    }
+
+    fn write_request(name: &str, contents: &str) -> PathBuf {
+        let folder = std::env::temp_dir().join(format!("mw-files-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        let path = folder.join("request.json");
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn flags_override_the_request_file() {
+        let path = write_request("final-check-cli-merge", r#"{ "first_name": "Jane", "last_name": "Smith", "media_group_values": { "slides": { "scanner": 10, "dpi": 2000 } } }"#);
+        let request = build_request(&args(&["SmithJ", "--request", &path.to_string_lossy(), "--first-name", "John", "--group-num", "3",
+            "--slides", "scanner=40, is_corrected=true", "--prints", "dpi=600"])).unwrap();
+        assert_eq!(request, json!({
+            "first_name": "John", "last_name": "Smith", "group_num": 3,
+            "media_group_values": { "slides": { "scanner": 40, "dpi": 2000, "is_corrected": true }, "prints": { "dpi": 600 } },
+        }));
+    }
+
+    #[test]
+    fn exits_2_when_the_request_cant_be_built() {
+        let not_an_object = write_request("final-check-cli-array", "[]");
+        for argv in [
+            vec!["SmithJ", "--request", "/no/such/request.json"],
+            vec!["SmithJ", "--request", &not_an_object.to_string_lossy()],
+            vec!["SmithJ", "--first-name", "John", "--last-name", "Smith", "--slides", "scanner"],
+            vec!["SmithJ", "--first-name", "John", "--last-name", "Smith"],
+            vec!["SmithJ", "--first-name", "John", "--last-name", "Smith", "--group-num", "100", "--group-num-precision", "2", "--slides", "scanner=1"],
+            vec!["SmithJ", "--first-name", "John", "--last-name", "Smith", "--naming-profile", "missing", "--slides", "scanner=1"],
+        ] {
+            assert_eq!(run(args(&argv), &NamingConfig::default()), EXIT_INVALID_REQUEST, "{argv:?}");
+        }
+    }
+
+    #[test]
+    fn exits_1_when_the_order_fails_the_check() {
+        let folder = std::env::temp_dir().join(format!("mw-files-final-check-cli-empty-{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        let argv = [&*folder.to_string_lossy(), "--first-name", "John", "--last-name", "Smith", "--slides", "scanner=1", "--format", "json"];
+        assert_eq!(run(args(&argv), &NamingConfig::default()), EXIT_FAILED);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,23 @@
+use crate::config::error::ConfigError;
+
+#[derive(Debug)]
+pub enum FinalCheckCliError {
+    CouldNotReadRequest(std::path::PathBuf, std::io::Error),
+    InvalidRequest(std::path::PathBuf, serde_json::Error),
+    RequestNotAnObject(std::path::PathBuf),
+    InvalidOption(String, String),
+    InvalidNamingProfile(ConfigError),
+}
+impl std::error::Error for FinalCheckCliError {}
+impl std::fmt::Display for FinalCheckCliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter)
+    -> std::fmt::Result {
+        match self {
+            FinalCheckCliError::CouldNotReadRequest(path, e) => write!(f, "could not read request file {}: {e}", path.to_string_lossy()),
+            FinalCheckCliError::InvalidRequest(path, e) => write!(f, "invalid request file {}: {e}", path.to_string_lossy()),
+            FinalCheckCliError::RequestNotAnObject(path) => write!(f, "request file {} must hold a JSON object", path.to_string_lossy()),
+            FinalCheckCliError::InvalidOption(option, msg) => write!(f, "invalid --{option}: {msg}"),
+            FinalCheckCliError::InvalidNamingProfile(e) => write!(f, "{e}"),
+        }
+    }
+}
\ No newline at end of file
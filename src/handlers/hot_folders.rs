@@ -0,0 +1,295 @@
+pub mod error;
+use std::{collections::{HashMap, HashSet}, fs::OpenOptions, io::Write, path::{Path, PathBuf}, sync::Arc, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+use actix::{Actor, AsyncContext, Context, Handler, Message, Recipient};
+use glob::glob;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use error::HotFolderError;
+use mw_files::{qc::final_check, reporter::SilentReporter};
+use crate::{config::{NamingConfig, WatchConfig}, handlers::job_history::{JobHistory, JobStatus}};
+
+#[derive(Serialize, Debug, Clone)]
+pub struct WatchResult {
+    pub group_dir: String,
+    pub passed: bool,
+    pub message: String,
+    /// Seconds since the Unix epoch.
+    pub checked_at: u64,
+}
+
+/// A hot folder result pushed to a connected websocket client.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct WatchEvent {
+    pub msg: String,
+    pub result: WatchResult,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct RegisterClient(pub Recipient<WatchEvent>);
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct UnregisterClient(pub Recipient<WatchEvent>);
+
+const RULES_FILE_SUFFIX: &str = ".final_check.json";
+/// Recorded in the job history as who submitted hot folder checks.
+const HOT_FOLDER_CLIENT: &str = "hot_folders";
+const FINAL_CHECK_JOB: &str = "final_check";
+
+/// What a group folder looked like when last polled. Any change restarts its debounce.
+/// Stored in the job history alongside each hot folder check, so groups that haven't changed since aren't checked again after a restart.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+struct FolderSignature {
+    files: usize,
+    bytes: u64,
+    /// Milliseconds since the Unix epoch.
+    last_modified: u64,
+}
+
+struct GroupState {
+    signature: FolderSignature,
+    changed_at: Instant,
+    /// The signature the group had when it was last checked, if it has been.
+    checked: Option<FolderSignature>,
+}
+
+pub struct HotFolderWatcher {
+    config: WatchConfig,
+    naming: NamingConfig,
+    history: Arc<JobHistory>,
+    groups: HashMap<PathBuf, GroupState>,
+    clients: HashSet<Recipient<WatchEvent>>,
+}
+
+
+impl HotFolderWatcher {
+    pub fn new(config: WatchConfig, naming: NamingConfig, history: Arc<JobHistory>) -> HotFolderWatcher {
+        HotFolderWatcher { config, naming, history, groups: HashMap::new(), clients: HashSet::new() }
+    }
+
+    /// Updates every group folder's signature, then checks the groups that changed and have settled.
+    fn poll(&mut self) {
+        let now = Instant::now();
+        let mut present = HashSet::new();
+        for folder in &self.config.folders {
+            let group_dirs = match group_dirs(folder) {
+                Ok(group_dirs) => group_dirs,
+                Err(e) => {
+                    println!("Hot folder error: {e}");
+                    continue;
+                }
+            };
+            for group_dir in group_dirs {
+                let signature = match folder_signature(&group_dir) {
+                    Ok(signature) => signature,
+                    Err(e) => {
+                        println!("Hot folder error: {e}");
+                        continue;
+                    }
+                };
+                match self.groups.get_mut(&group_dir) {
+                    Some(state) if state.signature != signature => {
+                        state.signature = signature;
+                        state.changed_at = now;
+                    }
+                    Some(_) => {}
+                    None => {
+                        let checked = last_checked_signature(&group_dir, &self.history);
+                        self.groups.insert(group_dir.clone(), GroupState { signature, changed_at: now, checked });
+                    }
+                }
+                present.insert(group_dir);
+            }
+        }
+        self.groups.retain(|group_dir, _| present.contains(group_dir));
+
+        let debounce = Duration::from_secs(self.config.debounce_seconds);
+        let settled: Vec<PathBuf> = self.groups.iter()
+            .filter(|(_, state)| state.checked != Some(state.signature) && state.changed_at.elapsed() >= debounce)
+            .map(|(group_dir, _)| group_dir.clone())
+            .collect();
+        for group_dir in settled {
+            let Some(state) = self.groups.get_mut(&group_dir) else {
+                continue;
+            };
+            state.checked = Some(state.signature);
+            let result = check_group(&group_dir, state.signature, &self.naming, &self.history);
+            self.publish(result);
+        }
+    }
+
+    /// Appends a result to the results log and pushes it to every connected client.
+    fn publish(&mut self, result: WatchResult) {
+        let msg = match result.passed {
+            true => format!("Hot folder final check successful for {}!", result.group_dir),
+            false => format!("Hot folder final check unsuccessful for {}: {}", result.group_dir, result.message),
+        };
+        println!("{msg}");
+        if let Err(e) = append_to_log(&self.config.results_log, &result) {
+            println!("Hot folder error: {e}");
+        }
+        for client in &self.clients {
+            client.do_send(WatchEvent { msg: msg.clone(), result: result.clone() });
+        }
+    }
+}
+
+impl Actor for HotFolderWatcher {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        println!("Watching {} hot folders", self.config.folders.len());
+        ctx.run_interval(Duration::from_secs(self.config.poll_seconds.max(1)), |watcher, _| watcher.poll());
+    }
+}
+
+impl Handler<RegisterClient> for HotFolderWatcher {
+    type Result = ();
+    fn handle(&mut self, msg: RegisterClient, _ctx: &mut Self::Context) {
+        self.clients.insert(msg.0);
+    }
+}
+
+impl Handler<UnregisterClient> for HotFolderWatcher {
+    type Result = ();
+    fn handle(&mut self, msg: UnregisterClient, _ctx: &mut Self::Context) {
+        self.clients.remove(&msg.0);
+    }
+}
+
+
+fn group_dirs(folder: &Path) -> Result<Vec<PathBuf>, HotFolderError> {
+    let entries = std::fs::read_dir(folder).map_err(|e| HotFolderError::CouldNotReadFolder(folder.to_path_buf(), e))?;
+    Ok(entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).filter(|path| path.is_dir()).collect())
+}
+
+fn rules_path(group_dir: &Path) -> PathBuf {
+    let name = group_dir.file_name().unwrap_or_default().to_string_lossy();
+    group_dir.with_file_name(format!("{name}{RULES_FILE_SUFFIX}"))
+}
+
+/// Summarizes every file in a group folder, and its rules file, so edits to either count as changes.
+fn folder_signature(group_dir: &Path) -> Result<FolderSignature, HotFolderError> {
+    let mut signature = FolderSignature { files: 0, bytes: 0, last_modified: 0 };
+    let pattern = format!("{}\\**\\*", group_dir.to_string_lossy());
+    let files = glob(&pattern).map_err(HotFolderError::InvalidDirectory)?
+        .map(|entry| entry.map_err(HotFolderError::InvalidFile))
+        .chain(std::iter::once(Ok(rules_path(group_dir))));
+    for path in files {
+        let path = path?;
+        let Ok(metadata) = std::fs::metadata(&path) else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        signature.files += 1;
+        signature.bytes += metadata.len();
+        if let Ok(modified) = metadata.modified() {
+            let modified = modified.duration_since(UNIX_EPOCH).map(|duration| duration.as_millis() as u64).unwrap_or(0);
+            signature.last_modified = signature.last_modified.max(modified);
+        }
+    }
+    Ok(signature)
+}
+
+/// The signature a group had when the watcher last checked it, from the job history.
+fn last_checked_signature(group_dir: &Path, history: &JobHistory) -> Option<FolderSignature> {
+    match history.last_signature(HOT_FOLDER_CLIENT, FINAL_CHECK_JOB, &group_dir.to_string_lossy()) {
+        Ok(signature) => signature.and_then(|signature| serde_json::from_value(signature).ok()),
+        Err(e) => {
+            println!("Could not look up past hot folder checks of {}: {e}", group_dir.to_string_lossy());
+            None
+        }
+    }
+}
+
+/// Checks a group and records the check in the job history, the same as a final check job sent over the websocket.
+fn check_group(group_dir: &Path, signature: FolderSignature, naming: &NamingConfig, history: &JobHistory) -> WatchResult {
+    let checked_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0);
+    let group_dir_name = group_dir.to_string_lossy().into_owned();
+    let request = read_rules(group_dir)
+        .and_then(|rules| naming.apply(json!({ "data": rules })).map_err(HotFolderError::InvalidNamingProfile));
+    let job_id = history.start(HOT_FOLDER_CLIENT, FINAL_CHECK_JOB, &group_dir_name, request.as_ref().unwrap_or(&Value::Null))
+        .inspect_err(|e| println!("Could not record hot folder check of {group_dir_name}: {e}"))
+        .ok();
+    let result = request
+        .and_then(|request| final_check::final_check(group_dir_name.clone(), request, &mut SilentReporter).map_err(HotFolderError::FinalCheckFailed));
+    let result = match result {
+        Ok(()) => WatchResult { group_dir: group_dir_name, passed: true, message: String::from("passed final check"), checked_at },
+        Err(e) => WatchResult { group_dir: group_dir_name, passed: false, message: e.to_string(), checked_at },
+    };
+    if let Some(job_id) = job_id {
+        let (status, message) = if result.passed { (JobStatus::Success, None) } else { (JobStatus::Failure, Some(&*result.message)) };
+        // A final check has no findings of its own, the same as one sent over the websocket
+        let recorded = history.record_signature(job_id, &json!(signature))
+            .and_then(|_| history.finish(job_id, status, message, &Value::Null));
+        if let Err(e) = recorded {
+            println!("Could not record result of job {job_id}: {e}");
+        }
+    }
+    result
+}
+
+/// The rules file holds the same `data` a final check job would be sent with.
+fn read_rules(group_dir: &Path) -> Result<Value, HotFolderError> {
+    let path = rules_path(group_dir);
+    let text = std::fs::read_to_string(&path).map_err(|e| HotFolderError::CouldNotReadRules(path.clone(), e))?;
+    serde_json::from_str(&text).map_err(|e| HotFolderError::InvalidRules(path, e))
+}
+
+fn append_to_log(path: &Path, result: &WatchResult) -> Result<(), HotFolderError> {
+    let line = serde_json::to_string(result).unwrap_or_default();
+    let mut log = OpenOptions::new().create(true).append(true).open(path).map_err(|e| HotFolderError::CouldNotWriteLog(path.to_path_buf(), e))?;
+    writeln!(log, "{line}").map_err(|e| HotFolderError::CouldNotWriteLog(path.to_path_buf(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::{Path, PathBuf}, sync::Arc};
+    use serde_json::Value;
+    use crate::{config::NamingConfig, handlers::job_history::{HistoryQuery, JobHistory, JobStatus}};
+    use super::{check_group, group_dirs, last_checked_signature, rules_path, FolderSignature};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let folder = std::env::temp_dir().join(format!("mw-files-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(&folder).unwrap();
+        folder
+    }
+
+    #[test]
+    fn finds_rules_next_to_the_group_folder() {
+        assert_eq!(rules_path(Path::new("/hot/Smith_01")), Path::new("/hot/Smith_01.final_check.json"));
+    }
+
+    #[test]
+    fn watches_only_folders() {
+        let folder = temp_dir("hot-folders-groups");
+        std::fs::create_dir(folder.join("Smith_01")).unwrap();
+        std::fs::write(folder.join("Smith_01.final_check.json"), "{}").unwrap();
+        assert_eq!(group_dirs(&folder).unwrap(), [folder.join("Smith_01")]);
+        assert!(group_dirs(&folder.join("missing")).is_err());
+    }
+
+    #[test]
+    fn records_checks_with_the_folder_signature() {
+        let folder = temp_dir("hot-folders-check");
+        let group_dir = folder.join("Smith_01");
+        std::fs::create_dir(&group_dir).unwrap();
+        let history = Arc::new(JobHistory::open(&folder.join("jobs.sqlite")).unwrap());
+        assert_eq!(last_checked_signature(&group_dir, &history), None);
+
+        let signature = FolderSignature { files: 2, bytes: 2048, last_modified: 1_700_000_000_000 };
+        let result = check_group(&group_dir, signature, &NamingConfig::default(), &history);
+        assert!(!result.passed);
+        assert!(result.message.contains("could not read rules file"), "{}", result.message);
+
+        assert_eq!(last_checked_signature(&group_dir, &history), Some(signature));
+        let jobs = history.list(&HistoryQuery { limit: 10, ..HistoryQuery::default() }).unwrap();
+        assert_eq!((jobs.len(), jobs[0].status, jobs[0].submitted_by.as_str()), (1, JobStatus::Failure, "hot_folders"));
+        assert_eq!(history.get(jobs[0].id).unwrap().findings, Some(Value::Null));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,31 @@
+use glob::{GlobError, PatternError};
+use mw_files::qc::final_check::error::FCError;
+use crate::config::error::ConfigError;
+
+#[derive(Debug)]
+pub enum HotFolderError {
+    CouldNotReadFolder(std::path::PathBuf, std::io::Error),
+    InvalidDirectory(PatternError),
+    InvalidFile(GlobError),
+    CouldNotReadRules(std::path::PathBuf, std::io::Error),
+    InvalidRules(std::path::PathBuf, serde_json::Error),
+    InvalidNamingProfile(ConfigError),
+    FinalCheckFailed(FCError),
+    CouldNotWriteLog(std::path::PathBuf, std::io::Error),
+}
+impl std::error::Error for HotFolderError {}
+impl std::fmt::Display for HotFolderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter)
+    -> std::fmt::Result {
+        match self {
+            HotFolderError::CouldNotReadFolder(path, e) => write!(f, "could not read {}: {e}", path.to_string_lossy()),
+            HotFolderError::InvalidDirectory(err) => write!(f, "invalid directory: {err}"),
+            HotFolderError::InvalidFile(err) => write!(f, "invalid file path: {err}"),
+            HotFolderError::CouldNotReadRules(path, e) => write!(f, "could not read rules file {}: {e}", path.to_string_lossy()),
+            HotFolderError::InvalidRules(path, e) => write!(f, "invalid rules file {}: {e}", path.to_string_lossy()),
+            HotFolderError::InvalidNamingProfile(e) => write!(f, "{e}"),
+            HotFolderError::FinalCheckFailed(e) => write!(f, "{e}"),
+            HotFolderError::CouldNotWriteLog(path, e) => write!(f, "could not write to results log {}: {e}", path.to_string_lossy()),
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,350 @@
+pub mod error;
+use std::{path::Path, sync::{Mutex, MutexGuard}, time::{SystemTime, UNIX_EPOCH}};
+use actix_web::{web, HttpRequest, HttpResponse};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use error::JobHistoryError;
+use crate::{config::ServerConfig, handlers::auth};
+
+/// Every job the worker runs, kept in a local SQLite database so results outlive the socket that asked for them.
+pub struct JobHistory {
+    connection: Mutex<Connection>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Success,
+    Failure,
+}
+impl std::fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            JobStatus::Running => write!(f, "running"),
+            JobStatus::Success => write!(f, "success"),
+            JobStatus::Failure => write!(f, "failure"),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct JobRecord {
+    pub id: i64,
+    /// Name of the token the job was sent with, or `hot_folders` for hot folder checks.
+    pub submitted_by: String,
+    pub job: String,
+    pub order_dir: String,
+    /// Folder name of `order_dir`.
+    pub order_name: String,
+    /// Seconds since the Unix epoch.
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+    pub status: JobStatus,
+    pub message: Option<String>,
+    /// Only included when fetching a single job.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request: Option<Value>,
+    /// Data the job sent back when it finished. Only included when fetching a single job.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub findings: Option<Value>,
+}
+
+/// Filters for listing past jobs, newest first.
+#[derive(Deserialize, Debug, Default)]
+pub struct HistoryQuery {
+    /// Matches either an order's directory or its folder name.
+    pub order: Option<String>,
+    pub submitted_by: Option<String>,
+    pub job: Option<String>,
+    pub status: Option<JobStatus>,
+    #[serde(default = "default_limit")]
+    pub limit: u32,
+}
+fn default_limit() -> u32 { 50 }
+
+#[derive(Deserialize, Debug)]
+struct HistoryData {
+    #[serde(default)]
+    data: HistoryQuery,
+}
+
+#[derive(Deserialize, Debug)]
+struct JobResultData {
+    data: JobResultRequest,
+}
+
+#[derive(Deserialize, Debug)]
+struct JobResultRequest {
+    id: i64,
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS jobs (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        submitted_by TEXT NOT NULL,
+        job TEXT NOT NULL,
+        order_dir TEXT NOT NULL,
+        order_name TEXT NOT NULL,
+        request TEXT NOT NULL,
+        started_at INTEGER NOT NULL,
+        finished_at INTEGER,
+        status TEXT NOT NULL,
+        message TEXT,
+        findings TEXT
+    );
+    CREATE INDEX IF NOT EXISTS jobs_by_order_dir ON jobs (order_dir);
+    CREATE INDEX IF NOT EXISTS jobs_by_order_name ON jobs (order_name);
+    CREATE TABLE IF NOT EXISTS folder_signatures (
+        job_id INTEGER PRIMARY KEY REFERENCES jobs (id),
+        signature TEXT NOT NULL
+    );
+";
+const SUMMARY_COLUMNS: &str = "id, submitted_by, job, order_dir, order_name, started_at, finished_at, status, message";
+
+
+impl JobHistory {
+    /// Opens or creates the database. Jobs still marked as running were cut off by the worker stopping, so they're marked as failed.
+    pub fn open(path: &Path) -> Result<JobHistory, JobHistoryError> {
+        let connection = Connection::open(path).map_err(|e| JobHistoryError::CouldNotOpen(path.to_path_buf(), e))?;
+        connection.execute_batch(SCHEMA).map_err(|e| JobHistoryError::CouldNotOpen(path.to_path_buf(), e))?;
+        connection.execute("UPDATE jobs SET status = ?1, message = ?2 WHERE status = ?3",
+            params![JobStatus::Failure.to_string(), "the worker stopped before the job finished", JobStatus::Running.to_string()])
+            .map_err(|e| JobHistoryError::CouldNotOpen(path.to_path_buf(), e))?;
+        Ok(JobHistory { connection: Mutex::new(connection) })
+    }
+
+    /// Records that a job started, returning its id.
+    pub fn start(&self, submitted_by: &str, job: &str, order_dir: &str, request: &Value) -> Result<i64, JobHistoryError> {
+        let order_name = Path::new(order_dir).file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let connection = self.lock();
+        connection.execute(
+            "INSERT INTO jobs (submitted_by, job, order_dir, order_name, request, started_at, status) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![submitted_by, job, order_dir, order_name, request.to_string(), now(), JobStatus::Running.to_string()],
+        ).map_err(JobHistoryError::DatabaseError)?;
+        Ok(connection.last_insert_rowid())
+    }
+
+    pub fn finish(&self, id: i64, status: JobStatus, message: Option<&str>, findings: &Value) -> Result<(), JobHistoryError> {
+        self.lock().execute(
+            "UPDATE jobs SET finished_at = ?1, status = ?2, message = ?3, findings = ?4 WHERE id = ?5",
+            params![now(), status.to_string(), message, findings.to_string(), id],
+        ).map_err(JobHistoryError::DatabaseError)?;
+        Ok(())
+    }
+
+    /// Lists jobs matching `query`, newest first, without their requests and findings.
+    pub fn list(&self, query: &HistoryQuery) -> Result<Vec<JobRecord>, JobHistoryError> {
+        let connection = self.lock();
+        let mut statement = connection.prepare(&format!(
+            "SELECT {SUMMARY_COLUMNS} FROM jobs
+            WHERE (?1 IS NULL OR order_dir = ?1 OR order_name = ?1) AND (?2 IS NULL OR submitted_by = ?2) AND (?3 IS NULL OR job = ?3) AND (?4 IS NULL OR status = ?4)
+            ORDER BY id DESC LIMIT ?5"
+        )).map_err(JobHistoryError::DatabaseError)?;
+        let status = query.status.map(|status| status.to_string());
+        let records = statement.query_map(params![query.order, query.submitted_by, query.job, status, query.limit], read_summary)
+            .map_err(JobHistoryError::DatabaseError)?;
+        records.collect::<Result<Vec<_>, _>>().map_err(JobHistoryError::DatabaseError)
+    }
+
+    /// Fetches one job along with its request and findings.
+    pub fn get(&self, id: i64) -> Result<JobRecord, JobHistoryError> {
+        let connection = self.lock();
+        let record = connection.query_row(&format!("SELECT {SUMMARY_COLUMNS}, request, findings FROM jobs WHERE id = ?1"), params![id], |row| {
+            let mut record = read_summary(row)?;
+            record.request = row.get::<_, Option<String>>(9)?.and_then(|text| serde_json::from_str(&text).ok());
+            record.findings = row.get::<_, Option<String>>(10)?.and_then(|text| serde_json::from_str(&text).ok());
+            Ok(record)
+        }).optional().map_err(JobHistoryError::DatabaseError)?;
+        record.ok_or(JobHistoryError::UnknownJob(id))
+    }
+
+    /// Records what the checked folder looked like when a job ran, apart from the job's findings.
+    pub fn record_signature(&self, id: i64, signature: &Value) -> Result<(), JobHistoryError> {
+        self.lock().execute(
+            "INSERT OR REPLACE INTO folder_signatures (job_id, signature) VALUES (?1, ?2)",
+            params![id, signature.to_string()],
+        ).map_err(JobHistoryError::DatabaseError)?;
+        Ok(())
+    }
+
+    /// Folder signature of the newest finished run of `job` that `submitted_by` sent for exactly `order_dir`, if there's been one.
+    pub fn last_signature(&self, submitted_by: &str, job: &str, order_dir: &str) -> Result<Option<Value>, JobHistoryError> {
+        let signature = self.lock().query_row(
+            "SELECT folder_signatures.signature FROM jobs JOIN folder_signatures ON folder_signatures.job_id = jobs.id
+            WHERE jobs.submitted_by = ?1 AND jobs.job = ?2 AND jobs.order_dir = ?3 AND jobs.finished_at IS NOT NULL ORDER BY jobs.id DESC LIMIT 1",
+            params![submitted_by, job, order_dir],
+            |row| row.get::<_, String>(0),
+        ).optional().map_err(JobHistoryError::DatabaseError)?;
+        Ok(signature.and_then(|text| serde_json::from_str(&text).ok()))
+    }
+
+    /// A panic while holding the lock can't leave a half written row, so a poisoned lock is still usable.
+    fn lock(&self) -> MutexGuard<'_, Connection> {
+        self.connection.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Lists past jobs for a `job_history` websocket request.
+pub fn list_from_request(history: &JobHistory, request_json: Value) -> Result<Vec<JobRecord>, JobHistoryError> {
+    let query = serde_json::from_value::<HistoryData>(request_json).map_err(JobHistoryError::DeserializeError)?.data;
+    history.list(&query)
+}
+
+/// Fetches a past job for a `job_result` websocket request.
+pub fn get_from_request(history: &JobHistory, request_json: Value) -> Result<JobRecord, JobHistoryError> {
+    let request = serde_json::from_value::<JobResultData>(request_json).map_err(JobHistoryError::DeserializeError)?.data;
+    history.get(request.id)
+}
+
+/// `GET /history`, filtered by the query string in the same way as [`HistoryQuery`].
+pub async fn list_http(req: HttpRequest, query: web::Query<HistoryQuery>, config: web::Data<ServerConfig>, history: web::Data<JobHistory>) -> HttpResponse {
+    if let Err(e) = auth::authenticate(&req, &config.tokens) {
+        return HttpResponse::Unauthorized().body(e.to_string());
+    }
+    match web::block(move || history.list(&query)).await {
+        Ok(Ok(records)) => HttpResponse::Ok().json(records),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// `GET /history/{id}`.
+pub async fn get_http(req: HttpRequest, id: web::Path<i64>, config: web::Data<ServerConfig>, history: web::Data<JobHistory>) -> HttpResponse {
+    if let Err(e) = auth::authenticate(&req, &config.tokens) {
+        return HttpResponse::Unauthorized().body(e.to_string());
+    }
+    let id = id.into_inner();
+    match web::block(move || history.get(id)).await {
+        Ok(Ok(record)) => HttpResponse::Ok().json(record),
+        Ok(Err(e @ JobHistoryError::UnknownJob(_))) => HttpResponse::NotFound().body(e.to_string()),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+fn read_summary(row: &Row) -> rusqlite::Result<JobRecord> {
+    let status: String = row.get(7)?;
+    Ok(JobRecord {
+        id: row.get(0)?,
+        submitted_by: row.get(1)?,
+        job: row.get(2)?,
+        order_dir: row.get(3)?,
+        order_name: row.get(4)?,
+        started_at: row.get(5)?,
+        finished_at: row.get(6)?,
+        status: match &*status {
+            "success" => JobStatus::Success,
+            "failure" => JobStatus::Failure,
+            _ => JobStatus::Running,
+        },
+        message: row.get(8)?,
+        request: None,
+        findings: None,
+    })
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use serde_json::{json, Value};
+    use super::{get_from_request, list_from_request, HistoryQuery, JobHistory, JobStatus, error::JobHistoryError};
+
+    /// Opens a fresh database in a temporary folder, returning its path so it can be reopened.
+    fn open_history(name: &str) -> (JobHistory, PathBuf) {
+        let folder = std::env::temp_dir().join(format!("mw-files-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(&folder).unwrap();
+        let path = folder.join("jobs.sqlite");
+        (JobHistory::open(&path).unwrap(), path)
+    }
+
+    #[test]
+    fn keeps_signatures_apart_from_findings() {
+        let (history, _) = open_history("history-signatures");
+        let id = history.start("hot_folders", "final_check", "/orders/Smith/01", &json!({})).unwrap();
+        history.record_signature(id, &json!({ "files": 3 })).unwrap();
+        assert_eq!(history.last_signature("hot_folders", "final_check", "/orders/Smith/01").unwrap(), None);
+
+        history.finish(id, JobStatus::Success, None, &Value::Null).unwrap();
+        assert_eq!(history.last_signature("hot_folders", "final_check", "/orders/Smith/01").unwrap(), Some(json!({ "files": 3 })));
+        assert_eq!(history.get(id).unwrap().findings, Some(Value::Null));
+    }
+
+    #[test]
+    fn looks_up_signatures_by_submitter_job_and_folder() {
+        let (history, _) = open_history("history-signature-lookup");
+        for (submitted_by, job, order_dir, files) in [("hot_folders", "final_check", "/orders/Smith/01", 1), ("hot_folders", "final_check", "/orders/Smith/01", 2),
+            ("front desk", "final_check", "/orders/Smith/01", 3), ("hot_folders", "bag_order", "/orders/Smith/01", 4), ("hot_folders", "final_check", "/orders/Smith/02", 5)] {
+            let id = history.start(submitted_by, job, order_dir, &json!({})).unwrap();
+            history.record_signature(id, &json!({ "files": files })).unwrap();
+            history.finish(id, JobStatus::Failure, Some("no files"), &Value::Null).unwrap();
+        }
+        assert_eq!(history.last_signature("hot_folders", "final_check", "/orders/Smith/01").unwrap(), Some(json!({ "files": 2 })));
+        assert_eq!(history.last_signature("hot_folders", "final_check", "/orders/Smith").unwrap(), None);
+    }
+    #[test]
+    fn fetches_a_job_with_its_request_and_findings() {
+        let (history, _) = open_history("history-get");
+        let request = json!({ "dir": "/orders/SmithJ", "job": "near_duplicates", "data": { "threshold": 0.9 } });
+        let id = history.start("lab", "near_duplicates", "/orders/SmithJ", &request).unwrap();
+        history.finish(id, JobStatus::Success, None, &json!([["a.jpg", "b.jpg"]])).unwrap();
+
+        let record = get_from_request(&history, json!({ "data": { "id": id } })).unwrap();
+        assert_eq!((record.order_name.as_str(), record.status), ("SmithJ", JobStatus::Success));
+        assert!(record.finished_at.is_some_and(|finished_at| finished_at >= record.started_at));
+        assert_eq!(record.request, Some(request));
+        assert_eq!(record.findings, Some(json!([["a.jpg", "b.jpg"]])));
+        assert!(matches!(history.get(id + 1), Err(JobHistoryError::UnknownJob(_))));
+        assert!(matches!(get_from_request(&history, json!({ "data": {} })), Err(JobHistoryError::DeserializeError(_))));
+    }
+
+    #[test]
+    fn lists_jobs_newest_first_by_filter() {
+        let (history, _) = open_history("history-list");
+        for (submitted_by, job, order_dir, status) in [("lab", "final_check", "/orders/SmithJ", JobStatus::Success), ("front desk", "final_check", "/orders/DoeA", JobStatus::Failure),
+            ("lab", "bag_order", "/orders/SmithJ", JobStatus::Success), ("lab", "final_check", "/archive/SmithJ", JobStatus::Failure)] {
+            let id = history.start(submitted_by, job, order_dir, &json!({})).unwrap();
+            history.finish(id, status, None, &Value::Null).unwrap();
+        }
+        let ids = |query: HistoryQuery| history.list(&query).unwrap().iter().map(|record| record.id).collect::<Vec<_>>();
+
+        assert_eq!(ids(HistoryQuery { limit: 10, ..HistoryQuery::default() }), [4, 3, 2, 1]);
+        assert_eq!(ids(HistoryQuery { limit: 2, ..HistoryQuery::default() }), [4, 3]);
+        assert_eq!(ids(HistoryQuery { order: Some(String::from("SmithJ")), limit: 10, ..HistoryQuery::default() }), [4, 3, 1]);
+        assert_eq!(ids(HistoryQuery { order: Some(String::from("/orders/SmithJ")), limit: 10, ..HistoryQuery::default() }), [3, 1]);
+        assert_eq!(ids(HistoryQuery { submitted_by: Some(String::from("lab")), job: Some(String::from("final_check")), limit: 10, ..HistoryQuery::default() }), [4, 1]);
+        assert_eq!(ids(HistoryQuery { status: Some(JobStatus::Failure), limit: 10, ..HistoryQuery::default() }), [4, 2]);
+
+        let records = list_from_request(&history, json!({ "data": { "status": "success" } })).unwrap();
+        assert_eq!(records.iter().map(|record| record.id).collect::<Vec<_>>(), [3, 1]);
+        assert!(records.iter().all(|record| record.request.is_none() && record.findings.is_none()));
+        assert!(matches!(list_from_request(&history, json!({ "data": { "status": "done" } })), Err(JobHistoryError::DeserializeError(_))));
+    }
+
+    #[test]
+    fn marks_jobs_cut_off_by_a_restart_as_failed() {
+        let (history, path) = open_history("history-reopen");
+        let finished = history.start("lab", "final_check", "/orders/SmithJ", &json!({})).unwrap();
+        history.finish(finished, JobStatus::Success, None, &Value::Null).unwrap();
+        let running = history.start("lab", "correct_slides", "/orders/SmithJ", &json!({})).unwrap();
+        drop(history);
+
+        let history = JobHistory::open(&path).unwrap();
+        assert_eq!(history.get(finished).unwrap().status, JobStatus::Success);
+        let record = history.get(running).unwrap();
+        assert_eq!(record.status, JobStatus::Failure);
+        assert_eq!(record.message.as_deref(), Some("the worker stopped before the job finished"));
+    }
+
+    #[test]
+    fn reports_a_database_it_cant_open() {
+        let (_, path) = open_history("history-open");
+        assert!(matches!(JobHistory::open(path.parent().unwrap()), Err(JobHistoryError::CouldNotOpen(..))));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,19 @@
+#[derive(Debug)]
+pub enum JobHistoryError {
+    CouldNotOpen(std::path::PathBuf, rusqlite::Error),
+    DatabaseError(rusqlite::Error),
+    DeserializeError(serde_json::Error),
+    UnknownJob(i64),
+}
+impl std::error::Error for JobHistoryError {}
+impl std::fmt::Display for JobHistoryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter)
+    -> std::fmt::Result {
+        match self {
+            JobHistoryError::CouldNotOpen(path, e) => write!(f, "could not open job history {}: {e}", path.to_string_lossy()),
+            JobHistoryError::DatabaseError(e) => write!(f, "job history error: {e}"),
+            JobHistoryError::DeserializeError(e) => write!(f, "could not deserialize request: {e}"),
+            JobHistoryError::UnknownJob(id) => write!(f, "no job has id {id}"),
+        }
+    }
+}
\ No newline at end of file
@@ -1,7 +1,8 @@
-use actix::Actor;
-use serde::Deserialize;
+use actix::Recipient;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use crate::{qc::final_check, utils::send_text::{self, WsStatus}, WorkerWs};
+use mw_files::{delivery::{bagit, contact_sheets, derivatives, gallery, manifest, order_manifest, packaging}, autocorr::{pipeline, photos::{color_restore, correct_slides, dust_removal, invert_negatives, orientation}}, qc::{final_check, near_duplicates}};
+use crate::{config::ServerConfig, handlers::{allowed_roots, auth::{self, Client}, job_history::{self, JobHistory, JobStatus}, send_text::{self, SocketText, WsReporter, WsStatus}}};
 
 mod error;
 use error::ServicesError;
@@ -12,32 +13,324 @@ struct ServiceRequest {
     dir: String
 }
 
-pub fn service_router(request: String, ctx: &mut<WorkerWs as Actor>::Context) -> Result<(), ServicesError> {
-    let json = serde_json::from_str(&request).map_err(|e| ServicesError::RequestParseError(e.to_string()))?;
+pub fn service_router(request: String, config: &ServerConfig, client: &Client, history: &JobHistory, socket: &Recipient<SocketText>) -> Result<(), ServicesError> {
+    let json: Value = serde_json::from_str(&request).map_err(|e| ServicesError::RequestParseError(e.to_string()))?;
+    // Looking up past jobs doesn't take a directory, and isn't worth recording itself
+    match json.get("job").and_then(Value::as_str) {
+        Some("job_history") => return list_job_history(history, json, socket),
+        Some("job_result") => return get_job_result(history, json, socket),
+        _ => {}
+    }
     let job_request = parse_base_job(&json).map_err(|_| ServicesError::InvalidJob(None))?;
-    return match &*job_request.job {
+
+    // The job still runs if it can't be recorded, since the history is only a log of what happened
+    let job_id = history.start(&client.name, &job_request.job, &job_request.dir, &json)
+        .inspect_err(|e| println!("Could not record {} job: {e}", job_request.job))
+        .ok();
+    let result = run_job(job_request, json, config, client, socket);
+    if let Some(job_id) = job_id {
+        let finished = match &result {
+            Ok(findings) => history.finish(job_id, JobStatus::Success, None, findings),
+            Err(e) => history.finish(job_id, JobStatus::Failure, Some(&e.to_string()), &Value::Null),
+        };
+        if let Err(e) = finished {
+            println!("Could not record result of job {job_id}: {e}");
+        }
+    }
+    result.map(|_| ())
+}
+
+/// Runs a job, returning the data it sent back to the client.
+fn run_job(job_request: ServiceRequest, json: Value, config: &ServerConfig, client: &Client, socket: &Recipient<SocketText>) -> Result<Value, ServicesError> {
+    if let Err(e) = auth::authorize(client, &job_request.job) {
+        send_text::send(&format!("Request refused: {e}"), Some(WsStatus::Failure), socket);
+        return Err(ServicesError::NotAuthorized(e.to_string()));
+    }
+    if let Err(e) = allowed_roots::check_request(&config.allowed_roots, &json) {
+        send_text::send(&format!("Request refused: {e}"), Some(WsStatus::Failure), socket);
+        return Err(ServicesError::PathNotAllowed(e.to_string()));
+    }
+    // Both jobs read a final check request, which takes its missing precisions from a naming profile
+    let json = match &*job_request.job {
+        "final_check" | "bag_order" => config.naming.apply(json).map_err(|e| {
+            send_text::send(&format!("Invalid naming profile: {e}"), Some(WsStatus::Failure), socket);
+            ServicesError::InvalidNamingProfile(e.to_string())
+        })?,
+        _ => json,
+    };
+    // Correction jobs that name a pipeline run it in place of their own single step
+    let job = match &*job_request.job {
+        "correct_slides" | "restore_colors" | "invert_negatives" | "remove_dust" if json.pointer("/data/pipeline").is_some_and(|name| !name.is_null()) => "run_pipeline",
+        job => job,
+    };
+    match job {
         "final_check" => {
-            send_text::send("Starting final check!", Some(WsStatus::Busy), ctx);
-            match final_check::final_check(job_request.dir, json.clone(), ctx) {
+            send_text::send("Starting final check!", Some(WsStatus::Busy), socket);
+            match final_check::final_check(job_request.dir, json.clone(), &mut WsReporter(socket)) {
                 Ok(_) => {
-                    send_text::send("Final check successful!", Some(WsStatus::Success), ctx);
-                    Ok(())
+                    send_text::send("Final check successful!", Some(WsStatus::Success), socket);
+                    Ok(Value::Null)
                 }
                 Err(e) => {
-                    send_text::send(&*format!("Final check unsuccessful: {e}"), Some(WsStatus::Failure), ctx);
+                    send_text::send(&format!("Final check unsuccessful: {e}"), Some(WsStatus::Failure), socket);
                     Err(ServicesError::InvalidFinalCheck(e.to_string()))
                 }
             }
         },
+        "near_duplicates" => {
+            send_text::send("Starting near-duplicate search!", Some(WsStatus::Busy), socket);
+            match near_duplicates::find_near_duplicates(job_request.dir, json.clone(), &mut WsReporter(socket)) {
+                Ok(clusters) => {
+                    send_text::send_data(&format!("Found {} clusters of near-duplicates!", clusters.len()), Some(WsStatus::Success), &clusters, socket);
+                    Ok(findings(&clusters))
+                }
+                Err(e) => {
+                    send_text::send(&format!("Near-duplicate search unsuccessful: {e}"), Some(WsStatus::Failure), socket);
+                    Err(ServicesError::InvalidNearDuplicates(e.to_string()))
+                }
+            }
+        },
+        "bake_orientation" => {
+            send_text::send("Starting orientation baking!", Some(WsStatus::Busy), socket);
+            match orientation::bake_orientation(job_request.dir, json.clone(), &mut WsReporter(socket)) {
+                Ok(rotated_count) => {
+                    send_text::send(&format!("Orientation baking successful! Rotated {rotated_count} files."), Some(WsStatus::Success), socket);
+                    Ok(Value::from(rotated_count))
+                }
+                Err(e) => {
+                    send_text::send(&format!("Orientation baking unsuccessful: {e}"), Some(WsStatus::Failure), socket);
+                    Err(ServicesError::InvalidOrientationBake(e.to_string()))
+                }
+            }
+        },
         "correct_slides" => {
-            send_text::send("Starting slides correction!", Some(WsStatus::Busy), ctx);
-            
-            Ok(())
+            send_text::send("Starting slides correction!", Some(WsStatus::Busy), socket);
+            match correct_slides::correct_slides(json.clone(), &mut WsReporter(socket)) {
+                Ok(corrections) => {
+                    send_text::send_data(&format!("Slides correction successful! Corrected {} slides.", corrections.len()), Some(WsStatus::Success), &corrections, socket);
+                    Ok(findings(&corrections))
+                }
+                Err(e) => {
+                    send_text::send(&format!("Slides correction unsuccessful: {e}"), Some(WsStatus::Failure), socket);
+                    Err(ServicesError::InvalidSlidesCorrection(e.to_string()))
+                }
+            }
+        },
+        "restore_colors" => {
+            send_text::send("Starting color restoration!", Some(WsStatus::Busy), socket);
+            match color_restore::restore_colors(json.clone(), &mut WsReporter(socket)) {
+                Ok(restorations) => {
+                    send_text::send_data(&format!("Color restoration successful! Restored {} files.", restorations.len()), Some(WsStatus::Success), &restorations, socket);
+                    Ok(findings(&restorations))
+                }
+                Err(e) => {
+                    send_text::send(&format!("Color restoration unsuccessful: {e}"), Some(WsStatus::Failure), socket);
+                    Err(ServicesError::InvalidColorRestoration(e.to_string()))
+                }
+            }
+        },
+        "invert_negatives" => {
+            send_text::send("Starting negative inversion!", Some(WsStatus::Busy), socket);
+            match invert_negatives::invert_negatives(json.clone(), &mut WsReporter(socket)) {
+                Ok(inversions) => {
+                    send_text::send_data(&format!("Negative inversion successful! Inverted {} negatives.", inversions.len()), Some(WsStatus::Success), &inversions, socket);
+                    Ok(findings(&inversions))
+                }
+                Err(e) => {
+                    send_text::send(&format!("Negative inversion unsuccessful: {e}"), Some(WsStatus::Failure), socket);
+                    Err(ServicesError::InvalidNegativeInversion(e.to_string()))
+                }
+            }
+        },
+        "remove_dust" => {
+            send_text::send("Starting dust removal!", Some(WsStatus::Busy), socket);
+            match dust_removal::remove_dust_from_folder(json.clone(), &mut WsReporter(socket)) {
+                Ok(removals) => {
+                    send_text::send_data(&format!("Dust removal successful! Cleaned {} files.", removals.len()), Some(WsStatus::Success), &removals, socket);
+                    Ok(findings(&removals))
+                }
+                Err(e) => {
+                    send_text::send(&format!("Dust removal unsuccessful: {e}"), Some(WsStatus::Failure), socket);
+                    Err(ServicesError::InvalidDustRemoval(e.to_string()))
+                }
+            }
+        },
+        "run_pipeline" => {
+            send_text::send("Starting correction pipeline!", Some(WsStatus::Busy), socket);
+            match pipeline::run_pipeline(json.clone(), &config.pipelines, &mut WsReporter(socket)) {
+                Ok(runs) => {
+                    send_text::send_data(&format!("Correction pipeline successful! Processed {} files.", runs.len()), Some(WsStatus::Success), &runs, socket);
+                    Ok(findings(&runs))
+                }
+                Err(e) => {
+                    send_text::send(&format!("Correction pipeline unsuccessful: {e}"), Some(WsStatus::Failure), socket);
+                    Err(ServicesError::InvalidPipeline(e.to_string()))
+                }
+            }
+        },
+        "generate_jpegs" => {
+            send_text::send("Starting JPEG generation!", Some(WsStatus::Busy), socket);
+            match derivatives::generate_jpegs(job_request.dir, json.clone(), &mut WsReporter(socket)) {
+                Ok(derivatives) => {
+                    send_text::send_data(&format!("JPEG generation successful! Wrote {} files.", derivatives.len()), Some(WsStatus::Success), &derivatives, socket);
+                    Ok(findings(&derivatives))
+                }
+                Err(e) => {
+                    send_text::send(&format!("JPEG generation unsuccessful: {e}"), Some(WsStatus::Failure), socket);
+                    Err(ServicesError::InvalidDerivatives(e.to_string()))
+                }
+            }
+        },
+        "contact_sheets" => {
+            send_text::send("Starting contact sheet generation!", Some(WsStatus::Busy), socket);
+            match contact_sheets::generate_contact_sheets(job_request.dir, json.clone(), &mut WsReporter(socket)) {
+                Ok(sheets) => {
+                    send_text::send_data(&format!("Contact sheet generation successful! Laid out {} groups.", sheets.len()), Some(WsStatus::Success), &sheets, socket);
+                    Ok(findings(&sheets))
+                }
+                Err(e) => {
+                    send_text::send(&format!("Contact sheet generation unsuccessful: {e}"), Some(WsStatus::Failure), socket);
+                    Err(ServicesError::InvalidContactSheets(e.to_string()))
+                }
+            }
+        },
+        "export_gallery" => {
+            send_text::send("Starting gallery export!", Some(WsStatus::Busy), socket);
+            match gallery::export_gallery(job_request.dir, json.clone(), &mut WsReporter(socket)) {
+                Ok(export) => {
+                    send_text::send_data(&format!("Gallery export successful! Added {} images.", export.images), Some(WsStatus::Success), &export, socket);
+                    Ok(findings(&export))
+                }
+                Err(e) => {
+                    send_text::send(&format!("Gallery export unsuccessful: {e}"), Some(WsStatus::Failure), socket);
+                    Err(ServicesError::InvalidGallery(e.to_string()))
+                }
+            }
+        },
+        "package_order" => {
+            send_text::send("Starting order packaging!", Some(WsStatus::Busy), socket);
+            match packaging::package_order(job_request.dir, json.clone(), &mut WsReporter(socket)) {
+                Ok(archives) => {
+                    send_text::send_data(&format!("Order packaging successful! Wrote {} archives.", archives.len()), Some(WsStatus::Success), &archives, socket);
+                    Ok(findings(&archives))
+                }
+                Err(e) => {
+                    send_text::send(&format!("Order packaging unsuccessful: {e}"), Some(WsStatus::Failure), socket);
+                    Err(ServicesError::InvalidPackaging(e.to_string()))
+                }
+            }
+        },
+        "write_manifest" => {
+            send_text::send("Starting manifest generation!", Some(WsStatus::Busy), socket);
+            match manifest::write_manifest(job_request.dir, json.clone(), &mut WsReporter(socket)) {
+                Ok(summary) => {
+                    send_text::send_data(&format!("Manifest generation successful! Hashed {} files.", summary.files), Some(WsStatus::Success), &summary, socket);
+                    Ok(findings(&summary))
+                }
+                Err(e) => {
+                    send_text::send(&format!("Manifest generation unsuccessful: {e}"), Some(WsStatus::Failure), socket);
+                    Err(ServicesError::InvalidManifest(e.to_string()))
+                }
+            }
+        },
+        "verify_manifest" => {
+            send_text::send("Starting manifest verification!", Some(WsStatus::Busy), socket);
+            match manifest::verify_manifest(job_request.dir, json.clone(), &mut WsReporter(socket)) {
+                Ok(verification) if verification.is_intact() => {
+                    send_text::send_data(&format!("Manifest verification successful! Verified {} files.", verification.verified), Some(WsStatus::Success), &verification, socket);
+                    Ok(findings(&verification))
+                }
+                Ok(verification) => {
+                    let msg = format!("Manifest verification unsuccessful: {} missing, {} extra and {} mismatched files",
+                        verification.missing.len(), verification.extra.len(), verification.mismatched.len());
+                    send_text::send_data(&msg, Some(WsStatus::Failure), &verification, socket);
+                    Err(ServicesError::InvalidManifest(msg))
+                }
+                Err(e) => {
+                    send_text::send(&format!("Manifest verification unsuccessful: {e}"), Some(WsStatus::Failure), socket);
+                    Err(ServicesError::InvalidManifest(e.to_string()))
+                }
+            }
+        },
+        "bag_order" => {
+            send_text::send("Starting bagging!", Some(WsStatus::Busy), socket);
+            match bagit::bag_order(job_request.dir, json.clone(), &mut WsReporter(socket)) {
+                Ok(summary) => {
+                    send_text::send_data(&format!("Bagging successful! Bagged {} files.", summary.payload_files), Some(WsStatus::Success), &summary, socket);
+                    Ok(findings(&summary))
+                }
+                Err(e) => {
+                    send_text::send(&format!("Bagging unsuccessful: {e}"), Some(WsStatus::Failure), socket);
+                    Err(ServicesError::InvalidBagIt(e.to_string()))
+                }
+            }
+        },
+        "validate_bag" => {
+            send_text::send("Starting bag validation!", Some(WsStatus::Busy), socket);
+            match bagit::validate_bag(job_request.dir, &mut WsReporter(socket)) {
+                Ok(validation) if validation.is_valid() => {
+                    send_text::send_data(&format!("Bag validation successful! Validated {} files.", validation.payload_files), Some(WsStatus::Success), &validation, socket);
+                    Ok(findings(&validation))
+                }
+                Ok(validation) => {
+                    let msg = format!("Bag validation unsuccessful: found {} problems", validation.problems.len());
+                    send_text::send_data(&msg, Some(WsStatus::Failure), &validation, socket);
+                    Err(ServicesError::InvalidBagIt(msg))
+                }
+                Err(e) => {
+                    send_text::send(&format!("Bag validation unsuccessful: {e}"), Some(WsStatus::Failure), socket);
+                    Err(ServicesError::InvalidBagIt(e.to_string()))
+                }
+            }
+        },
+        "export_order_manifest" => {
+            send_text::send("Starting order manifest export!", Some(WsStatus::Busy), socket);
+            match order_manifest::export_order_manifest(job_request.dir, json.clone(), &mut WsReporter(socket)) {
+                Ok(export) => {
+                    send_text::send_data(&format!("Order manifest export successful! Listed {} files.", export.total_files), Some(WsStatus::Success), &export, socket);
+                    Ok(findings(&export))
+                }
+                Err(e) => {
+                    send_text::send(&format!("Order manifest export unsuccessful: {e}"), Some(WsStatus::Failure), socket);
+                    Err(ServicesError::InvalidOrderManifest(e.to_string()))
+                }
+            }
         },
         _ => Err(ServicesError::InvalidJob(Some(String::from("Invalid job type specified in request!"))))
     }
 }
 
+fn list_job_history(history: &JobHistory, request_json: Value, socket: &Recipient<SocketText>) -> Result<(), ServicesError> {
+    match job_history::list_from_request(history, request_json) {
+        Ok(records) => {
+            send_text::send_data(&format!("Found {} past jobs!", records.len()), Some(WsStatus::Success), &records, socket);
+            Ok(())
+        }
+        Err(e) => {
+            send_text::send(&format!("Job history lookup unsuccessful: {e}"), Some(WsStatus::Failure), socket);
+            Err(ServicesError::InvalidJobHistory(e.to_string()))
+        }
+    }
+}
+
+fn get_job_result(history: &JobHistory, request_json: Value, socket: &Recipient<SocketText>) -> Result<(), ServicesError> {
+    match job_history::get_from_request(history, request_json) {
+        Ok(record) => {
+            send_text::send_data(&format!("Found job {}!", record.id), Some(WsStatus::Success), &record, socket);
+            Ok(())
+        }
+        Err(e) => {
+            send_text::send(&format!("Job result lookup unsuccessful: {e}"), Some(WsStatus::Failure), socket);
+            Err(ServicesError::InvalidJobHistory(e.to_string()))
+        }
+    }
+}
+
+/// Job results are always serializable, since they're sent back to the client the same way.
+fn findings<T: Serialize>(data: &T) -> Value {
+    serde_json::to_value(data).unwrap_or(Value::Null)
+}
+
 fn parse_base_job(request_json: &Value) -> std::result::Result<ServiceRequest, serde_json::Error> {
     serde_json::from_value(request_json.clone())
 }
\ No newline at end of file
@@ -3,6 +3,24 @@ pub enum ServicesError {
     RequestParseError(String),
     InvalidJob(Option<String>),
     InvalidFinalCheck(String),
+    InvalidNearDuplicates(String),
+    InvalidOrientationBake(String),
+    InvalidSlidesCorrection(String),
+    InvalidColorRestoration(String),
+    InvalidNegativeInversion(String),
+    InvalidDustRemoval(String),
+    InvalidPipeline(String),
+    InvalidDerivatives(String),
+    InvalidContactSheets(String),
+    InvalidGallery(String),
+    InvalidPackaging(String),
+    InvalidManifest(String),
+    InvalidBagIt(String),
+    InvalidOrderManifest(String),
+    InvalidNamingProfile(String),
+    PathNotAllowed(String),
+    NotAuthorized(String),
+    InvalidJobHistory(String),
 }
 impl std::error::Error for ServicesError {}
 impl std::fmt::Display for ServicesError {
@@ -14,7 +32,25 @@ impl std::fmt::Display for ServicesError {
                     Some(v) => write!(f, "Invalid job requested: {}", v),
                     None => write!(f, "Invalid job requested!")
                 },
-            ServicesError::InvalidFinalCheck(msg) => write!(f, "{}", msg)  
+            ServicesError::InvalidFinalCheck(msg) => write!(f, "{}", msg),
+            ServicesError::InvalidNearDuplicates(msg) => write!(f, "{}", msg),
+            ServicesError::InvalidOrientationBake(msg) => write!(f, "{}", msg),
+            ServicesError::InvalidSlidesCorrection(msg) => write!(f, "{}", msg),
+            ServicesError::InvalidColorRestoration(msg) => write!(f, "{}", msg),
+            ServicesError::InvalidNegativeInversion(msg) => write!(f, "{}", msg),
+            ServicesError::InvalidDustRemoval(msg) => write!(f, "{}", msg),
+            ServicesError::InvalidPipeline(msg) => write!(f, "{}", msg),
+            ServicesError::InvalidDerivatives(msg) => write!(f, "{}", msg),
+            ServicesError::InvalidContactSheets(msg) => write!(f, "{}", msg),
+            ServicesError::InvalidGallery(msg) => write!(f, "{}", msg),
+            ServicesError::InvalidPackaging(msg) => write!(f, "{}", msg),
+            ServicesError::InvalidManifest(msg) => write!(f, "{}", msg),
+            ServicesError::InvalidBagIt(msg) => write!(f, "{}", msg),
+            ServicesError::InvalidOrderManifest(msg) => write!(f, "{}", msg),
+            ServicesError::InvalidNamingProfile(msg) => write!(f, "{}", msg),
+            ServicesError::PathNotAllowed(msg) => write!(f, "{}", msg),
+            ServicesError::NotAuthorized(msg) => write!(f, "{}", msg),
+            ServicesError::InvalidJobHistory(msg) => write!(f, "{}", msg),
         }
     }
 }
\ No newline at end of file
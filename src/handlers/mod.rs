@@ -1 +1,7 @@
-pub mod jobs;
\ No newline at end of file
+pub mod jobs;
+pub mod send_text;
+pub mod auth;
+pub mod allowed_roots;
+pub mod job_history;
+pub mod hot_folders;
+pub mod final_check_cli;
\ No newline at end of file
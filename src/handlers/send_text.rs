@@ -0,0 +1,49 @@
+use actix::{Message, Recipient};
+use serde::Serialize;
+use mw_files::reporter::Reporter;
+
+/// A text frame for a websocket client, sent to its actor from whichever thread a job is running on.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct SocketText(pub String);
+
+pub enum WsStatus {
+    Success,
+    Failure,
+    Busy,
+}
+impl std::fmt::Display for WsStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WsStatus::Success => write!(f, "success"),
+            WsStatus::Failure => write!(f, "failure"),
+            WsStatus::Busy => write!(f, "busy")
+        }
+    }
+}
+
+pub fn send(msg: &str, status: Option<WsStatus>, socket: &Recipient<SocketText>) {
+    let status_msg = match status {
+        Some(status) => status.to_string(),
+        None => "".to_string()
+    };
+    socket.do_send(SocketText(format!("{{\"msg\":\"{}\", \"status\":\"{status_msg}\"}}", msg.replace('\"', "\\\""))))
+}
+
+/// Sends a message along with a JSON serialized result for the client to use.
+pub fn send_data<T: Serialize>(msg: &str, status: Option<WsStatus>, data: &T, socket: &Recipient<SocketText>) {
+    let status_msg = match status {
+        Some(status) => status.to_string(),
+        None => "".to_string()
+    };
+    let data = serde_json::to_value(data).unwrap_or(serde_json::Value::Null);
+    socket.do_send(SocketText(serde_json::json!({"msg": msg, "status": status_msg, "data": data}).to_string()))
+}
+
+/// Forwards a job's progress to the websocket client as busy messages.
+pub struct WsReporter<'a>(pub &'a Recipient<SocketText>);
+impl Reporter for WsReporter<'_> {
+    fn progress(&mut self, msg: &str) {
+        send(msg, Some(WsStatus::Busy), self.0)
+    }
+}
\ No newline at end of file
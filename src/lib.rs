@@ -0,0 +1,8 @@
+//! Parsing, checks and corrections for scanned media, shared by the worker server and any other tools.
+//! Jobs report progress through a [`reporter::Reporter`] instead of a particular transport.
+
+pub mod qc;
+pub mod utils;
+pub mod autocorr;
+pub mod delivery;
+pub mod reporter;
\ No newline at end of file
@@ -1,26 +1,71 @@
-#![allow(dead_code)]
-use actix::{Actor, StreamHandler};
+use std::sync::Arc;
+use actix::{fut, Actor, Addr, Arbiter, AsyncContext, Handler, StreamHandler};
 use actix_cors::Cors;
-use actix_web::{web, App, Error, HttpRequest, HttpResponse, HttpServer};
+use actix_web::{http::header, web, App, Error, HttpRequest, HttpResponse, HttpServer};
 use actix_web_actors::ws::{self};
+use clap::{Parser, Subcommand};
 
 mod handlers;
-mod qc;
-mod utils;
-mod autocorr;
-use utils::send_text::{self, WsStatus};
-use handlers::jobs;
+mod config;
+use handlers::send_text::{self, SocketText, WsStatus};
+use config::{NamingConfig, ServerArgs, ServerConfig};
+use handlers::{auth::{self, Client}, final_check_cli::{self, FinalCheckArgs}, hot_folders::{HotFolderWatcher, RegisterClient, UnregisterClient, WatchEvent}, job_history::{self, JobHistory}, jobs};
 
-struct WorkerWs;
+/// Hosts the worker websocket unless a command is given.
+#[derive(Parser, Debug)]
+#[command(version)]
+struct Cli {
+    #[command(flatten)]
+    server: ServerArgs,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Runs a final check on a directory and exits non-zero if it fails.
+    FinalCheck(FinalCheckArgs),
+}
+
+struct WorkerWs {
+    watcher: Option<Addr<HotFolderWatcher>>,
+    config: web::Data<ServerConfig>,
+    history: web::Data<JobHistory>,
+    client: Client,
+}
 impl Actor for WorkerWs {
     type Context = ws::WebsocketContext<Self>;
+
+    fn stopped(&mut self, ctx: &mut Self::Context) {
+        if let Some(watcher) = &self.watcher {
+            watcher.do_send(UnregisterClient(ctx.address().recipient()));
+        }
+    }
+}
+impl Handler<WatchEvent> for WorkerWs {
+    type Result = ();
+    fn handle(&mut self, msg: WatchEvent, ctx: &mut Self::Context) {
+        let status = if msg.result.passed { WsStatus::Success } else { WsStatus::Failure };
+        send_text::send_data(&msg.msg, Some(status), &msg.result, &ctx.address().recipient());
+    }
+}
+impl Handler<SocketText> for WorkerWs {
+    type Result = ();
+    fn handle(&mut self, msg: SocketText, ctx: &mut Self::Context) {
+        ctx.text(msg.0);
+    }
 }
 impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WorkerWs {
     fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
         match msg {
             Ok(ws::Message::Text(text)) => {
                 print!("Received a message:\n{}\n", text);
-                let _ = jobs::service_router(text.to_string(), ctx);
+                // Jobs run on the blocking pool, so this socket and the others on its thread keep getting their messages meanwhile
+                let (config, client, history, socket) = (self.config.clone(), self.client.clone(), self.history.clone(), ctx.address().recipient());
+                let job = web::block(move || jobs::service_router(text.to_string(), &config, &client, &history, &socket));
+                ctx.spawn(fut::wrap_future(async move {
+                    let _ = job.await;
+                }));
             }
             Ok(ws::Message::Close(close_reason)) => {
                 println!("Received a close message!");
@@ -35,7 +80,10 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WorkerWs {
 
     fn started(&mut self, ctx: &mut Self::Context) {
         println!("Opened a socket!");
-        send_text::send("Connected to a worker!", Some(WsStatus::Success), ctx);
+        if let Some(watcher) = &self.watcher {
+            watcher.do_send(RegisterClient(ctx.address().recipient()));
+        }
+        send_text::send("Connected to a worker!", Some(WsStatus::Success), &ctx.address().recipient());
     }
 
     fn finished(&mut self, _ctx: &mut Self::Context) {
@@ -43,29 +91,100 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WorkerWs {
     }
 }
 
-async fn index(req: HttpRequest, stream: web::Payload) -> Result<HttpResponse, Error> {
-    ws::start(WorkerWs {}, &req, stream)
+async fn index(req: HttpRequest, stream: web::Payload, watcher: web::Data<Option<Addr<HotFolderWatcher>>>, config: web::Data<ServerConfig>, history: web::Data<JobHistory>) -> Result<HttpResponse, Error> {
+    // Browsers don't apply CORS to websockets, so the origin is checked here
+    let origin = req.headers().get(header::ORIGIN).and_then(|origin| origin.to_str().ok());
+    if let Some(origin) = origin.filter(|origin| !config.allowed_origins.is_empty() && !config.allowed_origins.iter().any(|allowed| allowed == origin)) {
+        println!("Refused a socket from {origin}");
+        return Ok(HttpResponse::Forbidden().finish());
+    }
+    let client = match auth::authenticate(&req, &config.tokens) {
+        Ok(client) => client,
+        Err(e) => {
+            println!("Refused a socket: {e}");
+            return Ok(HttpResponse::Unauthorized().finish());
+        }
+    };
+    println!("Authenticated {} as {}", client.name, client.role);
+    ws::start(WorkerWs { watcher: watcher.get_ref().clone(), config, history, client }, &req, stream)
 }
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    let ip = "127.0.0.1";
-    let port = 7001;
+    let cli = Cli::parse();
+    if let Some(Command::FinalCheck(args)) = cli.command {
+        let naming = match NamingConfig::load(&cli.server) {
+            Ok(naming) => naming,
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(2);
+            }
+        };
+        std::process::exit(final_check_cli::run(args, &naming));
+    }
+    let config = match ServerConfig::load(&cli.server) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+    };
+    let tls = match config.load_tls() {
+        Ok(tls) => tls,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+    };
+    let history = match JobHistory::open(&config.history_path) {
+        Ok(history) => Arc::new(history),
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+    };
+
+    // Final checks block, so hot folders are watched on their own thread
+    let watcher_arbiter = Arbiter::new();
+    if config.tokens.is_empty() {
+        println!("No tokens are configured, so every client can run every job");
+    }
+    if config.allowed_roots.is_empty() {
+        println!("No allowed_roots are configured, so jobs may use any directory");
+    }
+
+    let (naming, watcher_history) = (config.naming.clone(), history.clone());
+    let watcher = config.watch.clone()
+        .map(|watch_config| HotFolderWatcher::start_in_arbiter(&watcher_arbiter.handle(), move |_| HotFolderWatcher::new(watch_config, naming, watcher_history)));
+    let watcher = web::Data::new(watcher);
+    let history = web::Data::from(history);
+
+    let (ip, port, http_workers) = (config.bind_address.clone(), config.port, config.http_workers);
+    let config = web::Data::new(config);
+
     let server = HttpServer::new(move || {
-        let cors = Cors::permissive();
-        App::new().service(
-            web::scope("/ws")
-                .route("/", web::get().to(index))
-        )
-        .wrap(cors)
-    });
-    match server.bind((ip, port)) {
+        let cors = match config.allowed_origins.is_empty() {
+            true => Cors::permissive(),
+            false => config.allowed_origins.iter().fold(Cors::default().allow_any_method().allow_any_header(), |cors, origin| cors.allowed_origin(origin)),
+        };
+        App::new().app_data(watcher.clone()).app_data(config.clone()).app_data(history.clone())
+            .route(&config.ws_path, web::get().to(index))
+            .route("/history", web::get().to(job_history::list_http))
+            .route("/history/{id}", web::get().to(job_history::get_http))
+            .wrap(cors)
+    }).workers(http_workers);
+    let scheme = if tls.is_some() { "wss" } else { "ws" };
+    let bound = match tls {
+        Some(tls) => server.bind_rustls_0_23((ip.as_str(), port), tls),
+        None => server.bind((ip.as_str(), port)),
+    };
+    match bound {
         Ok(v) => {
-            println!("Hosting at {}:{}", ip, port);
+            println!("Hosting at {}://{}:{}", scheme, ip, port);
             v.run().await
         }
         Err(e) => { 
-            println!("{}", e.to_string());
+            println!("{}", e);
             Err(e)   
         }
     }
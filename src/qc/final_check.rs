@@ -1,9 +1,8 @@
-use actix::Actor;
 use media_folder::MediaFolder;
 use serde_json::Value;
 use serde::Deserialize;
 use glob::{glob, Paths};
-use crate::WorkerWs;
+use crate::reporter::Reporter;
 
 pub mod media_file;
 pub mod media_folder;
@@ -11,6 +10,9 @@ pub mod error;
 pub mod media_groups;
 pub mod photo_group_options;
 pub mod final_check_request;
+pub mod duplicates;
+pub mod frame_check;
+pub mod order_sheet;
 
 use error::FCError;
 use media_file::MediaFile;
@@ -25,18 +27,21 @@ struct Data {
 }
 
 
-pub fn final_check(dir: String, request_json: Value, _ctx: &mut<WorkerWs as Actor>::Context) -> std::result::Result<(), FCError> {
+pub fn final_check(dir: String, request_json: Value, _reporter: &mut dyn Reporter) -> std::result::Result<(), FCError> {
     let final_check_req = parse_final_check_request(request_json)?;
 
     let pattern = build_directory_pattern(&dir, &final_check_req)?;
-    let files = glob(&*pattern).map_err(|e| FCError::InvalidDirectory(e))?;
+    let files = glob(&pattern).map_err(FCError::InvalidDirectory)?;
     let media_files = parse_media_files(files)?;
-    if media_files.len() == 0 {
+    if media_files.is_empty() {
         return Err(FCError::NoFilesInDirectory(pattern));
     }
-    let counted_media_groups: MediaGroupValues = MediaGroupValues::from_media_files(&media_files).map_err(|e| FCError::MediaGroupingError(e))?;
-    let media_folder = MediaFolder { files: media_files, group_options: counted_media_groups };
-    media_folder.group_options.counts_equal(final_check_req.media_group_values).map_err(|e| FCError::IncorrectMediaCount(e))?;
+    let counted_media_groups: MediaGroupValues = MediaGroupValues::from_media_files(&media_files).map_err(FCError::MediaGroupingError)?;
+    let mut media_folder = MediaFolder { files: media_files, group_options: counted_media_groups };
+    media_folder.group_options.counts_equal(final_check_req.media_group_values).map_err(FCError::IncorrectMediaCount)?;
+    if let Some(scope) = final_check_req.duplicate_scope {
+        duplicates::check_duplicates(&dir, &mut media_folder.files, scope)?;
+    }
 
     final_check_req.verify_media_folder(media_folder)?;
 
@@ -44,22 +49,31 @@ pub fn final_check(dir: String, request_json: Value, _ctx: &mut<WorkerWs as Acto
 }
 
 
-fn build_directory_pattern(dir: &String, final_check_request: &FinalCheckRequest) -> Result<String, FCError> {
-    let mut ret = format!("{dir}\\");
+fn build_directory_pattern(dir: &str, final_check_request: &FinalCheckRequest) -> Result<String, FCError> {
+    Ok(format!("{}\\*", checked_folder(dir, final_check_request)))
+}
+
+/// The folder a final check looks at, which is the group's subfolder if the request names a group number.
+pub fn checked_folder(dir: &str, final_check_request: &FinalCheckRequest) -> String {
+    let mut ret = dir.to_string();
 
-    if let Some(num) = final_check_request.group_num.and_then(|num| Some(num.to_string())) {
+    if let Some(num) = final_check_request.group_num.map(|num| num.to_string()) {
         let precision_difference: usize = usize::try_from(final_check_request.group_num_precision).unwrap() - num.len();
         let padding: &str = if precision_difference > 0 { &str::repeat("0", precision_difference) } else {""};
-        ret.push_str(&*format!("{padding}{num}\\"));
+        ret.push_str(&format!("\\{padding}{num}"));
     }
-    ret.push('*');
 
-    Ok(ret)
+    ret
 }
 
 fn parse_final_check_request(request_json: Value) -> std::result::Result<FinalCheckRequest, FCError> {
-    let data = serde_json::from_value::<Data>(request_json).map_err(|e| FCError::DeserializeError(e))?;
-    let data = data.data;
+    let data = serde_json::from_value::<Data>(request_json).map_err(FCError::DeserializeError)?;
+    let mut data = data.data;
+
+    if let Some(order_sheet) = &data.order_sheet {
+        data.media_group_values = order_sheet::load_media_group_values(std::path::Path::new(order_sheet), data.group_num, data.group_char, data.media_group_values)
+            .map_err(FCError::OrderSheetError)?;
+    }
 
     if data.media_group_values.slides.is_none() && data.media_group_values.negatives.is_none() && data.media_group_values.prints.is_none() {
         return Err(FCError::InvalidRequest("no properties were defined in request for expecting_media".to_string()));
@@ -72,6 +86,11 @@ fn parse_final_check_request(request_json: Value) -> std::result::Result<FinalCh
     if data.group_num_precision > 6 {
         return Err(FCError::GroupNumberPrecisionTooHigh(data.group_num_precision))
     }
+    for group in [&data.media_group_values.slides, &data.media_group_values.prints, &data.media_group_values.negatives].into_iter().flatten() {
+        if let Some(frame_check) = &group.frame_check {
+            frame_check.validate().map_err(FCError::InvalidRequest)?;
+        }
+    }
 
     Ok(data)
 }
@@ -81,7 +100,7 @@ fn parse_media_files(paths : Paths) -> Result<Vec<MediaFile>, FCError> {
     let mut ret = Vec::new();
     
     for entry in paths {
-        let path = entry.map_err(|e| FCError::InvalidFile(e))?;
+        let path = entry.map_err(FCError::InvalidFile)?;
         let media_file = MediaFile::from_path(&path).map_err(|e| FCError::MediaFileParseError(path, e))?;
         if let Some(media_file) = media_file {
             ret.push(media_file);
@@ -89,4 +108,18 @@ fn parse_media_files(paths : Paths) -> Result<Vec<MediaFile>, FCError> {
     }
 
     Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::{error::FCError, parse_final_check_request};
+
+    #[test]
+    fn rejects_frame_check_fractions_outside_0_to_1() {
+        let request = json!({ "data": { "first_name": "John", "last_name": "Smith", "media_group_values": {
+            "slides": { "scanner": 10, "frame_check": { "max_black_fraction": 1.5 } }
+        } } });
+        assert!(matches!(parse_final_check_request(request), Err(FCError::InvalidRequest(_))));
+    }
 }
\ No newline at end of file
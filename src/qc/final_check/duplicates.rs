@@ -0,0 +1,65 @@
+use std::{collections::{BTreeMap, HashSet}, path::Path};
+use glob::glob;
+use serde::{Deserialize, Serialize};
+use crate::utils::{hashing, paths};
+use super::{error::FCError, media_file::MediaFile};
+
+/// How far to look for files with the same contents as the files being checked.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateScope {
+    /// Only compare files within the checked folder against each other.
+    Folder,
+    /// Also compare the checked folder against every other file in the order directory.
+    Order,
+}
+
+/// Files sharing the same contents, at least one of which is in the checked folder.
+#[derive(Serialize, Debug, Clone)]
+pub struct DuplicateGroup {
+    pub content_hash: String,
+    pub files: Vec<String>,
+}
+
+
+/// Hashes the checked files, and every other order file for [`DuplicateScope::Order`], then errors with every group of identical files.
+/// Files are only hashed here since reading whole masters is slow, so checks without a scope don't pay for it.
+/// Each checked file's hash is kept in its `content_hash`.
+pub fn check_duplicates(order_dir: &str, media_files: &mut [MediaFile], scope: DuplicateScope) -> Result<(), FCError> {
+    let mut files_by_hash: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for media_file in media_files.iter_mut() {
+        let hash = hashing::sha256_file(&media_file.path).map_err(|e| FCError::CouldNotHash(media_file.path.clone(), e))?;
+        files_by_hash.entry(hash.clone()).or_default().push(media_file.raw_file_name.clone());
+        media_file.content_hash = Some(hash);
+    }
+
+    if scope == DuplicateScope::Order {
+        let checked_paths: HashSet<&Path> = media_files.iter().map(|media_file| media_file.path.as_path()).collect();
+        let canonical_order_dir = paths::canonical_dir(Path::new(order_dir));
+        let order_files = glob(&format!("{order_dir}\\**\\*")).map_err(FCError::InvalidDirectory)?;
+        for entry in order_files {
+            let path = entry.map_err(FCError::InvalidFile)?;
+            if !path.is_file() || checked_paths.contains(path.as_path()) || is_thumbs_db(&path) || !paths::resolves_inside(&path, &canonical_order_dir) {
+                continue;
+            }
+            let hash = hashing::sha256_file(&path).map_err(|e| FCError::CouldNotHash(path.clone(), e))?;
+            // Only files matching a checked file matter, other order folders are checked on their own
+            if let Some(files) = files_by_hash.get_mut(&hash) {
+                files.push(path.strip_prefix(order_dir).unwrap_or(&path).to_string_lossy().into_owned());
+            }
+        }
+    }
+
+    let duplicate_groups: Vec<DuplicateGroup> = files_by_hash.into_iter()
+        .filter(|(_, files)| files.len() > 1)
+        .map(|(content_hash, files)| DuplicateGroup { content_hash, files })
+        .collect();
+    match duplicate_groups.is_empty() {
+        true => Ok(()),
+        false => Err(FCError::DuplicateContent(duplicate_groups)),
+    }
+}
+
+fn is_thumbs_db(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| name.to_string_lossy().to_lowercase() == "thumbs.db")
+}
\ No newline at end of file
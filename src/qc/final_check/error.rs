@@ -2,7 +2,7 @@ use glob::{GlobError, PatternError};
 
 use crate::utils::types::{file_extension_type::FileExtensionType, media_types::MediaType};
 
-use super::{media_file::{error::MediaFileParseError, MediaFile}, media_groups::error::MediaGroupsError};
+use super::{duplicates::DuplicateGroup, frame_check::FrameDefect, order_sheet::error::OrderSheetError, media_file::{error::MediaFileParseError, MediaFile}, media_groups::error::MediaGroupsError};
 
 #[derive(Debug)]
 pub enum FCError {
@@ -16,20 +16,26 @@ pub enum FCError {
     MediaGroupingError(MediaGroupsError),
     MediaFileParseError(std::path::PathBuf, MediaFileParseError),
     IncorrectMediaCount(MediaGroupsError),
-    IncompatibleFileExtension(MediaType, FileExtensionType, MediaFile),
+    IncompatibleFileExtension(MediaType, FileExtensionType, Box<MediaFile>),
     OutOfPlaceMediaType(MediaType),
-    IncorrectLastName(String, String, MediaFile),
-    IncorrectFirstInitial(char, char, MediaFile),
-    IncorrectDpi(u64, u64, MediaFile),
-    NotCorrected(MediaFile),
-    MissingGroupNumber(u64, MediaFile),
-    IncorrectGroupNumber(u64, u64, MediaFile),
-    MissingGroupChar(char, MediaFile),
-    IncorrectGroupChar(char, char, MediaFile),
-    IncorrectGroupNumberPrecision(u64, u64, MediaFile),
+    IncorrectLastName(String, String, Box<MediaFile>),
+    IncorrectFirstInitial(char, char, Box<MediaFile>),
+    IncorrectDpi(u64, u64, Box<MediaFile>),
+    NotCorrected(Box<MediaFile>),
+    MissingGroupNumber(u64, Box<MediaFile>),
+    IncorrectGroupNumber(u64, u64, Box<MediaFile>),
+    MissingGroupChar(char, Box<MediaFile>),
+    IncorrectGroupChar(char, char, Box<MediaFile>),
+    IncorrectGroupNumberPrecision(u64, u64, Box<MediaFile>),
     RepeatedIndexNumber(u32, String, String),
-    IncorrectIndexNumberPrecision(u64, u64, MediaFile),
+    IncorrectIndexNumberPrecision(u64, u64, Box<MediaFile>),
     FolderSkippedIndexNumber(u32),
+    CouldNotHash(std::path::PathBuf, std::io::Error),
+    DuplicateContent(Vec<DuplicateGroup>),
+    CouldNotDecodeImage(Box<MediaFile>, image::ImageError),
+    DefectiveFrame(FrameDefect, Box<MediaFile>),
+    ReliesOnOrientationFlag(u16, Box<MediaFile>),
+    OrderSheetError(OrderSheetError),
 }
 impl std::error::Error for FCError {}
 impl std::fmt::Display for FCError {
@@ -47,8 +53,8 @@ impl std::fmt::Display for FCError {
             FCError::MediaFileParseError(path, e) => write!(f, "error parsing {}: {e}", path.file_name().unwrap_or(std::ffi::OsStr::new("invalid file name")).to_string_lossy()),
             FCError::IncorrectMediaCount(e) => write!(f, "incorrect media count: {e}"),
             FCError::IncompatibleFileExtension(media_type, file_extension_type, media_file) => 
-                write!(f, "file {} has a media type of {} but an incompatible file extension of {}", media_type.to_string(), file_extension_type.to_string(), media_file.raw_file_name),
-            FCError::OutOfPlaceMediaType(media_type) => write!(f, "found a file with {}, but wasn't expecting any", media_type.to_string()),
+                write!(f, "file {} has a media type of {} but an incompatible file extension of {}", media_type, file_extension_type, media_file.raw_file_name),
+            FCError::OutOfPlaceMediaType(media_type) => write!(f, "found a file with {}, but wasn't expecting any", media_type),
             FCError::IncorrectLastName(expected, got, media_file) => write!(f, "file {} had last name {got} when it should be {expected}", media_file.raw_file_name),
             FCError::IncorrectFirstInitial(expected, got, media_file) => write!(f, "file {} had a first initial {got} when it should have been {expected}", media_file.raw_file_name),
             FCError::IncorrectDpi(expected, got, media_file) => write!(f, "file {} had dpi {got} when it should have been {expected}", media_file.raw_file_name),
@@ -60,7 +66,16 @@ impl std::fmt::Display for FCError {
             FCError::IncorrectGroupNumberPrecision(expected, got, media_file) => write!(f, "file {} had a group number precision of {got} digits when it should have been {expected} digits", media_file.raw_file_name),
             FCError::RepeatedIndexNumber(index_number, file_name_1, file_name_2) => write!(f, "files {file_name_1} and {file_name_2} have the same index number {index_number}"),
             FCError::IncorrectIndexNumberPrecision(expected, got, media_file) => write!(f, "file {} had an index number precision of {got} digits when it should have been {expected}", media_file.raw_file_name),
-            FCError::FolderSkippedIndexNumber(index_number) => write!(f, "folder skipped index number {index_number}")
+            FCError::FolderSkippedIndexNumber(index_number) => write!(f, "folder skipped index number {index_number}"),
+            FCError::CouldNotHash(path, e) => write!(f, "could not hash {}: {e}", path.to_string_lossy()),
+            FCError::DuplicateContent(duplicate_groups) => {
+                let groups: Vec<String> = duplicate_groups.iter().map(|group| format!("{} (sha256 {})", group.files.join(", "), group.content_hash)).collect();
+                write!(f, "found {} groups of files with identical contents: {}", groups.len(), groups.join("; "))
+            }
+            FCError::CouldNotDecodeImage(media_file, e) => write!(f, "could not decode file {}: {e}", media_file.raw_file_name),
+            FCError::DefectiveFrame(defect, media_file) => write!(f, "file {} was flagged as a {defect} frame", media_file.raw_file_name),
+            FCError::ReliesOnOrientationFlag(orientation, media_file) => write!(f, "file {} relies on EXIF orientation {orientation} instead of being stored upright", media_file.raw_file_name),
+            FCError::OrderSheetError(e) => write!(f, "{e}"),
         }
     }
 }
\ No newline at end of file
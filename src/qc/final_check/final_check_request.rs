@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use serde::Deserialize;
-use crate::{qc::final_check::{error::FCError, media_file::MediaFile, photo_group_options::PhotoGroupOptions}, utils::types::{file_extension_type::FileExtensionType, media_types::{photo_media_data::PhotoMediaData, MediaType}}};
-use super::{media_folder::MediaFolder, media_groups::MediaGroupValues};
+use crate::{qc::final_check::{error::FCError, frame_check, media_file::MediaFile, photo_group_options::PhotoGroupOptions}, utils::types::{file_extension_type::FileExtensionType, media_types::{photo_media_data::PhotoMediaData, MediaType}}};
+use super::{duplicates::DuplicateScope, media_folder::MediaFolder, media_groups::MediaGroupValues};
 
 #[derive(Deserialize, Debug)]
 pub struct FinalCheckRequest {
@@ -18,7 +18,14 @@ pub struct FinalCheckRequest {
     pub group_num_precision: u64,   // Guaranteed 6 or less
     #[serde(default = "default_3")]
     pub index_num_precision: u64,
+    /// If set, files with identical contents are reported within this scope.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duplicate_scope: Option<DuplicateScope>,
+    /// Path to an intake order sheet (.csv or .xlsx). If set, expected counts and DPIs are read from it instead of `media_group_values`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_sheet: Option<String>,
 
+    #[serde(default)]
     pub media_group_values: MediaGroupValues
 }
 fn default_2() -> u64 { 2 }
@@ -32,17 +39,23 @@ impl FinalCheckRequest {
             if let Some(photo_group_options) = photo_group_options {
                 if let Some(expected_dpi) = photo_group_options.dpi {
                     if photo_data.dpi != expected_dpi {
-                        return Err(FCError::IncorrectDpi(expected_dpi, photo_data.dpi, media_file.clone()))
+                        return Err(FCError::IncorrectDpi(expected_dpi, photo_data.dpi, Box::new(media_file.clone())))
+                    }
+                }
+                if photo_group_options.require_upright && photo_data.orientation != 1 {
+                    return Err(FCError::ReliesOnOrientationFlag(photo_data.orientation, Box::new(media_file.clone())))
+                }
+                if let Some(frame_check_options) = &photo_group_options.frame_check {
+                    let defect = frame_check::find_frame_defect(&media_file.path, frame_check_options).map_err(|e| FCError::CouldNotDecodeImage(Box::new(media_file.clone()), e))?;
+                    if let Some(defect) = defect {
+                        return Err(FCError::DefectiveFrame(defect, Box::new(media_file.clone())))
                     }
                 }
                 if photo_group_options.is_corrected && !photo_data.is_corrected {
-                    println!("Should be corrected!");
-                    return Err(FCError::NotCorrected(media_file.clone()))
-                } else {
-                    println!("Shouldn't be corrected!");
+                    return Err(FCError::NotCorrected(Box::new(media_file.clone())))
                 }
             } else {
-                return Err(FCError::OutOfPlaceMediaType(media_file.media_type.clone()))
+                return Err(FCError::OutOfPlaceMediaType(media_file.media_type))
             }
 
             Ok(())
@@ -51,48 +64,48 @@ impl FinalCheckRequest {
         let mut seen_index_numbers: HashMap<u32, String> = HashMap::new();
         for media_file in media_folder.files {
             match &media_file.media_type {
-                MediaType::Prints(print_data) => check_against_photo_group_options(&media_file, &self.media_group_values.prints, &print_data)?,
-                MediaType::Slides(slides_data) => check_against_photo_group_options(&media_file, &self.media_group_values.slides, &slides_data)?,
-                MediaType::Negatives(negatives_data) => check_against_photo_group_options(&media_file, &self.media_group_values.negatives, &negatives_data)?,
+                MediaType::Prints(print_data) => check_against_photo_group_options(&media_file, &self.media_group_values.prints, print_data)?,
+                MediaType::Slides(slides_data) => check_against_photo_group_options(&media_file, &self.media_group_values.slides, slides_data)?,
+                MediaType::Negatives(negatives_data) => check_against_photo_group_options(&media_file, &self.media_group_values.negatives, negatives_data)?,
             }
             match &media_file.media_type {
                 MediaType::Prints(_) | MediaType::Slides(_) | MediaType::Negatives(_) => {
                     match &media_file.file_extension {
                         FileExtensionType::Tiff | FileExtensionType::Jpeg => {}
-                        _ => { return Err(FCError::IncompatibleFileExtension(media_file.media_type, media_file.file_extension, media_file)); }
+                        _ => { return Err(FCError::IncompatibleFileExtension(media_file.media_type, media_file.file_extension, Box::new(media_file))); }
                     }
                 }
             }
 
             if media_file.last_name != self.last_name {
-                return Err(FCError::IncorrectLastName(self.last_name.clone(), media_file.last_name.clone(), media_file))
+                return Err(FCError::IncorrectLastName(self.last_name.clone(), media_file.last_name.clone(), Box::new(media_file)))
             }
             let expected_first_initial = self.first_name.chars().next().unwrap();
             if media_file.first_name_initial != expected_first_initial {
-                return Err(FCError::IncorrectFirstInitial(expected_first_initial, media_file.first_name_initial, media_file))
+                return Err(FCError::IncorrectFirstInitial(expected_first_initial, media_file.first_name_initial, Box::new(media_file)))
             }
             if let Some(expected) = self.group_num {
                 if let Some(got) = media_file.group_number {
                     let got = u64::from(got);
                     if got != expected {
-                        return Err(FCError::IncorrectGroupNumber(expected, got, media_file))
+                        return Err(FCError::IncorrectGroupNumber(expected, got, Box::new(media_file)))
                     }
                     // Group number precisions are guaranteed to be defined at this point
                     let media_file_group_precision = u64::try_from(media_file.group_number_precision.unwrap()).unwrap();
                     if self.group_num_precision != media_file_group_precision {
-                        return Err(FCError::IncorrectGroupNumberPrecision(self.group_num_precision, media_file_group_precision, media_file))
+                        return Err(FCError::IncorrectGroupNumberPrecision(self.group_num_precision, media_file_group_precision, Box::new(media_file)))
                     }
                 } else {
-                    return Err(FCError::MissingGroupNumber(expected, media_file))
+                    return Err(FCError::MissingGroupNumber(expected, Box::new(media_file)))
                 }
             }
             if let Some(expected) = self.group_char {
                 if let Some(got) = media_file.group_character {
                     if got != expected {
-                        return Err(FCError::IncorrectGroupChar(expected, got, media_file))
+                        return Err(FCError::IncorrectGroupChar(expected, got, Box::new(media_file)))
                     }
                 } else {
-                    return Err(FCError::MissingGroupChar(expected, media_file))
+                    return Err(FCError::MissingGroupChar(expected, Box::new(media_file)))
                 }
             }
             if let Some(repeated_file_name) = seen_index_numbers.insert(media_file.index_number, media_file.raw_file_name.clone()) {
@@ -100,18 +113,16 @@ impl FinalCheckRequest {
             }
             let media_file_index_num_precision = u64::try_from(media_file.index_number_precision).unwrap();
             if media_file_index_num_precision != self.index_num_precision {
-                return Err(FCError::IncorrectIndexNumberPrecision(self.index_num_precision, media_file_index_num_precision, media_file))
+                return Err(FCError::IncorrectIndexNumberPrecision(self.index_num_precision, media_file_index_num_precision, Box::new(media_file)))
             }
         }
 
         let mut seen_index_numbers: Vec<u32> = seen_index_numbers.keys().cloned().collect();
         seen_index_numbers.sort();
-        let mut expecting_value = 1;
-        for index_number in seen_index_numbers {
+        for (expecting_value, index_number) in (1..).zip(seen_index_numbers) {
             if index_number != expecting_value {
                 return Err(FCError::FolderSkippedIndexNumber(expecting_value));
             }
-            expecting_value += 1;
         }
 
 
@@ -0,0 +1,118 @@
+use std::path::Path;
+use serde::Deserialize;
+
+/// Thresholds for flagging frames that are most likely empty holder slots or failed exposures.
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct FrameCheckOptions {
+    /// Frames with a brightness standard deviation below this are flagged as blank.
+    #[serde(default = "default_min_std_dev")]
+    pub min_std_dev: f64,
+    /// Frames with more than this fraction of near-black pixels are flagged as black.
+    #[serde(default = "default_max_clipped_fraction")]
+    pub max_black_fraction: f64,
+    /// Frames with more than this fraction of near-white pixels are flagged as overexposed.
+    #[serde(default = "default_max_clipped_fraction")]
+    pub max_white_fraction: f64,
+}
+fn default_min_std_dev() -> f64 { 3.0 }
+fn default_max_clipped_fraction() -> f64 { 0.95 }
+impl FrameCheckOptions {
+    /// Rejects thresholds that would silently pass or fail every frame.
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.min_std_dev.is_finite() || self.min_std_dev < 0.0 {
+            return Err(format!("frame check min_std_dev {} is not a finite, non-negative number", self.min_std_dev))
+        }
+        for (name, fraction) in [("max_black_fraction", self.max_black_fraction), ("max_white_fraction", self.max_white_fraction)] {
+            if !(0.0..=1.0).contains(&fraction) {
+                return Err(format!("frame check {name} {fraction} is not between 0 and 1"))
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FrameDefect {
+    Blank,
+    Black,
+    Overexposed,
+}
+impl std::fmt::Display for FrameDefect {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FrameDefect::Blank => write!(f, "blank"),
+            FrameDefect::Black => write!(f, "black"),
+            FrameDefect::Overexposed => write!(f, "clipped white"),
+        }
+    }
+}
+
+const BLACK_LEVEL: u8 = 8;
+const WHITE_LEVEL: u8 = 247;
+
+/// Decodes an image and checks its brightness histogram against the options.
+pub fn find_frame_defect(path: &Path, options: &FrameCheckOptions) -> Result<Option<FrameDefect>, image::ImageError> {
+    let luma = image::open(path)?.into_luma8();
+    let mut histogram = [0u64; 256];
+    for pixel in luma.pixels() {
+        histogram[usize::from(pixel[0])] += 1;
+    }
+    let pixel_count = histogram.iter().sum::<u64>().max(1) as f64;
+
+    let black_fraction = histogram[..=usize::from(BLACK_LEVEL)].iter().sum::<u64>() as f64 / pixel_count;
+    if black_fraction > options.max_black_fraction {
+        return Ok(Some(FrameDefect::Black))
+    }
+    let white_fraction = histogram[usize::from(WHITE_LEVEL)..].iter().sum::<u64>() as f64 / pixel_count;
+    if white_fraction > options.max_white_fraction {
+        return Ok(Some(FrameDefect::Overexposed))
+    }
+
+    let mean = histogram.iter().enumerate().map(|(level, count)| level as f64 * *count as f64).sum::<f64>() / pixel_count;
+    let variance = histogram.iter().enumerate().map(|(level, count)| (level as f64 - mean).powi(2) * *count as f64).sum::<f64>() / pixel_count;
+    if variance.sqrt() < options.min_std_dev {
+        return Ok(Some(FrameDefect::Blank))
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GrayImage, Luma};
+    use serde_json::json;
+    use crate::utils::test_files;
+    use super::{find_frame_defect, FrameCheckOptions, FrameDefect};
+
+    fn options(json: serde_json::Value) -> FrameCheckOptions {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn validate_rejects_thresholds_that_pass_or_fail_everything() {
+        assert!(options(json!({})).validate().is_ok());
+        for invalid in [json!({ "min_std_dev": -1.0 }), json!({ "max_black_fraction": 1.5 }), json!({ "max_white_fraction": -0.2 })] {
+            assert!(options(invalid.clone()).validate().is_err(), "{invalid} was accepted");
+        }
+        let not_finite = FrameCheckOptions { min_std_dev: f64::NAN, ..options(json!({})) };
+        assert!(not_finite.validate().is_err());
+        let not_finite = FrameCheckOptions { max_black_fraction: f64::INFINITY, ..options(json!({})) };
+        assert!(not_finite.validate().is_err());
+    }
+
+    #[test]
+    fn finds_each_kind_of_defect() {
+        let dir = test_files::temp_dir("frame-check");
+        let frames = [
+            ("black.png", GrayImage::from_pixel(32, 32, Luma([2])), Some(FrameDefect::Black)),
+            ("white.png", GrayImage::from_pixel(32, 32, Luma([252])), Some(FrameDefect::Overexposed)),
+            ("blank.png", GrayImage::from_pixel(32, 32, Luma([128])), Some(FrameDefect::Blank)),
+            ("scene.png", GrayImage::from_fn(32, 32, |x, y| Luma([(x * 4 + y * 3) as u8])), None),
+        ];
+        for (file_name, image, expected) in frames {
+            let path = dir.join(file_name);
+            image.save(&path).unwrap();
+            assert_eq!(find_frame_defect(&path, &options(json!({}))).unwrap(), expected, "{file_name}");
+        }
+    }
+}
\ No newline at end of file
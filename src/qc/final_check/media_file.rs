@@ -20,6 +20,8 @@ pub struct MediaFile {
     pub index_number_precision: usize,
     pub scan_type: ScanType,
     pub file_extension: FileExtensionType,
+    /// SHA-256 of the file's contents, filled in when a final check looks for duplicates.
+    pub content_hash: Option<String>,
 }
 
 #[derive(Debug)]
@@ -34,9 +36,9 @@ enum SectionReadState {
     End
 }
 impl MediaFile {
-    pub fn from_path(path: &std::path::PathBuf) -> Result<Option<MediaFile>, MediaFileParseError> {
+    pub fn from_path(path: &std::path::Path) -> Result<Option<MediaFile>, MediaFileParseError> {
         if !path.is_file() {
-            return Err(MediaFileParseError::NotAFile(path.clone()))
+            return Err(MediaFileParseError::NotAFile(path.to_path_buf()))
         }
         let file_name = &*path.file_name().unwrap_or(OsStr::new("invalid file name")).to_string_lossy().into_owned();
         if file_name.to_lowercase() == "thumbs.db" {
@@ -65,7 +67,7 @@ impl MediaFile {
                         break;
                     }
                     SectionReadState::MediaType => {
-                        media_type = Some(MediaType::from_path(word, path).map_err(|e| MediaFileParseError::MediaTypeError(e))?);
+                        media_type = Some(MediaType::from_path(word, path).map_err(MediaFileParseError::MediaTypeError)?);
                         current_section = SectionReadState::GroupNumber;
                         break;
                     }
@@ -134,10 +136,10 @@ impl MediaFile {
 
         let last_name = last_name.to_string();
         let media_type = media_type.unwrap();
-        let path = path.clone();
+        let path = path.to_path_buf();
         let raw_file_name = file_name.to_string();
         let ret = MediaFile { path, raw_file_name, last_name, first_name_initial, media_type, group_number, group_number_precision,
-            group_character, index_number, index_number_precision, scan_type, file_extension };
+            group_character, index_number, index_number_precision, scan_type, file_extension, content_hash: None };
         Ok(Some(ret))
     }
 }
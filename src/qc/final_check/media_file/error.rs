@@ -9,7 +9,7 @@ pub enum MediaFileParseError {
     ExpectedGroupOrIndexNumber(String),
     NoIndexNumber,
     InvalidExtension(String),
-    ExpectedEnd(String)
+    ExpectedEnd(String),
 }
 impl std::error::Error for MediaFileParseError {}
 impl std::fmt::Display for MediaFileParseError {
@@ -23,7 +23,7 @@ impl std::fmt::Display for MediaFileParseError {
             MediaFileParseError::ExpectedGroupOrIndexNumber(word) => write!(f, "unrecognized text \"{word}\" where a group or index number should have been"),
             MediaFileParseError::NoIndexNumber => write!(f, "no index number could be found"),
             MediaFileParseError::InvalidExtension(word) => write!(f, "invalid extension \"{word}\""),
-            MediaFileParseError::ExpectedEnd(word) => write!(f, "unexpected additional field \"{word}\"")
+            MediaFileParseError::ExpectedEnd(word) => write!(f, "unexpected additional field \"{word}\""),
         }
     }
 }
\ No newline at end of file
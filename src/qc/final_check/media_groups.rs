@@ -8,7 +8,7 @@ use error::MediaGroupsError;
 
 
 #[serde_with::skip_serializing_none]
-#[derive(Deserialize, Debug, Copy, Clone)]
+#[derive(Deserialize, Debug, Default, Copy, Clone)]
 pub struct MediaGroupValues {
     pub slides: Option<PhotoGroupOptions>,
     pub prints: Option<PhotoGroupOptions>,
@@ -17,25 +17,25 @@ pub struct MediaGroupValues {
 impl MediaGroupValues {
     pub fn counts_equal(&self, expected_media: MediaGroupValues) -> Result<(), MediaGroupsError> {
         fn equals_or_err(counted: u64, expected: u64, media_and_scan_type : &str) -> Result<(), MediaGroupsError> {
-            return match counted == expected {
+            match counted == expected {
                 true => Ok(()),
                 false => Err(MediaGroupsError::IncorrectMediaAndScanTypeCount(media_and_scan_type.to_string(), counted, expected))
             }
         }
 
         if let Some(counted_slides) = &self.slides {
-            let expected_slides = expected_media.slides.unwrap();
+            let expected_slides = expected_media.slides.unwrap_or_default();
             equals_or_err(counted_slides.scanner, expected_slides.scanner, "scanner slides")?;
             equals_or_err(counted_slides.hs, expected_slides.hs, "handscan slides")?;
         }
         if let Some(counted_prints) = &self.prints {
-            let expected_prints = expected_media.prints.unwrap();
+            let expected_prints = expected_media.prints.unwrap_or_default();
             equals_or_err(counted_prints.scanner, expected_prints.scanner, "scanner prints")?;
             equals_or_err(counted_prints.hs, expected_prints.hs, "handscan prints")?;
             equals_or_err(counted_prints.oshs, expected_prints.oshs, "oversized prints")?;
         }
         if let Some(counted_negs) = &self.negatives {
-            let expected_negs = expected_media.negatives.unwrap();
+            let expected_negs = expected_media.negatives.unwrap_or_default();
             equals_or_err(counted_negs.scanner, expected_negs.scanner, "scanner negatives")?;
             equals_or_err(counted_negs.hs, expected_negs.hs, "handscan negatives")?;
         }
@@ -44,7 +44,7 @@ impl MediaGroupValues {
     }
 
 
-    pub fn from_media_files(media_files: &Vec<MediaFile>) -> Result<MediaGroupValues, MediaGroupsError> {
+    pub fn from_media_files(media_files: &[MediaFile]) -> Result<MediaGroupValues, MediaGroupsError> {
         let mut slides = PhotoGroupOptions::new();
         let mut prints = PhotoGroupOptions::new();
         let mut negatives = PhotoGroupOptions::new();
@@ -59,7 +59,7 @@ impl MediaGroupValues {
                     match media_file.scan_type {
                         ScanType::Default => slides.scanner += 1,
                         ScanType::HandScan => slides.hs += 1,
-                        _ => return Err(MediaGroupsError::InvalidScanTypeMediaGroupCombo(media_file.scan_type.clone(), media_file.media_type.clone(), media_file.raw_file_name.clone())),
+                        _ => return Err(MediaGroupsError::InvalidScanTypeMediaGroupCombo(media_file.scan_type, media_file.media_type, media_file.raw_file_name.clone())),
                     }
                 }
                 MediaType::Prints(_) => {
@@ -75,7 +75,7 @@ impl MediaGroupValues {
                     match media_file.scan_type {
                         ScanType::Default => negatives.scanner += 1,
                         ScanType::HandScan => negatives.hs += 1,
-                        _ => return Err(MediaGroupsError::InvalidScanTypeMediaGroupCombo(media_file.scan_type.clone(), media_file.media_type.clone(), media_file.raw_file_name.clone())),
+                        _ => return Err(MediaGroupsError::InvalidScanTypeMediaGroupCombo(media_file.scan_type, media_file.media_type, media_file.raw_file_name.clone())),
                     }
                 }
             }
@@ -11,7 +11,7 @@ impl std::fmt::Display for MediaGroupsError {
     -> std::fmt::Result {
         match self {
             MediaGroupsError::IncorrectMediaAndScanTypeCount(scan_and_media_type, counted, expected) => write!(f, "expected {expected} {scan_and_media_type}, counted {counted}"),
-            MediaGroupsError::InvalidScanTypeMediaGroupCombo(scan_type, media_type, file_name) => write!(f, "{file_name} can't be of type {} and be scanned as a/an {}", media_type.to_string(), scan_type.to_string()),
+            MediaGroupsError::InvalidScanTypeMediaGroupCombo(scan_type, media_type, file_name) => write!(f, "{file_name} can't be of type {} and be scanned as a/an {}", media_type, scan_type),
         }
     }
 }
\ No newline at end of file
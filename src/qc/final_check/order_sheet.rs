@@ -0,0 +1,212 @@
+pub mod error;
+use std::path::Path;
+use calamine::{open_workbook_auto, Reader};
+use error::OrderSheetError;
+use super::{media_groups::MediaGroupValues, photo_group_options::PhotoGroupOptions};
+
+const GROUP_COLUMN: &str = "group";
+const MEDIA_TYPE_COLUMN: &str = "media_type";
+const SCANNER_COLUMN: &str = "scanner";
+const HS_COLUMN: &str = "hs";
+const OSHS_COLUMN: &str = "oshs";
+const DPI_COLUMN: &str = "dpi";
+
+/// Indices of the columns we read, found by header name.
+struct Columns {
+    group: Option<usize>,
+    media_type: usize,
+    scanner: Option<usize>,
+    hs: Option<usize>,
+    oshs: Option<usize>,
+    dpi: Option<usize>,
+}
+
+
+/// Builds the expected media of one group from an intake order sheet, as a CSV or the first sheet of a workbook.
+/// Counts and DPIs come from the sheet, while other options for a media type, such as `is_corrected`, are kept from `base`.
+pub fn load_media_group_values(path: &Path, group_num: Option<u64>, group_char: Option<char>, base: MediaGroupValues) -> Result<MediaGroupValues, OrderSheetError> {
+    let rows = read_rows(path)?;
+    let mut rows = rows.into_iter();
+    let header = rows.next().ok_or(OrderSheetError::EmptySheet)?;
+    let columns = find_columns(&header)?;
+
+    let mut values = MediaGroupValues { slides: None, prints: None, negatives: None };
+    // Spreadsheet row numbers, counting the header as row 1
+    for (row_number, row) in (2..).zip(rows) {
+        if row.iter().all(|cell| cell.trim().is_empty()) {
+            continue;
+        }
+        let cell = |index: Option<usize>| index.and_then(|index| row.get(index)).map(|cell| cell.trim()).unwrap_or("");
+        let (row_group_num, row_group_char) = parse_group(cell(columns.group)).ok_or_else(|| OrderSheetError::InvalidCell(row_number, GROUP_COLUMN.to_string(), cell(columns.group).to_string()))?;
+        if row_group_num != group_num || row_group_char != group_char {
+            continue;
+        }
+
+        let media_type = cell(Some(columns.media_type));
+        let (target, base_options) = match media_type.to_lowercase().as_str() {
+            "slides" => (&mut values.slides, base.slides),
+            "prints" => (&mut values.prints, base.prints),
+            "negs" | "negatives" => (&mut values.negatives, base.negatives),
+            _ => return Err(OrderSheetError::UnknownMediaType(row_number, media_type.to_string())),
+        };
+        if target.is_some() {
+            return Err(OrderSheetError::DuplicateRow(row_number, media_type.to_string()));
+        }
+        let count = |column: &str, index: Option<usize>| parse_count(cell(index)).ok_or_else(|| OrderSheetError::InvalidCell(row_number, column.to_string(), cell(index).to_string()));
+        let dpi = match cell(columns.dpi) {
+            "" => None,
+            dpi => Some(parse_count(dpi).ok_or_else(|| OrderSheetError::InvalidCell(row_number, DPI_COLUMN.to_string(), dpi.to_string()))?),
+        };
+
+        let base_options = base_options.unwrap_or_else(PhotoGroupOptions::new);
+        *target = Some(PhotoGroupOptions {
+            scanner: count(SCANNER_COLUMN, columns.scanner)?,
+            hs: count(HS_COLUMN, columns.hs)?,
+            oshs: count(OSHS_COLUMN, columns.oshs)?,
+            dpi: dpi.or(base_options.dpi),
+            ..base_options
+        });
+    }
+
+    if values.slides.is_none() && values.prints.is_none() && values.negatives.is_none() {
+        let group = format!("{}{}", group_num.map(|number| number.to_string()).unwrap_or_default(), group_char.map(String::from).unwrap_or_default());
+        return Err(OrderSheetError::NoRowsForGroup(if group.is_empty() { String::from("ungrouped media") } else { format!("group {group}") }));
+    }
+
+    Ok(values)
+}
+
+fn read_rows(path: &Path) -> Result<Vec<Vec<String>>, OrderSheetError> {
+    let extension = path.extension().unwrap_or_default().to_string_lossy().to_lowercase();
+    match extension.as_str() {
+        "csv" => {
+            let contents = std::fs::read_to_string(path).map_err(|e| OrderSheetError::CouldNotRead(path.to_path_buf(), e))?;
+            // Excel writes a byte order mark at the start of UTF-8 CSVs
+            Ok(parse_csv(contents.trim_start_matches('\u{feff}')))
+        }
+        "xlsx" | "xlsm" | "xls" | "ods" => {
+            let mut workbook = open_workbook_auto(path).map_err(|e| OrderSheetError::WorkbookError(path.to_path_buf(), e.to_string()))?;
+            let range = workbook.worksheet_range_at(0).ok_or(OrderSheetError::EmptySheet)?
+                .map_err(|e| OrderSheetError::WorkbookError(path.to_path_buf(), e.to_string()))?;
+            Ok(range.rows().map(|row| row.iter().map(|cell| cell.to_string()).collect()).collect())
+        }
+        _ => Err(OrderSheetError::UnsupportedFormat(extension)),
+    }
+}
+
+/// Matches header names case-insensitively, treating spaces as underscores so "Media Type" finds `media_type`.
+fn find_columns(header: &[String]) -> Result<Columns, OrderSheetError> {
+    let find = |name: &str| header.iter().position(|cell| cell.trim().to_lowercase().replace(' ', "_") == name);
+    Ok(Columns {
+        group: find(GROUP_COLUMN),
+        media_type: find(MEDIA_TYPE_COLUMN).ok_or_else(|| OrderSheetError::MissingColumn(MEDIA_TYPE_COLUMN.to_string()))?,
+        scanner: find(SCANNER_COLUMN),
+        hs: find(HS_COLUMN),
+        oshs: find(OSHS_COLUMN),
+        dpi: find(DPI_COLUMN),
+    })
+}
+
+/// Reads a group like "01A", "3" or "B" into its number and character. Empty means ungrouped.
+fn parse_group(group: &str) -> Option<(Option<u64>, Option<char>)> {
+    let digits_end = group.find(|c: char| !c.is_ascii_digit()).unwrap_or(group.len());
+    let (digits, rest) = group.split_at(digits_end);
+    let number = match digits {
+        "" => None,
+        digits => Some(digits.parse().ok()?),
+    };
+    let mut rest = rest.chars();
+    let character = rest.next();
+    match rest.next() {
+        Some(_) => None,
+        None => Some((number, character)),
+    }
+}
+
+/// Blank cells count as zero. Workbooks store whole numbers as floats, which display without a fraction.
+fn parse_count(cell: &str) -> Option<u64> {
+    match cell {
+        "" => Some(0),
+        cell => cell.parse().ok(),
+    }
+}
+
+/// Splits CSV text into rows of fields, handling quoted fields with commas, doubled quotes and line breaks.
+fn parse_csv(contents: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut characters = contents.chars().peekable();
+    while let Some(character) = characters.next() {
+        match (character, in_quotes) {
+            ('"', true) if characters.peek() == Some(&'"') => {
+                field.push('"');
+                characters.next();
+            }
+            ('"', _) => in_quotes = !in_quotes,
+            (',', false) => row.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (character, _) => field.push(character),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use crate::{qc::final_check::{media_groups::MediaGroupValues, photo_group_options::PhotoGroupOptions}, utils::test_files};
+    use super::{load_media_group_values, parse_csv, parse_group, error::OrderSheetError};
+
+    fn write_sheet(name: &str, contents: &str) -> PathBuf {
+        let path = test_files::temp_dir(name).join("order.csv");
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn matches_columns_by_name_and_reads_the_group() {
+        let path = write_sheet("order-sheet-columns", "\u{feff}DPI,Scanner,Media Type,Group,HS\r\n600,10,Slides,01A,2\r\n300,5,Prints,01A,\r\n1200,7,Slides,02,1\r\n");
+        let base = MediaGroupValues { slides: Some(PhotoGroupOptions { is_corrected: true, dpi: Some(2000), ..PhotoGroupOptions::new() }), prints: None, negatives: None };
+        let values = load_media_group_values(&path, Some(1), Some('A'), base).unwrap();
+        let slides = values.slides.unwrap();
+        assert_eq!((slides.scanner, slides.hs, slides.oshs, slides.dpi, slides.is_corrected), (10, 2, 0, Some(600), true));
+        let prints = values.prints.unwrap();
+        assert_eq!((prints.scanner, prints.hs, prints.dpi), (5, 0, Some(300)));
+        assert!(values.negatives.is_none());
+    }
+
+    #[test]
+    fn reports_bad_sheets() {
+        let load = |name: &str, contents: &str| load_media_group_values(&write_sheet(name, contents), None, None, MediaGroupValues::default());
+        assert!(matches!(load("order-sheet-empty", ""), Err(OrderSheetError::EmptySheet)));
+        assert!(matches!(load("order-sheet-no-type", "group,scanner\n,10\n"), Err(OrderSheetError::MissingColumn(column)) if column == "media_type"));
+        assert!(matches!(load("order-sheet-count", "media_type,scanner\nSlides,ten\n"), Err(OrderSheetError::InvalidCell(2, column, cell)) if column == "scanner" && cell == "ten"));
+        assert!(matches!(load("order-sheet-type", "media_type,scanner\n\nFilm,3\n"), Err(OrderSheetError::UnknownMediaType(3, _))));
+        assert!(matches!(load("order-sheet-duplicate", "media_type,scanner\nSlides,3\nslides,4\n"), Err(OrderSheetError::DuplicateRow(3, _))));
+        assert!(matches!(load("order-sheet-group", "group,media_type,scanner\n01,Slides,3\n"), Err(OrderSheetError::NoRowsForGroup(group)) if group == "ungrouped media"));
+        assert!(matches!(load_media_group_values(&PathBuf::from("order.txt"), None, None, MediaGroupValues::default()), Err(OrderSheetError::UnsupportedFormat(_))));
+    }
+
+    #[test]
+    fn reads_groups() {
+        assert_eq!(parse_group(""), Some((None, None)));
+        assert_eq!(parse_group("01A"), Some((Some(1), Some('A'))));
+        assert_eq!(parse_group("B"), Some((None, Some('B'))));
+        assert_eq!(parse_group("1AB"), None);
+    }
+
+    #[test]
+    fn reads_quoted_csv_fields() {
+        assert_eq!(parse_csv("a,\"b, \"\"c\"\"\nd\"\r\n,e"), [vec!["a", "b, \"c\"\nd"], vec!["", "e"]]);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,29 @@
+#[derive(Debug)]
+pub enum OrderSheetError {
+    CouldNotRead(std::path::PathBuf, std::io::Error),
+    WorkbookError(std::path::PathBuf, String),
+    UnsupportedFormat(String),
+    EmptySheet,
+    MissingColumn(String),
+    InvalidCell(usize, String, String),
+    UnknownMediaType(usize, String),
+    DuplicateRow(usize, String),
+    NoRowsForGroup(String),
+}
+impl std::error::Error for OrderSheetError {}
+impl std::fmt::Display for OrderSheetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter)
+    -> std::fmt::Result {
+        match self {
+            OrderSheetError::CouldNotRead(path, e) => write!(f, "could not read order sheet {}: {e}", path.to_string_lossy()),
+            OrderSheetError::WorkbookError(path, e) => write!(f, "could not read workbook {}: {e}", path.to_string_lossy()),
+            OrderSheetError::UnsupportedFormat(extension) => write!(f, "order sheets must be .csv or .xlsx, not .{extension}"),
+            OrderSheetError::EmptySheet => write!(f, "order sheet has no header row"),
+            OrderSheetError::MissingColumn(column) => write!(f, "order sheet has no {column} column"),
+            OrderSheetError::InvalidCell(row, column, value) => write!(f, "row {row} of the order sheet has an invalid {column} of \"{value}\""),
+            OrderSheetError::UnknownMediaType(row, media_type) => write!(f, "row {row} of the order sheet has an unknown media type \"{media_type}\""),
+            OrderSheetError::DuplicateRow(row, media_type) => write!(f, "row {row} of the order sheet repeats {media_type} for the same group"),
+            OrderSheetError::NoRowsForGroup(group) => write!(f, "order sheet has no rows for {group}"),
+        }
+    }
+}
\ No newline at end of file
@@ -1,4 +1,5 @@
 use serde::Deserialize;
+use super::frame_check::FrameCheckOptions;
 
 #[derive(Deserialize, Debug, Copy, Clone)]
 pub struct PhotoGroupOptions {
@@ -13,13 +14,24 @@ pub struct PhotoGroupOptions {
     #[serde(default = "default_false")]
     pub is_corrected: bool,
     #[serde(default = "default_3")]
-    pub index_precision: u64
+    pub index_precision: u64,
+    /// If true, files must have upright pixels instead of relying on an EXIF orientation flag.
+    #[serde(default = "default_false")]
+    pub require_upright: bool,
+    /// If set, every image of this media type is checked for blank, black or overexposed frames.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_check: Option<FrameCheckOptions>,
 }
 fn default_0() -> u64 { 0 }
 fn default_false() -> bool { false }
 fn default_3() -> u64 { 3 }
 impl PhotoGroupOptions {
     pub fn new() -> PhotoGroupOptions {
-        PhotoGroupOptions{dpi: None, scanner: 0, hs: 0, oshs: 0, is_corrected: false, index_precision: 0 }
+        PhotoGroupOptions{dpi: None, scanner: 0, hs: 0, oshs: 0, is_corrected: false, index_precision: 0, require_upright: false, frame_check: None }
+    }
+}
+impl Default for PhotoGroupOptions {
+    fn default() -> Self {
+        Self::new()
     }
 }
\ No newline at end of file
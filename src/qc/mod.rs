@@ -1,3 +1,4 @@
 pub mod final_check;
+pub mod near_duplicates;
 
 // TODO: move this to a mod.rs
\ No newline at end of file
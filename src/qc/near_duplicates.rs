@@ -0,0 +1,144 @@
+use std::path::Path;
+use glob::glob;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::{reporter::Reporter, utils::{image_hash::{self, PerceptualHashType}, image_io, paths}};
+use super::final_check::media_file::MediaFile;
+
+pub mod error;
+use error::NearDuplicatesError;
+
+
+#[derive(Deserialize, Debug)]
+struct Data {
+    data: NearDuplicatesRequest
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NearDuplicatesRequest {
+    /// Fraction of matching hash bits, from 0.0 to 1.0, for two images to be considered near-duplicates.
+    #[serde(default = "default_similarity_threshold")]
+    pub similarity_threshold: f64,
+    #[serde(default = "default_hash_type")]
+    pub hash_type: PerceptualHashType,
+}
+fn default_similarity_threshold() -> f64 { 0.9 }
+fn default_hash_type() -> PerceptualHashType { PerceptualHashType::DHash }
+
+/// How many files are compared against the rest between progress messages.
+const COMPARISON_BATCH_SIZE: usize = 100;
+
+/// A group of files which all look alike, directly or through other members of the cluster.
+#[derive(Serialize, Debug)]
+pub struct NearDuplicateCluster {
+    pub file_names: Vec<String>,
+}
+
+
+/// Finds clusters of visually similar images anywhere under a directory.
+pub fn find_near_duplicates(dir: String, request_json: Value, reporter: &mut dyn Reporter) -> Result<Vec<NearDuplicateCluster>, NearDuplicatesError> {
+    let request = parse_near_duplicates_request(request_json)?;
+
+    let canonical_root = paths::canonical_dir(Path::new(&dir));
+    let files = glob(&format!("{dir}\\**\\*")).map_err(NearDuplicatesError::InvalidDirectory)?;
+    let mut hashed_files: Vec<(MediaFile, u64)> = Vec::new();
+    for entry in files {
+        let path = entry.map_err(NearDuplicatesError::InvalidFile)?;
+        if !image_io::is_image_file(&path) || !paths::resolves_inside(&path, &canonical_root) {
+            continue;
+        }
+        let media_file = MediaFile::from_path(&path).map_err(|e| NearDuplicatesError::MediaFileParseError(path.clone(), e))?;
+        if let Some(media_file) = media_file {
+            reporter.progress(&format!("Fingerprinting {}", media_file.raw_file_name));
+            let image = image::open(&path).map_err(|e| NearDuplicatesError::CouldNotDecodeImage(path, e))?;
+            hashed_files.push((media_file, image_hash::perceptual_hash(&image, request.hash_type)));
+        }
+    }
+
+    Ok(cluster(&hashed_files, request.similarity_threshold, reporter))
+}
+
+
+fn parse_near_duplicates_request(request_json: Value) -> Result<NearDuplicatesRequest, NearDuplicatesError> {
+    let data = serde_json::from_value::<Data>(request_json).map_err(NearDuplicatesError::DeserializeError)?;
+    let data = data.data;
+
+    if !(0.0..=1.0).contains(&data.similarity_threshold) {
+        return Err(NearDuplicatesError::InvalidRequest(format!("similarity threshold {} is not between 0 and 1", data.similarity_threshold)))
+    }
+
+    Ok(data)
+}
+
+/// Groups files whose hashes are at least `threshold` similar, using a union-find over every pair.
+fn cluster(hashed_files: &[(MediaFile, u64)], threshold: f64, reporter: &mut dyn Reporter) -> Vec<NearDuplicateCluster> {
+    fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        i
+    }
+
+    let mut parents: Vec<usize> = (0..hashed_files.len()).collect();
+    for i in 0..hashed_files.len() {
+        if i % COMPARISON_BATCH_SIZE == 0 {
+            let batch_end = (i + COMPARISON_BATCH_SIZE).min(hashed_files.len());
+            reporter.progress(&format!("Comparing files {}-{batch_end} of {} against the rest", i + 1, hashed_files.len()));
+        }
+        for j in i + 1..hashed_files.len() {
+            if image_hash::similarity(hashed_files[i].1, hashed_files[j].1) >= threshold {
+                let (root_i, root_j) = (find_root(&mut parents, i), find_root(&mut parents, j));
+                parents[root_j] = root_i;
+            }
+        }
+    }
+
+    let mut clusters: Vec<Vec<String>> = vec![Vec::new(); hashed_files.len()];
+    for (i, (media_file, _)) in hashed_files.iter().enumerate() {
+        let root = find_root(&mut parents, i);
+        clusters[root].push(media_file.raw_file_name.clone());
+    }
+
+    clusters.into_iter()
+        .filter(|file_names| file_names.len() > 1)
+        .map(|mut file_names| {
+            file_names.sort();
+            NearDuplicateCluster { file_names }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::{qc::final_check::media_file::MediaFile, reporter::SilentReporter, utils::test_files};
+    use super::{cluster, error::NearDuplicatesError, parse_near_duplicates_request};
+
+    #[test]
+    fn rejects_thresholds_outside_0_to_1() {
+        for threshold in [-0.1, 1.5] {
+            let result = parse_near_duplicates_request(json!({ "data": { "similarity_threshold": threshold } }));
+            assert!(matches!(result, Err(NearDuplicatesError::InvalidRequest(_))), "threshold {threshold} was accepted");
+        }
+        assert!(parse_near_duplicates_request(json!({ "data": { "similarity_threshold": 1.0 } })).is_ok());
+    }
+
+    #[test]
+    fn clusters_files_similar_through_another_file() {
+        let dir = test_files::temp_dir("near-duplicates-cluster");
+        let media_files: Vec<MediaFile> = (1..=4).map(|index| {
+            let path = dir.join(format!("SmithJ_Slides_{index:03}.jpg"));
+            test_files::write_scan(&path, &test_files::gradient(16, 16, [255, 255, 255]));
+            MediaFile::from_path(&path).unwrap().unwrap()
+        }).collect();
+        // 1 and 3 differ by two bits, but each differs from 2 by one
+        let hashes = [0b00, 0b01, 0b11, u64::MAX];
+        let hashed_files: Vec<(MediaFile, u64)> = media_files.into_iter().zip(hashes).collect();
+
+        let clusters = cluster(&hashed_files, 0.98, &mut SilentReporter);
+
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].file_names, ["SmithJ_Slides_001.jpg", "SmithJ_Slides_002.jpg", "SmithJ_Slides_003.jpg"]);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,26 @@
+use glob::{GlobError, PatternError};
+use crate::qc::final_check::media_file::error::MediaFileParseError;
+
+#[derive(Debug)]
+pub enum NearDuplicatesError {
+    DeserializeError(serde_json::Error),
+    InvalidRequest(String),
+    InvalidDirectory(PatternError),
+    InvalidFile(GlobError),
+    MediaFileParseError(std::path::PathBuf, MediaFileParseError),
+    CouldNotDecodeImage(std::path::PathBuf, image::ImageError),
+}
+impl std::error::Error for NearDuplicatesError {}
+impl std::fmt::Display for NearDuplicatesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter)
+    -> std::fmt::Result {
+        match self {
+            NearDuplicatesError::DeserializeError(err) => write!(f, "could not deserialize request: {err}"),
+            NearDuplicatesError::InvalidRequest(err) => write!(f, "invalid request: {err}"),
+            NearDuplicatesError::InvalidDirectory(err) => write!(f, "invalid directory: {err}"),
+            NearDuplicatesError::InvalidFile(err) => write!(f, "invalid file path: {err}"),
+            NearDuplicatesError::MediaFileParseError(path, e) => write!(f, "error parsing {}: {e}", path.file_name().unwrap_or(std::ffi::OsStr::new("invalid file name")).to_string_lossy()),
+            NearDuplicatesError::CouldNotDecodeImage(path, e) => write!(f, "could not decode {}: {e}", path.to_string_lossy()),
+        }
+    }
+}
\ No newline at end of file
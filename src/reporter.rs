@@ -0,0 +1,10 @@
+/// Receives progress messages while a job runs, such as the websocket forwarding them to its client.
+pub trait Reporter {
+    fn progress(&mut self, msg: &str);
+}
+
+/// Discards progress, for jobs run where nobody is watching.
+pub struct SilentReporter;
+impl Reporter for SilentReporter {
+    fn progress(&mut self, _msg: &str) {}
+}
\ No newline at end of file
@@ -0,0 +1,27 @@
+use std::{fs::File, io::{self, Read}, path::Path};
+use sha2::{Digest, Sha256, Sha512};
+
+/// Computes the SHA-256 digest of a file's contents as a lowercase hex string.
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    digest_file::<Sha256>(path)
+}
+
+/// Computes the SHA-512 digest of a file's contents as a lowercase hex string.
+pub fn sha512_file(path: &Path) -> io::Result<String> {
+    digest_file::<Sha512>(path)
+}
+
+fn digest_file<D: Digest>(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = D::new();
+    let mut buffer = vec![0u8; 1 << 16];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hasher.finalize().iter().map(|byte| format!("{byte:02x}")).collect())
+}
\ No newline at end of file
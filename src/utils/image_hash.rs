@@ -0,0 +1,98 @@
+use image::{imageops::FilterType, DynamicImage};
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum PerceptualHashType {
+    /// Difference hash; compares neighbouring pixels of a 9x8 thumbnail.
+    #[serde(rename = "dhash")]
+    DHash,
+    /// DCT hash; compares low frequencies of a 32x32 thumbnail against their median.
+    #[serde(rename = "phash")]
+    PHash,
+}
+
+/// Computes a 64 bit perceptual hash of an image. Visually similar images have hashes with few differing bits.
+pub fn perceptual_hash(image: &DynamicImage, hash_type: PerceptualHashType) -> u64 {
+    match hash_type {
+        PerceptualHashType::DHash => dhash(image),
+        PerceptualHashType::PHash => phash(image),
+    }
+}
+
+/// Fraction of bits two perceptual hashes have in common, from 0.0 to 1.0.
+pub fn similarity(hash_1: u64, hash_2: u64) -> f64 {
+    1.0 - f64::from((hash_1 ^ hash_2).count_ones()) / 64.0
+}
+
+fn dhash(image: &DynamicImage) -> u64 {
+    let thumbnail = image.resize_exact(9, 8, FilterType::Triangle).into_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if thumbnail.get_pixel(x, y)[0] < thumbnail.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    hash
+}
+
+fn phash(image: &DynamicImage) -> u64 {
+    const SIZE: usize = 32;
+    let thumbnail = image.resize_exact(SIZE as u32, SIZE as u32, FilterType::Triangle).into_luma8();
+    let pixels: Vec<f64> = thumbnail.pixels().map(|pixel| f64::from(pixel[0])).collect();
+
+    // Only the top left 8x8 block of the 2D DCT-II is needed
+    let cosines: Vec<f64> = (0..8 * SIZE)
+        .map(|i| {
+            let (u, x) = (i / SIZE, i % SIZE);
+            (std::f64::consts::PI * u as f64 * (2 * x + 1) as f64 / (2 * SIZE) as f64).cos()
+        })
+        .collect();
+    let mut rows = vec![0.0; SIZE * 8];
+    for y in 0..SIZE {
+        for u in 0..8 {
+            rows[y * 8 + u] = (0..SIZE).map(|x| pixels[y * SIZE + x] * cosines[u * SIZE + x]).sum();
+        }
+    }
+    let mut coefficients = [0.0; 64];
+    for v in 0..8 {
+        for u in 0..8 {
+            coefficients[v * 8 + u] = (0..SIZE).map(|y| rows[y * 8 + u] * cosines[v * SIZE + y]).sum();
+        }
+    }
+
+    // The DC coefficient only reflects overall brightness, so it's left out of the median
+    let mut sorted = coefficients[1..].to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = sorted[sorted.len() / 2];
+
+    coefficients.iter().fold(0u64, |hash, coefficient| (hash << 1) | u64::from(*coefficient > median))
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, GrayImage, Luma};
+    use super::{perceptual_hash, similarity, PerceptualHashType};
+
+    #[test]
+    fn similarity_counts_matching_bits() {
+        assert_eq!(similarity(0xF0F0, 0xF0F0), 1.0);
+        assert_eq!(similarity(0, u64::MAX), 0.0);
+        assert_eq!(similarity(0, 0xFF), 0.875);
+    }
+
+    #[test]
+    fn resized_copies_hash_alike_and_different_images_dont() {
+        let image = DynamicImage::ImageLuma8(GrayImage::from_fn(400, 300, |x, y| Luma([((f64::from(x) / 60.0).sin() * (f64::from(y) / 45.0).cos() * 127.0 + 128.0) as u8])));
+        let resized = image.thumbnail(200, 150);
+        let different = DynamicImage::ImageLuma8(GrayImage::from_fn(400, 300, |x, y| Luma([if (x / 50 + y / 50) % 2 == 0 { 0 } else { 255 }])));
+
+        for hash_type in [PerceptualHashType::DHash, PerceptualHashType::PHash] {
+            let hash = perceptual_hash(&image, hash_type);
+            assert!(similarity(hash, perceptual_hash(&resized, hash_type)) >= 0.9, "{hash_type:?} of a resized copy differs");
+            assert!(similarity(hash, perceptual_hash(&different, hash_type)) < 0.9, "{hash_type:?} of a different image matches");
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,109 @@
+pub mod error;
+use std::{fs::File, io::{BufWriter, Seek, Write}, path::Path, str::FromStr};
+use image::{codecs::jpeg::JpegEncoder, ColorType, DynamicImage, ImageFormat, ImageResult};
+use little_exif::exif_tag::ExifTag;
+use tiff::{encoder::{colortype::{self, ColorType as TiffColorType}, Rational, TiffEncoder, TiffValue}, tags::{ResolutionUnit, Tag}};
+use error::ImageIoError;
+use super::types::{file_extension_type::FileExtensionType, media_types::photo_media_data::ImageTags};
+
+/// Saves an image in the format implied by the path's extension.
+/// JPEGs are written at `jpeg_quality` instead of the encoder's low default, converting to 8 bits per channel if needed.
+pub fn save_image(image: &DynamicImage, path: &Path, jpeg_quality: u8) -> ImageResult<()> {
+    match ImageFormat::from_path(path)? {
+        ImageFormat::Jpeg => {
+            let mut writer = BufWriter::new(File::create(path)?);
+            let encoder = JpegEncoder::new_with_quality(&mut writer, jpeg_quality);
+            match image.color() {
+                ColorType::L8 | ColorType::L16 | ColorType::La8 | ColorType::La16 => image.to_luma8().write_with_encoder(encoder),
+                _ => image.to_rgb8().write_with_encoder(encoder),
+            }
+        }
+        _ => image.save(path),
+    }
+}
+
+/// Saves an image with the tags read from its source, which re-encoding would otherwise lose.
+/// TIFFs are written with the tags in their own header, since the image crate's encoder always writes a resolution of 1 dpi.
+/// Other formats get the tags as EXIF.
+pub fn save_image_with_tags(image: &DynamicImage, path: &Path, jpeg_quality: u8, tags: &ImageTags) -> Result<(), ImageIoError> {
+    match ImageFormat::from_path(path).map_err(ImageIoError::ImageError)? {
+        ImageFormat::Tiff => {
+            let mut writer = BufWriter::new(File::create(path).map_err(ImageIoError::CouldNotWrite)?);
+            save_tiff(image, &mut writer, tags)?;
+            writer.flush().map_err(ImageIoError::CouldNotWrite)
+        }
+        _ => {
+            save_image(image, path, jpeg_quality).map_err(ImageIoError::ImageError)?;
+            // little_exif always links an EXIF sub-IFD, which it can't read back if nothing is written into it
+            let mut metadata = tags.to_metadata();
+            metadata.set_tag(ExifTag::ExifImageWidth(vec![image.width()]));
+            metadata.set_tag(ExifTag::ExifImageHeight(vec![image.height()]));
+            metadata.write_to_file(path).map_err(ImageIoError::MetadataError)
+        }
+    }
+}
+
+/// Whether an image has more than 8 bits per channel, such as a 16 bit TIFF master.
+pub fn is_high_bit_depth(color: ColorType) -> bool {
+    matches!(color, ColorType::L16 | ColorType::La16 | ColorType::Rgb16 | ColorType::Rgba16 | ColorType::Rgb32F | ColorType::Rgba32F)
+}
+
+/// Whether a path is a file with one of the image extensions we deliver.
+pub fn is_image_file(path: &Path) -> bool {
+    path.is_file() && path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| FileExtensionType::from_str(&extension.to_lowercase()).is_ok())
+}
+
+/// Whether two existing folders are the same once `..` and symlinks are resolved. Folders that can't be
+/// resolved are compared as given.
+pub fn is_same_folder(a: &Path, b: &Path) -> bool {
+    match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Writes the same color types as the image crate's TIFF encoder, with grayscale and alpha widened to RGBA since TIFF has no such type.
+fn save_tiff<W: Write + Seek>(image: &DynamicImage, writer: &mut W, tags: &ImageTags) -> Result<(), ImageIoError> {
+    let mut encoder = TiffEncoder::new(writer).map_err(ImageIoError::TiffError)?;
+    let (width, height) = (image.width(), image.height());
+    match image {
+        DynamicImage::ImageLuma8(buffer) => write_tiff::<colortype::Gray8, _>(&mut encoder, width, height, buffer.as_raw(), tags),
+        DynamicImage::ImageRgb8(buffer) => write_tiff::<colortype::RGB8, _>(&mut encoder, width, height, buffer.as_raw(), tags),
+        DynamicImage::ImageRgba8(buffer) => write_tiff::<colortype::RGBA8, _>(&mut encoder, width, height, buffer.as_raw(), tags),
+        DynamicImage::ImageLumaA8(_) => write_tiff::<colortype::RGBA8, _>(&mut encoder, width, height, image.to_rgba8().as_raw(), tags),
+        DynamicImage::ImageLuma16(buffer) => write_tiff::<colortype::Gray16, _>(&mut encoder, width, height, buffer.as_raw(), tags),
+        DynamicImage::ImageRgb16(buffer) => write_tiff::<colortype::RGB16, _>(&mut encoder, width, height, buffer.as_raw(), tags),
+        DynamicImage::ImageRgba16(buffer) => write_tiff::<colortype::RGBA16, _>(&mut encoder, width, height, buffer.as_raw(), tags),
+        DynamicImage::ImageLumaA16(_) => write_tiff::<colortype::RGBA16, _>(&mut encoder, width, height, image.to_rgba16().as_raw(), tags),
+        DynamicImage::ImageRgb32F(buffer) => write_tiff::<colortype::RGB32Float, _>(&mut encoder, width, height, buffer.as_raw(), tags),
+        DynamicImage::ImageRgba32F(buffer) => write_tiff::<colortype::RGBA32Float, _>(&mut encoder, width, height, buffer.as_raw(), tags),
+        _ => Err(ImageIoError::UnsupportedColor(image.color())),
+    }
+}
+
+fn write_tiff<C: TiffColorType, W: Write + Seek>(encoder: &mut TiffEncoder<W>, width: u32, height: u32, data: &[C::Inner], tags: &ImageTags) -> Result<(), ImageIoError>
+where [C::Inner]: TiffValue {
+    let mut tiff_image = encoder.new_image::<C>(width, height).map_err(ImageIoError::TiffError)?;
+    if let Some((n, d)) = tags.x_resolution {
+        tiff_image.x_resolution(Rational { n, d });
+    }
+    if let Some((n, d)) = tags.y_resolution {
+        tiff_image.y_resolution(Rational { n, d });
+    }
+    if tags.x_resolution.is_some() || tags.y_resolution.is_some() {
+        tiff_image.resolution_unit(ResolutionUnit::Inch);
+    }
+    let directory = tiff_image.encoder();
+    if let Some(software) = &tags.software {
+        directory.write_tag(Tag::Software, software.as_str()).map_err(ImageIoError::TiffError)?;
+    }
+    if let Some(model) = &tags.model {
+        directory.write_tag(Tag::Model, model.as_str()).map_err(ImageIoError::TiffError)?;
+    }
+    if let Some(orientation) = tags.orientation {
+        directory.write_tag(Tag::Orientation, orientation).map_err(ImageIoError::TiffError)?;
+    }
+    tiff_image.write_data(data).map_err(ImageIoError::TiffError)
+}
\ No newline at end of file
@@ -0,0 +1,21 @@
+#[derive(Debug)]
+pub enum ImageIoError {
+    ImageError(image::ImageError),
+    TiffError(tiff::TiffError),
+    CouldNotWrite(std::io::Error),
+    MetadataError(std::io::Error),
+    UnsupportedColor(image::ColorType),
+}
+impl std::error::Error for ImageIoError {}
+impl std::fmt::Display for ImageIoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter)
+    -> std::fmt::Result {
+        match self {
+            ImageIoError::ImageError(e) => write!(f, "{e}"),
+            ImageIoError::TiffError(e) => write!(f, "could not encode TIFF: {e}"),
+            ImageIoError::CouldNotWrite(e) => write!(f, "could not write file: {e}"),
+            ImageIoError::MetadataError(e) => write!(f, "could not write metadata: {e}"),
+            ImageIoError::UnsupportedColor(color) => write!(f, "can't write a TIFF with color type {color:?}"),
+        }
+    }
+}
\ No newline at end of file
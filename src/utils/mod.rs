@@ -1,2 +1,9 @@
 pub mod types;
-pub mod send_text;
\ No newline at end of file
+pub mod hashing;
+pub mod image_hash;
+pub mod image_io;
+pub mod pdf;
+pub mod date;
+pub mod paths;
+#[cfg(test)]
+pub mod test_files;
\ No newline at end of file
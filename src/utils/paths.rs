@@ -0,0 +1,47 @@
+use std::path::{Component, Path, PathBuf};
+
+/// Resolves `..` and symlinks in a folder about to be walked, so walked paths can be checked with [`resolves_inside`].
+/// A folder that can't be resolved is returned as given.
+pub fn canonical_dir(dir: &Path) -> PathBuf {
+    std::fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf())
+}
+
+/// Resolves a folder that may not exist yet through its nearest existing parent, so it can be compared with resolved folders.
+pub fn canonical_new_dir(dir: &Path) -> PathBuf {
+    if let Ok(resolved) = std::fs::canonicalize(dir) {
+        return resolved;
+    }
+    let parent = match dir.parent() {
+        Some(parent) if parent.as_os_str().is_empty() => Path::new("."),
+        Some(parent) => parent,
+        None => return dir.to_path_buf(),
+    };
+    match dir.components().next_back() {
+        Some(Component::Normal(name)) => canonical_new_dir(parent).join(name),
+        Some(Component::ParentDir) => {
+            let resolved = canonical_new_dir(parent);
+            resolved.parent().map(Path::to_path_buf).unwrap_or(resolved)
+        }
+        _ => dir.to_path_buf(),
+    }
+}
+
+/// Whether a path found by walking a folder still resolves inside it. Globs follow symlinks, which could otherwise
+/// pull in files from anywhere on the machine.
+pub fn resolves_inside(path: &Path, canonical_dir: &Path) -> bool {
+    std::fs::canonicalize(path).is_ok_and(|resolved| resolved.starts_with(canonical_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::test_files;
+    use super::{canonical_dir, canonical_new_dir};
+
+    #[test]
+    fn resolves_folders_that_dont_exist_yet() {
+        let dir = canonical_dir(&test_files::temp_dir("paths-new-dir"));
+        assert_eq!(canonical_new_dir(&dir), dir);
+        assert_eq!(canonical_new_dir(&dir.join("a").join("b")), dir.join("a").join("b"));
+        assert_eq!(canonical_new_dir(&dir.join("a").join("..").join("b")), dir.join("b"));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,56 @@
+use std::{fs::File, io::{BufWriter, Write}, path::Path};
+use image::{codecs::jpeg::JpegEncoder, DynamicImage, ImageResult};
+
+/// Writes a PDF with one page per image, each page sized so the image prints at `dpi`.
+/// Pages are embedded as JPEGs at `jpeg_quality`, which keeps sheets of photos small.
+pub fn write_image_pdf(pages: &[DynamicImage], dpi: f64, jpeg_quality: u8, path: &Path) -> ImageResult<()> {
+    // Objects 1 and 2 are the catalog and page tree, then every page takes a page, content and image object
+    let page_ids: Vec<usize> = (0..pages.len()).map(|page| 3 + page * 3).collect();
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!("<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_ids.iter().map(|id| format!("{id} 0 R")).collect::<Vec<_>>().join(" "), pages.len()).into_bytes(),
+    ];
+
+    for (page, page_id) in pages.iter().zip(&page_ids) {
+        let (width, height) = (f64::from(page.width()) * 72.0 / dpi, f64::from(page.height()) * 72.0 / dpi);
+        let mut jpeg = Vec::new();
+        page.to_rgb8().write_with_encoder(JpegEncoder::new_with_quality(&mut jpeg, jpeg_quality))?;
+
+        objects.push(format!("<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {width:.2} {height:.2}] /Resources << /XObject << /Im0 {} 0 R >> >> /Contents {} 0 R >>",
+            page_id + 2, page_id + 1).into_bytes());
+        let content = format!("q {width:.2} 0 0 {height:.2} 0 0 cm /Im0 Do Q");
+        objects.push(format!("<< /Length {} >>\nstream\n{content}\nendstream", content.len()).into_bytes());
+        let mut image = format!("<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /DCTDecode /Length {} >>\nstream\n",
+            page.width(), page.height(), jpeg.len()).into_bytes();
+        image.extend_from_slice(&jpeg);
+        image.extend_from_slice(b"\nendstream");
+        objects.push(image);
+    }
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    let mut offsets = Vec::with_capacity(objects.len());
+    let mut position = 0;
+    let mut write = |writer: &mut BufWriter<File>, bytes: &[u8]| -> std::io::Result<usize> {
+        writer.write_all(bytes)?;
+        position += bytes.len();
+        Ok(position)
+    };
+    let mut offset = write(&mut writer, b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n")?;
+    for (id, object) in (1..).zip(&objects) {
+        offsets.push(offset);
+        write(&mut writer, format!("{id} 0 obj\n").as_bytes())?;
+        write(&mut writer, object)?;
+        offset = write(&mut writer, b"\nendobj\n")?;
+    }
+
+    let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        xref.push_str(&format!("{offset:010} 00000 n \n"));
+    }
+    xref.push_str(&format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{offset}\n%%EOF\n", objects.len() + 1));
+    write(&mut writer, xref.as_bytes())?;
+    writer.flush()?;
+
+    Ok(())
+}
\ No newline at end of file
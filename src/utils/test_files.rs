@@ -0,0 +1,31 @@
+use std::path::{Path, PathBuf};
+use image::{DynamicImage, Rgb, RgbImage};
+use crate::utils::{image_io, types::media_types::photo_media_data::ImageTags};
+
+/// Creates an empty folder for one test under the system temp folder, replacing any left by an earlier run.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("mw-files-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Writes an image at 300 DPI, as scans are delivered, so it parses as a media file.
+pub fn write_scan(path: &Path, image: &DynamicImage) {
+    write_scan_with_tags(path, image, ImageTags::default());
+}
+
+/// Writes an image with `tags`, filling in a 300 DPI resolution if it has none.
+pub fn write_scan_with_tags(path: &Path, image: &DynamicImage, mut tags: ImageTags) {
+    tags.x_resolution = tags.x_resolution.or(Some((300, 1)));
+    tags.y_resolution = tags.y_resolution.or(Some((300, 1)));
+    image_io::save_image_with_tags(image, path, 95, &tags).unwrap();
+}
+
+/// A horizontal gradient from black to `color`, which gives perceptual hashes and edge detection something to find.
+pub fn gradient(width: u32, height: u32, color: [u8; 3]) -> DynamicImage {
+    DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, _| {
+        let scale = |channel: u8| (u32::from(channel) * x / width.max(1)) as u8;
+        Rgb([scale(color[0]), scale(color[1]), scale(color[2])])
+    }))
+}
\ No newline at end of file
@@ -16,12 +16,12 @@ impl FromStr for FileExtensionType {
         }
     }
 }
-impl ToString for FileExtensionType {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for FileExtensionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            FileExtensionType::None => "".to_string(),
-            FileExtensionType::Jpeg => "jpg".to_string(),
-            FileExtensionType::Tiff => "tif".to_string()
+            FileExtensionType::None => write!(f, ""),
+            FileExtensionType::Jpeg => write!(f, "jpg"),
+            FileExtensionType::Tiff => write!(f, "tif")
         }
     }
 }
\ No newline at end of file
@@ -9,22 +9,22 @@ pub enum MediaType {
     Slides(PhotoMediaData),
     Negatives(PhotoMediaData)
 }
-impl ToString for MediaType {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for MediaType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            MediaType::Prints(_) => "Prints".to_string(),
-            MediaType::Slides(_) => "Slides".to_string(),
-            MediaType::Negatives(_) => "Negatives".to_string(),
+            MediaType::Prints(_) => write!(f, "Prints"),
+            MediaType::Slides(_) => write!(f, "Slides"),
+            MediaType::Negatives(_) => write!(f, "Negatives"),
         }
     }
 }
 impl MediaType {
     /// Creates a MediaType with its respective MediaData from the media type as a string and a file path.
-    pub fn from_path(word: &str, path: &std::path::PathBuf) -> Result<MediaType, MediaTypeError> {
+    pub fn from_path(word: &str, path: &std::path::Path) -> Result<MediaType, MediaTypeError> {
         match word {
-            "Prints" => Ok(MediaType::Prints(PhotoMediaData::from_path(path).map_err(|e| MediaTypeError::PhotoMediaDataError(e))?)),
-            "Slides" => Ok(MediaType::Slides(PhotoMediaData::from_path(path).map_err(|e| MediaTypeError::PhotoMediaDataError(e))?)),
-            "Negs" => Ok(MediaType::Negatives(PhotoMediaData::from_path(path).map_err(|e| MediaTypeError::PhotoMediaDataError(e))?)),
+            "Prints" => Ok(MediaType::Prints(PhotoMediaData::from_path(path).map_err(MediaTypeError::PhotoMediaDataError)?)),
+            "Slides" => Ok(MediaType::Slides(PhotoMediaData::from_path(path).map_err(MediaTypeError::PhotoMediaDataError)?)),
+            "Negs" => Ok(MediaType::Negatives(PhotoMediaData::from_path(path).map_err(MediaTypeError::PhotoMediaDataError)?)),
             _ => Err(MediaTypeError::UnrecognizedMediaType(word.to_string()))
         }
     }
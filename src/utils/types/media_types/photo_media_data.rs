@@ -1,7 +1,8 @@
 pub mod error;
-use std::{panic, path::PathBuf};
+use std::{fs::File, io::BufReader, panic, path::Path};
 use error::PhotoMediaDataError;
-use little_exif::{endian::Endian, metadata::Metadata};
+use little_exif::{endian::Endian, exif_tag::ExifTag, metadata::Metadata};
+use tiff::{decoder::{ifd::Value, Decoder}, tags::Tag};
 use crate::utils::types::scan_type::ScanType;
 
 #[derive(Debug, Clone, Copy)]
@@ -9,22 +10,21 @@ pub struct  PhotoMediaData {
     pub dpi: u64,
     pub is_corrected: bool,
     pub true_scan_type: Option<ScanType>,
+    /// EXIF orientation flag, where 1 means the pixels are already upright.
+    pub orientation: u16,
 }
 impl PhotoMediaData {   
-    pub fn from_path(path: &PathBuf) -> Result<PhotoMediaData, PhotoMediaDataError> {
-        fn get_dpi(metadata: &Metadata, hex_code: u16) -> Result<u32, PhotoMediaDataError> {
-            let dpi = metadata.get_tag_by_hex(hex_code).ok_or(PhotoMediaDataError::NoDpiFound)?.value_as_u8_vec(&Endian::Little);
-            let first_u32 = u32::from_le_bytes(dpi[0..4].try_into().map_err(|_| PhotoMediaDataError::BadlyFormattedExifTag("dpi".to_string()))?);
-            let second_u32 = u32::from_le_bytes(dpi[4..8].try_into().map_err(|_| PhotoMediaDataError::BadlyFormattedExifTag("dpi".to_string()))?);
-            return Ok(first_u32 / second_u32);
+    pub fn from_path(path: &Path) -> Result<PhotoMediaData, PhotoMediaDataError> {
+        fn get_dpi(resolution: Option<(u32, u32)>) -> Result<u32, PhotoMediaDataError> {
+            let (numerator, denominator) = resolution.ok_or(PhotoMediaDataError::NoDpiFound)?;
+            numerator.checked_div(denominator).ok_or(PhotoMediaDataError::BadlyFormattedExifTag("dpi".to_string()))
         }
 
-        let metadata = panic::catch_unwind(|| Metadata::new_from_path(&path))
-            .map_err(|_| PhotoMediaDataError::CouldNotReadPath(path.clone()))?.map_err(|_| PhotoMediaDataError::CouldNotReadPath(path.clone()))?;
+        let tags = ImageTags::from_path(path)?;
 
         // Get DPI
-        let horiz_dpi = get_dpi(&metadata, 0x011a)?;
-        let vert_dpi = get_dpi(&metadata, 0x011b)?;
+        let horiz_dpi = get_dpi(tags.x_resolution)?;
+        let vert_dpi = get_dpi(tags.y_resolution)?;
         if horiz_dpi != vert_dpi {
             return Err(PhotoMediaDataError::DifferentXYDpi(horiz_dpi, vert_dpi));
         }
@@ -32,24 +32,134 @@ impl PhotoMediaData {
 
         // Get software if used
         let mut is_corrected = false;
-        if let Some(tag) = metadata.get_tag_by_hex(0x0131) {
-            let software = &*String::from_utf8_lossy(&tag.value_as_u8_vec(&Endian::Little)).to_ascii_lowercase();
-            if software.contains("photoshop") {
+        if let Some(software) = &tags.software {
+            if software.to_ascii_lowercase().contains("photoshop") {
                 is_corrected = true;
             }
-            println!("{is_corrected}");
         }
 
         // Get hardware used to capture
         let mut true_scan_type = None;
-        if let Some(tag) = metadata.get_tag_by_hex(0x0110) {
-            let hardware = &*String::from_utf8_lossy(&tag.value_as_u8_vec(&Endian::Little)).to_ascii_lowercase();
-            if hardware.contains("powerslide") {
+        if let Some(hardware) = &tags.model {
+            if hardware.to_ascii_lowercase().contains("powerslide") {
                 true_scan_type = Some(ScanType::Default);
             }
         }
         
+        // Orientation defaults to upright when missing
+        let orientation = tags.orientation.unwrap_or(1);
+        
         // Todo: Set is_corrected and scan_type correctly
-        Ok(PhotoMediaData{dpi, is_corrected, true_scan_type })
+        Ok(PhotoMediaData{dpi, is_corrected, true_scan_type, orientation })
+    }
+}
+
+/// The header tags we read from scans, from either a TIFF's own tags or a JPEG's EXIF block.
+#[derive(Debug, Clone, Default)]
+pub struct ImageTags {
+    pub x_resolution: Option<(u32, u32)>,
+    pub y_resolution: Option<(u32, u32)>,
+    pub software: Option<String>,
+    pub model: Option<String>,
+    pub orientation: Option<u16>,
+}
+impl ImageTags {
+    pub fn from_path(path: &Path) -> Result<ImageTags, PhotoMediaDataError> {
+        let is_tiff = path.extension().is_some_and(|extension| matches!(&*extension.to_string_lossy().to_lowercase(), "tif" | "tiff"));
+        match is_tiff {
+            true => Self::from_tiff(path),
+            false => Self::from_exif(path),
+        }
+    }
+
+    /// Builds EXIF metadata holding these tags, for writing into a file that has none.
+    pub fn to_metadata(&self) -> Metadata {
+        let mut metadata = Metadata::new();
+        if let Some((numerator, denominator)) = self.x_resolution {
+            metadata.set_tag(ExifTag::XResolution(vec![numerator, denominator]));
+        }
+        if let Some((numerator, denominator)) = self.y_resolution {
+            metadata.set_tag(ExifTag::YResolution(vec![numerator, denominator]));
+        }
+        if self.x_resolution.is_some() || self.y_resolution.is_some() {
+            // Inches
+            metadata.set_tag(ExifTag::ResolutionUnit(vec![2]));
+        }
+        if let Some(software) = &self.software {
+            metadata.set_tag(ExifTag::Software(software.clone()));
+        }
+        if let Some(model) = &self.model {
+            metadata.set_tag(ExifTag::Model(model.clone()));
+        }
+        if let Some(orientation) = self.orientation {
+            metadata.set_tag(ExifTag::Orientation(vec![orientation]));
+        }
+        metadata
+    }
+
+    fn from_exif(path: &Path) -> Result<ImageTags, PhotoMediaDataError> {
+        fn get_rational(metadata: &Metadata, hex_code: u16) -> Result<Option<(u32, u32)>, PhotoMediaDataError> {
+            let Some(tag) = metadata.get_tag_by_hex(hex_code) else {
+                return Ok(None);
+            };
+            let value = tag.value_as_u8_vec(&Endian::Little);
+            let first_u32 = u32::from_le_bytes(value.get(0..4).and_then(|bytes| bytes.try_into().ok()).ok_or(PhotoMediaDataError::BadlyFormattedExifTag("dpi".to_string()))?);
+            let second_u32 = u32::from_le_bytes(value.get(4..8).and_then(|bytes| bytes.try_into().ok()).ok_or(PhotoMediaDataError::BadlyFormattedExifTag("dpi".to_string()))?);
+            Ok(Some((first_u32, second_u32)))
+        }
+        fn get_string(metadata: &Metadata, hex_code: u16) -> Option<String> {
+            let tag = metadata.get_tag_by_hex(hex_code)?;
+            Some(String::from_utf8_lossy(&tag.value_as_u8_vec(&Endian::Little)).trim_end_matches('\0').to_string())
+        }
+
+        let metadata = panic::catch_unwind(|| Metadata::new_from_path(path))
+            .map_err(|_| PhotoMediaDataError::CouldNotReadPath(path.to_path_buf()))?.map_err(|_| PhotoMediaDataError::CouldNotReadPath(path.to_path_buf()))?;
+
+        let mut orientation = None;
+        if let Some(tag) = metadata.get_tag_by_hex(0x0112) {
+            let value = tag.value_as_u8_vec(&Endian::Little);
+            orientation = Some(u16::from_le_bytes(value.get(0..2).and_then(|bytes| bytes.try_into().ok()).ok_or(PhotoMediaDataError::BadlyFormattedExifTag("orientation".to_string()))?));
+        }
+
+        Ok(ImageTags {
+            x_resolution: get_rational(&metadata, 0x011a)?,
+            y_resolution: get_rational(&metadata, 0x011b)?,
+            software: get_string(&metadata, 0x0131),
+            model: get_string(&metadata, 0x0110),
+            orientation,
+        })
+    }
+
+    fn from_tiff(path: &Path) -> Result<ImageTags, PhotoMediaDataError> {
+        fn get_rational(decoder: &mut Decoder<BufReader<File>>, tag: Tag) -> Result<Option<(u32, u32)>, PhotoMediaDataError> {
+            match decoder.find_tag(tag) {
+                Ok(Some(Value::Rational(numerator, denominator))) => Ok(Some((numerator, denominator))),
+                Ok(Some(Value::List(values))) => match values.first() {
+                    Some(Value::Rational(numerator, denominator)) => Ok(Some((*numerator, *denominator))),
+                    _ => Err(PhotoMediaDataError::BadlyFormattedExifTag("dpi".to_string())),
+                },
+                Ok(None) => Ok(None),
+                _ => Err(PhotoMediaDataError::BadlyFormattedExifTag("dpi".to_string())),
+            }
+        }
+        fn get_string(decoder: &mut Decoder<BufReader<File>>, tag: Tag) -> Option<String> {
+            decoder.find_tag(tag).ok().flatten()?.into_string().ok()
+        }
+
+        let file = File::open(path).map_err(|_| PhotoMediaDataError::CouldNotReadPath(path.to_path_buf()))?;
+        let mut decoder = Decoder::new(BufReader::new(file)).map_err(|_| PhotoMediaDataError::CouldNotReadPath(path.to_path_buf()))?;
+
+        let orientation = match decoder.find_tag(Tag::Orientation) {
+            Ok(Some(value)) => Some(value.into_u16().map_err(|_| PhotoMediaDataError::BadlyFormattedExifTag("orientation".to_string()))?),
+            _ => None,
+        };
+
+        Ok(ImageTags {
+            x_resolution: get_rational(&mut decoder, Tag::XResolution)?,
+            y_resolution: get_rational(&mut decoder, Tag::YResolution)?,
+            software: get_string(&mut decoder, Tag::Software),
+            model: get_string(&mut decoder, Tag::Model),
+            orientation,
+        })
     }
 }
\ No newline at end of file
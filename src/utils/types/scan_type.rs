@@ -17,12 +17,12 @@ impl FromStr for ScanType {
         }
     }
 }
-impl ToString for ScanType {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for ScanType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            ScanType::Default => "Default".to_string(),
-            ScanType::HandScan => "Hand Scan".to_string(),
-            ScanType::OversizedHandScan => "Oversized Hand Scan".to_string(),
+            ScanType::Default => write!(f, "Default"),
+            ScanType::HandScan => write!(f, "Hand Scan"),
+            ScanType::OversizedHandScan => write!(f, "Oversized Hand Scan"),
         }
     }
 }
\ No newline at end of file